@@ -0,0 +1,3125 @@
+use super::cli::{Cli, Command, ExportFormat, Hosts, Import, ListFormat, Tls};
+use super::{
+    backup, browser, caddy, cli, compose, config, crash, daemon, diff, dnsmasq, doctor, editor,
+    engines, error, get_default_store_path, git, health, hosts, http_api, import, init, install,
+    journal, logging, notification, outdated, pm2, port, process, project, project_config, prompt,
+    proxy, qr, run_history, scan, search, server, server_store, share, shell, store, supervisor,
+    sync, tls, top, tui, vscode, workspace,
+};
+use config::Config;
+use daemon::{Daemon, DaemonClient, DaemonPaths};
+use error::{ApplicationError, ErrorCategory};
+use health::HealthCheck;
+use journal::Journal;
+use port::PortChoice;
+use process::RuntimeState;
+use project::Project;
+use project_config::ProjectConfig;
+use server::{RestartPolicy, Server, DEFAULT_INSPECT_PORT};
+use server_store::ServerStore;
+use share::TunnelProvider;
+
+use clap::{CommandFactory, StructOpt};
+use colored::*;
+use ngrammatic::CorpusBuilder;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryInto;
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+use tracing_subscriber::filter::LevelFilter;
+
+// The server store file provided on the command line, which overrides the default location
+static STORE_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+// Return the path to the server store file
+fn get_store_path() -> Result<PathBuf, ApplicationError> {
+    if let Some(store_path) = STORE_PATH.get() {
+        return Ok(store_path.clone());
+    }
+    get_default_store_path()
+}
+
+// Return the path to the config file, which lives next to the server store file
+fn get_config_path() -> Result<PathBuf, ApplicationError> {
+    Ok(get_store_path()?.with_file_name("config.toml"))
+}
+
+// Return the path to the runtime state file, which lives next to the server store file
+fn get_runtime_state_path() -> Result<PathBuf, ApplicationError> {
+    Ok(get_store_path()?.with_file_name("runtime.toml"))
+}
+
+// Return the path to the history of how servers exited, which lives next to the server store file
+fn get_history_path() -> Result<PathBuf, ApplicationError> {
+    Ok(get_store_path()?.with_file_name("history.toml"))
+}
+
+// Return the path to the directory containing the servers' log files
+fn get_logs_dir() -> Result<PathBuf, ApplicationError> {
+    Ok(get_store_path()?.with_file_name("logs"))
+}
+
+// Return the path to the daemon's control socket
+fn get_socket_path() -> Result<PathBuf, ApplicationError> {
+    Ok(get_store_path()?.with_file_name("daemon.sock"))
+}
+
+// Connect to the daemon if it is running
+fn connect_daemon() -> Result<Option<DaemonClient>, ApplicationError> {
+    Ok(DaemonClient::connect(&get_socket_path()?))
+}
+
+// Print diagnostic logs to stderr so that they stay separate from the output of commands
+fn init_logging(verbosity: u8, quiet: bool) {
+    let level = if quiet {
+        LevelFilter::OFF
+    } else {
+        match verbosity {
+            0 => LevelFilter::WARN,
+            1 => LevelFilter::INFO,
+            2 => LevelFilter::DEBUG,
+            _ => LevelFilter::TRACE,
+        }
+    };
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(io::stderr)
+        .with_ansi(io::stderr().is_terminal())
+        .with_target(false)
+        .init();
+}
+
+fn run() -> Result<(), ApplicationError> {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        // Help and version output exit successfully, but usage errors are validation errors
+        Err(err) if err.use_stderr() => {
+            let _ = err.print();
+            std::process::exit(ErrorCategory::Validation.get_exit_code());
+        }
+        Err(err) => err.exit(),
+    };
+    init_logging(cli.verbosity, cli.quiet);
+    if let Some(store_path) = cli.store {
+        // Resolve relative paths now because background servers and the daemon run elsewhere
+        let _ = STORE_PATH.set(std::path::absolute(&store_path).unwrap_or(store_path));
+    }
+    // Ignore a malformed config here so that `config set` can still fix it
+    let config = load_config().unwrap_or_default();
+    if cli.no_input || config.no_input == Some(true) {
+        prompt::disable_input();
+    }
+    if cli.yes {
+        prompt::assume_yes();
+    }
+    if let Some(shell) = config.shell {
+        shell::set_default(shell);
+    }
+    notification::set_enabled(config.notifications.unwrap_or(true));
+    git::set_enabled(config.git_status.unwrap_or(true));
+    let modifies_store = matches!(
+        cli.command,
+        Command::Add { .. }
+            | Command::Adopt
+            | Command::Scan { .. }
+            | Command::Import { .. }
+            | Command::Restore { .. }
+            | Command::Edit { .. }
+            | Command::Remove { .. }
+            | Command::Clone { .. }
+            | Command::Archive { .. }
+            | Command::Unarchive { .. }
+            | Command::Pin { .. }
+            | Command::Unpin { .. }
+            | Command::Prune { .. }
+            | Command::Undo
+            | Command::Sync { .. }
+    );
+    backup::set_pending(modifies_store || matches!(cli.command, Command::Tls(_)));
+    // Remember the servers that destructive operations change so that they can be undone
+    let journal_operation = match &cli.command {
+        Command::Edit { .. } => Some("edit"),
+        Command::Remove { .. } => Some("remove"),
+        Command::Prune { .. } => Some("prune"),
+        _ => None,
+    };
+    let old_store = match journal_operation {
+        Some(_) => Some(load_store()?),
+        None => None,
+    };
+    run_command(cli.command)?;
+    if let (Some(operation), Some(old_store)) = (journal_operation, old_store) {
+        load_journal()?.record(operation, &old_store, &load_store()?)?;
+    }
+    if cli.sync_caddy && modifies_store {
+        sync_caddy(None, None)?;
+    }
+    Ok(())
+}
+
+// Write the Caddyfile to the provided or configured location and reload Caddy
+fn sync_caddy(tag: Option<&str>, output: Option<PathBuf>) -> Result<(), ApplicationError> {
+    let config = load_config()?;
+    let caddyfile = output
+        .or_else(|| config.get_caddyfile())
+        .ok_or(ApplicationError::NoCaddyfile)?;
+    let server_store = load_store()?;
+    caddy::write_caddyfile(
+        &server_store.get_enabled_with_tag(tag),
+        &config.get_domain_suffix(),
+        &caddyfile,
+    )?;
+    caddy::reload(&caddyfile, config.caddy_reload_command.as_deref())?;
+    println!(
+        "Reloaded Caddy with {}",
+        caddyfile.display().to_string().bold()
+    );
+    Ok(())
+}
+
+fn run_command(command: Command) -> Result<(), ApplicationError> {
+    match command {
+        Command::Config { action: None } => {
+            println!("Server store path: {:?}", get_store_path()?);
+            println!("Config path: {:?}", get_config_path()?);
+            let config = load_config()?;
+            match config.get_servers_dir() {
+                Some(servers_dir) => println!("Servers directory: {:?}", servers_dir),
+                None => println!("Servers directory: not configured"),
+            }
+            if let Some(path_root) = config.get_path_root() {
+                println!("Path root: {:?}", path_root);
+            }
+            println!("Domain suffix: {}", config.get_domain_suffix());
+            println!("Package manager: {}", config.get_package_manager());
+            println!(
+                "Shell: {}",
+                config.shell.unwrap_or_else(shell::Shell::get_default)
+            );
+            println!(
+                "Notifications: {}",
+                if config.notifications.unwrap_or(true) {
+                    "enabled"
+                } else {
+                    "disabled"
+                }
+            );
+            println!(
+                "Git status: {}",
+                if config.git_status.unwrap_or(true) {
+                    "enabled"
+                } else {
+                    "disabled"
+                }
+            );
+            Ok(())
+        }
+
+        Command::Config {
+            action: Some(cli::Config::Get { key }),
+        } => {
+            if let Some(value) = Config::get_value(&get_config_path()?, &key)? {
+                println!("{}", value);
+            }
+            Ok(())
+        }
+
+        Command::Config {
+            action: Some(cli::Config::Set { key, value }),
+        } => set_config_value(&key, Some(&value)),
+
+        Command::Config {
+            action: Some(cli::Config::Unset { key }),
+        } => set_config_value(&key, None),
+
+        Command::Add {
+            path,
+            name,
+            start_script,
+            command,
+            procfile,
+            port,
+            strict,
+        } => {
+            let server_store = load_store()?;
+            let path = path.unwrap_or_else(|| PathBuf::from("."));
+            let absolute_path =
+                fs::canonicalize(path.clone()).map_err(|_| ApplicationError::ParsePath(path))?;
+            let project_config = ProjectConfig::load(&absolute_path)?;
+            let mut project =
+                Project::from_path(absolute_path, load_config()?.get_package_manager())?;
+
+            // Change the default name if one is provided, preferring the command line
+            if let Some(name) = name.or_else(|| project_config.name.clone()) {
+                project.name = name;
+            }
+
+            let port_range = load_config()?.get_port_range();
+            // Offer to add each package of a monorepo instead of its root
+            if !procfile && start_script.is_none() && command.is_none() {
+                let packages = workspace::find_packages(&project)
+                    .into_iter()
+                    .filter(|package| server_store.validate_new_project(package).is_ok())
+                    .collect::<Vec<_>>();
+                let packages = if packages.is_empty() {
+                    packages
+                } else {
+                    prompt::choose_packages(
+                        packages,
+                        "Which workspace packages do you want to add?",
+                    )?
+                };
+                if !packages.is_empty() {
+                    return add_packages(&server_store, packages, port, strict);
+                }
+            }
+
+            // Abort if the project is invalid before prompting the user for the start command
+            server_store.validate_new_project(&project)?;
+            check_node_version(&project, strict)?;
+
+            if procfile {
+                let processes =
+                    prompt::choose_processes(&project, "Which processes do you want to add?")?;
+                let free_ports = server_store.get_free_ports(port_range, processes.len());
+                let mut servers = vec![];
+                for (index, process) in processes.into_iter().enumerate() {
+                    let name = format!("{}-{}", project.name, process.name);
+                    // Use consecutive ports starting at the provided port
+                    let port = match port {
+                        Some(PortChoice::Fixed(port)) => index
+                            .try_into()
+                            .ok()
+                            .and_then(|index| port.checked_add(index))
+                            .ok_or(ApplicationError::NoFreePort)?,
+                        _ => prompt::choose_port(
+                            port,
+                            free_ports.get(index).copied(),
+                            format!("What port does {} listen on?", name).as_str(),
+                        )?,
+                    };
+                    let existing = server_store
+                        .get_all()
+                        .into_iter()
+                        .chain(servers.iter())
+                        .find(|server| server.port == port);
+                    if let Some(existing) = existing {
+                        return Err(ApplicationError::DuplicateServerPort {
+                            port,
+                            existing: existing.name.clone(),
+                        });
+                    }
+                    servers.push(Server {
+                        name,
+                        ..Server::from_project(project.clone(), process.start_command, port)
+                    });
+                }
+                return server_store.add_servers(servers);
+            }
+
+            // A start script on the command line overrides the project config's start command
+            let command = match start_script {
+                Some(_) => command,
+                None => command.or_else(|| project_config.start_command.clone()),
+            };
+            let start_command = match command {
+                Some(command) => command,
+                None => prompt::choose_start_command(
+                    &project,
+                    start_script,
+                    "Which script starts the server?",
+                )?,
+            };
+            let auto_port = server_store.get_free_ports(port_range, 1).first().copied();
+            let port = prompt::choose_port(
+                port.or(project_config.port.map(PortChoice::Fixed)),
+                auto_port,
+                "What port does the server listen on?",
+            )?;
+            server_store.add_servers(vec![project_config.apply(Server::from_project(
+                project,
+                start_command,
+                port,
+            ))])
+        }
+
+        Command::Adopt => {
+            let server_store = load_store()?;
+            let dir = env::current_dir().map_err(|_| ApplicationError::ParsePath(".".into()))?;
+            let project_config = ProjectConfig::load(&dir)?;
+            let mut project = Project::from_path(dir, load_config()?.get_package_manager())?;
+            if let Some(name) = project_config.name.clone() {
+                project.name = name;
+            }
+            server_store.validate_new_project(&project)?;
+
+            let start_command = match project_config.start_command.clone() {
+                Some(start_command) => start_command,
+                None => project.get_start_command(&project.get_default_start_script()?),
+            };
+            let port = match project_config.port {
+                Some(port) => port,
+                None => server_store
+                    .get_free_ports(load_config()?.get_port_range(), 1)
+                    .first()
+                    .copied()
+                    .ok_or(ApplicationError::NoFreePort)?,
+            };
+            let server = project_config.apply(Server::from_project(project, start_command, port));
+            println!(
+                "Adding {} ({}) on port {}",
+                server.name.bold().green(),
+                server.start_command.cyan(),
+                server.port.to_string().yellow()
+            );
+            server_store.add_servers(vec![server])
+        }
+
+        Command::Scan { dir } => {
+            let server_store = load_store()?;
+            let scan_dir = match dir {
+                Some(dir) => dir,
+                None => load_config()?
+                    .get_servers_dir()
+                    .ok_or(ApplicationError::NoServersDir)?,
+            };
+
+            // Skip projects that are already registered or whose names are taken
+            let mut names = HashSet::new();
+            let projects = scan::find_projects(&scan_dir, load_config()?.get_package_manager())
+                .into_iter()
+                .filter(|project| {
+                    server_store.validate_new_project(project).is_ok()
+                        && names.insert(project.name.clone())
+                })
+                .collect::<Vec<_>>();
+            if projects.is_empty() {
+                println!("No new projects found in {:?}", scan_dir);
+                return Ok(());
+            }
+
+            // --yes adds every new project without prompting
+            let projects = prompt::choose_projects(projects, "Which projects do you want to add?")?;
+            let ports =
+                server_store.get_free_ports(load_config()?.get_port_range(), projects.len());
+            if ports.len() < projects.len() {
+                return Err(ApplicationError::NoFreePort);
+            }
+            let mut servers = vec![];
+            for (project, port) in projects.into_iter().zip(ports) {
+                let start_script = match project.get_default_start_script() {
+                    Ok(start_script) => start_script,
+                    Err(err) => {
+                        eprintln!("{}: skipping {}: {}", "Warning".bold().yellow(), project, err);
+                        continue;
+                    }
+                };
+                let start_command = project.get_start_command(&start_script);
+                println!(
+                    "Adding {} ({}) on port {}",
+                    project.name.bold().green(),
+                    start_command.cyan(),
+                    port.to_string().yellow()
+                );
+                servers.push(Server::from_project(project, start_command, port));
+            }
+            server_store.add_servers(servers)
+        }
+
+        Command::Export { format } => {
+            let server_store = load_store()?;
+            let mut servers = server_store.get_all();
+            servers.sort_by_key(|server| server.name.as_str());
+            // Wrap the servers like the store file does so that exports can be imported again
+            let raw_store = HashMap::from([("servers", servers)]);
+            match format {
+                ExportFormat::Toml => {
+                    let stringified = toml::to_string_pretty(&raw_store)
+                        .map_err(|_| ApplicationError::StringifyStore)?;
+                    print!("{}", stringified);
+                }
+                ExportFormat::Json => {
+                    let stringified = serde_json::to_string_pretty(&raw_store)
+                        .map_err(|_| ApplicationError::StringifyStore)?;
+                    println!("{}", stringified);
+                }
+            }
+            Ok(())
+        }
+
+        Command::Import {
+            source,
+            path,
+            merge: _,
+            replace,
+            force,
+        } => match source {
+            Some(Import::Pm2 { path }) => {
+                let server_store = load_store()?;
+                let servers = pm2::read_apps(&path)?;
+                import::add_servers(&server_store, servers, load_config()?.get_port_range())?;
+                Ok(())
+            }
+            Some(Import::Compose { path }) => {
+                let server_store = load_store()?;
+                let servers = compose::read_services(&path)?;
+                import::add_servers(&server_store, servers, load_config()?.get_port_range())?;
+                Ok(())
+            }
+            Some(Import::Caddy { path }) => {
+                let server_store = load_store()?;
+                let config = load_config()?;
+                let servers = caddy::read_sites(&path, &config.get_domain_suffix())?
+                    .into_iter()
+                    .map(|site| {
+                        Ok(import::ImportedServer {
+                            start_command: prompt::choose_imported_start_command(&site.name)?,
+                            ..site
+                        })
+                    })
+                    .collect::<Result<Vec<_>, ApplicationError>>()?;
+                import::add_servers(&server_store, servers, config.get_port_range())?;
+                Ok(())
+            }
+            // clap requires the path when there isn't a subcommand
+            None => {
+                let server_store = load_store()?;
+                let servers = ServerStore::read_export(&path.unwrap_or_default())?;
+                if !replace {
+                    return import::merge_servers(&server_store, servers);
+                }
+                if prompt::confirm(
+                    force,
+                    "Are you sure you want to replace all of the existing servers?",
+                )? {
+                    let removed = import::replace_servers(&server_store, servers)?;
+                    remove_host_entries(&removed.iter().collect::<Vec<_>>())?;
+                }
+                Ok(())
+            }
+        },
+
+        Command::Restore {
+            backup,
+            list,
+            force,
+        } => {
+            let store_path = get_store_path()?;
+            let backups = backup::list(&store_path);
+            if list {
+                if backups.is_empty() {
+                    println!("No backups");
+                }
+                for backup in backups {
+                    let server_count = ServerStore::load(backup.path.clone())
+                        .map(|server_store| server_store.get_all().len().to_string())
+                        .unwrap_or_else(|_| "?".to_string());
+                    println!(
+                        "{} {} ({} servers)",
+                        backup.id.bold().green(),
+                        backup.created_at.format("%Y-%m-%d %H:%M:%S"),
+                        server_count
+                    );
+                }
+                return Ok(());
+            }
+
+            let backup =
+                prompt::choose_backup(backups, backup, "Which backup do you want to restore?")?;
+            if prompt::confirm(
+                force,
+                "Are you sure you want to replace the servers with the backup?",
+            )? {
+                backup::restore(&store_path, &backup)?;
+                println!(
+                    "Restored the servers from {}",
+                    backup
+                        .created_at
+                        .format("%Y-%m-%d %H:%M:%S")
+                        .to_string()
+                        .bold()
+                );
+            }
+            Ok(())
+        }
+
+        Command::Undo => {
+            let entry = load_journal()?.undo(&load_store()?)?;
+            let mut names = entry
+                .before
+                .iter()
+                .map(|server| server.name.as_str())
+                .chain(entry.after.iter().map(|name| name.as_str()))
+                .collect::<Vec<_>>();
+            names.sort_unstable();
+            names.dedup();
+            println!(
+                "Undid {} of {}",
+                entry.operation.bold(),
+                names.join(", ").bold().green()
+            );
+            Ok(())
+        }
+
+        Command::Sync { remote } => {
+            let remote = remote.or(load_config()?.sync_remote);
+            match sync::sync(&get_store_path()?, remote.as_deref())? {
+                sync::SyncOutcome::Committed => println!(
+                    "Committed the servers. Set the {} config key to sync them with a remote.",
+                    "sync_remote".bold().cyan()
+                ),
+                sync::SyncOutcome::UpToDate => println!("The servers are already up to date"),
+                sync::SyncOutcome::Synced { pulled } => {
+                    println!(
+                        "Synced the servers, pulling {} change(s)",
+                        pulled.to_string().bold()
+                    )
+                }
+            }
+            Ok(())
+        }
+
+        Command::Edit {
+            field: None,
+            raw: true,
+            ..
+        } => edit_raw(),
+
+        Command::Edit {
+            field: None,
+            server,
+            ..
+        } => edit_fields(server.get()),
+
+        Command::Edit {
+            field: Some(field), ..
+        } => {
+            let server_store = load_store()?;
+            let server_name = prompt::choose_server(
+                &server_store,
+                field.get_server(),
+                "Which server do you want to edit?",
+            )?
+            .name
+            .clone();
+            match field {
+                cli::Edit::Name { name, force, .. } => {
+                    edit_name(&server_store, &server_name, name, force)
+                }
+                cli::Edit::StartScript {
+                    start_script,
+                    command,
+                    force,
+                    ..
+                } => edit_start_script(&server_store, &server_name, start_script, command, force),
+                cli::Edit::Path { path, force, .. } => {
+                    edit_path(&server_store, &server_name, path, force)
+                }
+                cli::Edit::Port { port, force, .. } => {
+                    edit_port(&server_store, &server_name, port, force)
+                }
+                cli::Edit::InjectPort { enabled, force, .. } => {
+                    edit_inject_port(&server_store, &server_name, enabled, force)
+                }
+                cli::Edit::EnvFile {
+                    env_files, force, ..
+                } => edit_env_files(&server_store, &server_name, env_files, force),
+                cli::Edit::Restart { restart, force, .. } => {
+                    edit_restart(&server_store, &server_name, restart, force)
+                }
+                cli::Edit::Shell {
+                    shell,
+                    clear,
+                    force,
+                    ..
+                } => edit_shell(&server_store, &server_name, shell, clear, force),
+                cli::Edit::HealthCheck {
+                    url,
+                    command,
+                    clear,
+                    force,
+                    ..
+                } => edit_health_check(&server_store, &server_name, url, command, clear, force),
+                cli::Edit::Domain {
+                    domain,
+                    clear,
+                    force,
+                    ..
+                } => edit_domain(&server_store, &server_name, domain, clear, force),
+                cli::Edit::IdleTimeout {
+                    minutes,
+                    clear,
+                    force,
+                    ..
+                } => edit_idle_timeout(&server_store, &server_name, minutes, clear, force),
+                cli::Edit::GracePeriod {
+                    seconds,
+                    clear,
+                    force,
+                    ..
+                } => edit_grace_period(&server_store, &server_name, seconds, clear, force),
+                cli::Edit::DependsOn {
+                    depends_on, force, ..
+                } => edit_depends_on(&server_store, &server_name, depends_on, force),
+                cli::Edit::Description {
+                    description,
+                    clear,
+                    force,
+                    ..
+                } => edit_description(&server_store, &server_name, description, clear, force),
+                cli::Edit::Tags { tags, force, .. } => {
+                    edit_tags(&server_store, &server_name, tags, force)
+                }
+            }
+        }
+
+        Command::Run {
+            server,
+            all,
+            tag,
+            last,
+            detach,
+            no_log,
+            open,
+            restart,
+            strict,
+            inspect,
+        } => {
+            let server = server.get();
+            let mut server_store = load_store()?;
+            let server_names = if all {
+                let mut servers = server_store.get_enabled_with_tag(None);
+                if servers.is_empty() {
+                    return Err(ApplicationError::NoServers);
+                }
+                servers.sort_by_key(|server| server.name.as_str());
+                servers
+                    .into_iter()
+                    .map(|server| server.name.clone())
+                    .collect()
+            } else if let Some(tag) = tag {
+                let mut servers = server_store.get_enabled_with_tag(Some(tag.as_str()));
+                if servers.is_empty() {
+                    return Err(ApplicationError::NoTaggedServers(tag));
+                }
+                servers.sort_by_key(|server| server.name.as_str());
+                servers
+                    .into_iter()
+                    .map(|server| server.name.clone())
+                    .collect()
+            } else if last {
+                let server = server_store
+                    .get_last_run()
+                    .ok_or(ApplicationError::NoRunHistory)?;
+                vec![server.name.clone()]
+            } else if server.len() > 1 {
+                server
+            } else {
+                let server = prompt::choose_server(
+                    &server_store,
+                    server.into_iter().next(),
+                    "Which server do you want to run?",
+                )?;
+                vec![server.name.clone()]
+            };
+            let runtime_state = load_runtime_state()?;
+            let requested_names = server_names
+                .iter()
+                .map(|server_name| server_name.as_str())
+                .collect::<Vec<_>>();
+            // Start dependencies first unless they are already running in the background
+            let server_names = supervisor::get_start_order(&server_store, &requested_names)?
+                .into_iter()
+                .filter(|server_name| {
+                    requested_names.contains(&server_name.as_str())
+                        || runtime_state.get(server_name).is_none()
+                })
+                .collect::<Vec<_>>();
+            let server_names = server_names
+                .iter()
+                .map(|server_name| server_name.as_str())
+                .collect::<Vec<_>>();
+            let logs_dir = if no_log { None } else { Some(get_logs_dir()?) };
+
+            if let Some(inspect_port) = inspect {
+                // Give each server its own inspector port so that they can be debugged together
+                let mut inspect_port = inspect_port.unwrap_or(DEFAULT_INSPECT_PORT);
+                for server_name in &server_names {
+                    while !port::is_free(inspect_port) {
+                        inspect_port = inspect_port
+                            .checked_add(1)
+                            .ok_or(ApplicationError::NoFreePort)?;
+                    }
+                    server_store.get_one_mut(server_name)?.inspect_port = Some(inspect_port);
+                    println!(
+                        "Debugging {} with the inspector on port {}. Open {} to attach.",
+                        server_name.bold(),
+                        inspect_port.to_string().yellow(),
+                        "chrome://inspect".bold().cyan()
+                    );
+                    inspect_port = inspect_port.saturating_add(1);
+                }
+            }
+
+            for server_name in &server_names {
+                relink_moved_server(&mut server_store, server_name)?;
+                let server = server_store.get_one(server_name)?;
+                // Only Node projects have a package manager and a Node version to check
+                if let Some(package_manager) = server.package_manager {
+                    let project = Project::from_path(server.get_project_dir(), package_manager)?;
+                    check_node_version(&project, strict)?;
+                }
+                install_missing_dependencies(server)?;
+                if !resolve_port_conflict(server)? {
+                    return Ok(());
+                }
+            }
+
+            if detach {
+                // Let the daemon supervise the servers if it is running
+                let pids = match connect_daemon()? {
+                    Some(mut client) => server_names
+                        .iter()
+                        .map(|server_name| client.start(server_name).map(|process| process.pid))
+                        .collect::<Result<Vec<_>, _>>()?,
+                    None => {
+                        for server_name in &server_names {
+                            let server = server_store.get_one(server_name)?;
+                            if server.restart != RestartPolicy::Never {
+                                eprintln!(
+                                    "{}: {} won't be restarted because the daemon isn't running. Start it with `{}`",
+                                    "Warning".bold().yellow(),
+                                    server.name.bold(),
+                                    "server-room daemon".bold().cyan()
+                                );
+                            }
+                        }
+                        server_store.start_servers_detached(
+                            &server_names,
+                            &runtime_state,
+                            logs_dir.as_deref(),
+                        )?
+                    }
+                };
+                for (server_name, pid) in server_names.iter().zip(pids) {
+                    println!(
+                        "Started {} in the background (PID {})",
+                        server_name.bold().green(),
+                        pid
+                    );
+                }
+                if open {
+                    print_startup_times(&server_store, &requested_names)?;
+                    for server_name in &requested_names {
+                        let server = server_store.get_one(server_name)?;
+                        let elapsed = health::wait_until_ready(
+                            server,
+                            Some(Duration::from_secs(60)),
+                            &AtomicBool::new(false),
+                        )
+                        .ok_or_else(|| ApplicationError::NotReady {
+                            server: server.name.clone(),
+                            timeout: 60,
+                        })?;
+                        // Servers that were already running weren't just started
+                        if server_names.contains(server_name) {
+                            server_store.record_startup_time(server_name, elapsed)?;
+                        }
+                        browser::open(&server.get_url())?;
+                    }
+                }
+                Ok(())
+            } else {
+                print_startup_times(&server_store, &server_names)?;
+                match server_names.as_slice() {
+                    // Single servers that won't be restarted don't need the supervisor
+                    [server_name]
+                        if restart.unwrap_or(server_store.get_one(server_name)?.restart)
+                            == RestartPolicy::Never =>
+                    {
+                        // Report when the server is ready while it runs in the foreground
+                        let server = server_store.get_one(server_name)?.clone();
+                        let ready_store = server_store.clone();
+                        thread::spawn(move || {
+                            if let Some(elapsed) =
+                                health::wait_until_ready(&server, None, &AtomicBool::new(false))
+                            {
+                                let _ = ready_store.record_startup_time(&server.name, elapsed);
+                                println!(
+                                    "{} ready in {}",
+                                    server.name.bold().green(),
+                                    health::format_elapsed(elapsed)
+                                );
+                                if open {
+                                    if let Err(err) = browser::open(&server.get_url()) {
+                                        eprintln!("{}: {}", "Warning".bold().yellow(), err);
+                                    }
+                                }
+                            }
+                        });
+
+                        let log_path = logs_dir
+                            .map(|logs_dir| logging::get_log_path(&logs_dir, server_name));
+                        let record = server_store.start_server(
+                            server_name,
+                            log_path.as_deref(),
+                            &get_history_path()?,
+                        )?;
+                        if record.is_crash() {
+                            // Output is only captured when it is logged
+                            let output = match &log_path {
+                                Some(log_path) => {
+                                    logging::read_last_run(log_path, crash::TAIL_LINES)?
+                                }
+                                None => vec![],
+                            };
+                            eprintln!(
+                                "\n{}",
+                                crash::get_summary(
+                                    server_store.get_one(server_name)?,
+                                    &record,
+                                    &output
+                                )
+                            );
+                        }
+                        Ok(())
+                    }
+                    _ => {
+                        let open_names = if open { requested_names } else { vec![] };
+                        server_store.start_servers(
+                            &server_names,
+                            logs_dir.as_deref(),
+                            &get_history_path()?,
+                            restart,
+                            &open_names,
+                        )
+                    }
+                }
+            }
+        }
+
+        Command::Install {
+            server,
+            all,
+            tag,
+            jobs,
+        } => {
+            let server_store = load_store()?;
+            let servers = if all {
+                server_store.get_all()
+            } else if let Some(tag) = tag {
+                let servers = server_store.get_with_tag(Some(tag.as_str()));
+                if servers.is_empty() {
+                    return Err(ApplicationError::NoTaggedServers(tag));
+                }
+                servers
+            } else if server.is_empty() {
+                prompt::choose_servers(
+                    &server_store,
+                    "Which servers' dependencies do you want to install?",
+                )?
+            } else {
+                server
+                    .iter()
+                    .map(|server_name| server_store.get_one(server_name))
+                    .collect::<Result<Vec<_>, _>>()?
+            };
+            let mut servers = servers;
+            servers.sort_by_key(|server| server.name.as_str());
+            // Only projects with a package.json have dependencies to install
+            let (servers, skipped): (Vec<_>, Vec<_>) = servers
+                .into_iter()
+                .partition(|server| server.package_manager.is_some());
+            for server in skipped {
+                println!(
+                    "Skipped {} because it has no package.json",
+                    server.name.bold()
+                );
+            }
+
+            let mut installed = vec![];
+            let mut failed = vec![];
+            install::install_all(&servers, jobs, |server, result| match result {
+                install::InstallResult::Installed(elapsed) => {
+                    println!(
+                        "{} {} in {}",
+                        "Installed".green(),
+                        server.name.bold(),
+                        health::format_elapsed(elapsed)
+                    );
+                    installed.push(server.name.clone());
+                }
+                install::InstallResult::Failed(output) => {
+                    println!("{} {}", "Failed".red(), server.name.bold());
+                    for line in output {
+                        println!("    {}", line.dimmed());
+                    }
+                    failed.push(server.name.clone());
+                }
+            });
+
+            println!(
+                "\n{} installed, {} failed",
+                installed.len().to_string().green(),
+                failed.len().to_string().red()
+            );
+            if failed.is_empty() {
+                Ok(())
+            } else {
+                failed.sort();
+                Err(ApplicationError::InstallFailures(failed))
+            }
+        }
+
+        Command::Outdated { tag } => {
+            let server_store = load_store()?;
+            let mut servers = server_store
+                .get_with_tag(tag.as_deref())
+                .into_iter()
+                .filter(|server| server.package_manager.is_some())
+                .collect::<Vec<_>>();
+            if servers.is_empty() {
+                return Err(ApplicationError::NoServers);
+            }
+            servers.sort_by_key(|server| server.name.as_str());
+
+            let mut rows = vec![];
+            for (server, result) in servers.iter().zip(outdated::check_all(&servers)) {
+                match result {
+                    Ok(packages) => {
+                        rows.extend(packages.into_iter().map(|package| (server, package)))
+                    }
+                    Err(err) => eprintln!("{}: {}", "Warning".bold().yellow(), err),
+                }
+            }
+            if rows.is_empty() {
+                println!("Every dependency is up to date");
+                return Ok(());
+            }
+
+            let headers = ["Server", "Package", "Current", "Wanted", "Latest"];
+            let cells = rows
+                .iter()
+                .map(|(server, package)| {
+                    [
+                        server.name.clone(),
+                        package.name.clone(),
+                        package
+                            .current
+                            .clone()
+                            .unwrap_or_else(|| "missing".to_string()),
+                        package.wanted.clone(),
+                        package.latest.clone(),
+                    ]
+                })
+                .collect::<Vec<_>>();
+            let widths = (0..headers.len())
+                .map(|column| {
+                    cells
+                        .iter()
+                        .map(|row| row[column].len())
+                        .chain(std::iter::once(headers[column].len()))
+                        .max()
+                        .unwrap_or(0)
+                })
+                .collect::<Vec<_>>();
+            let header = headers
+                .iter()
+                .zip(&widths)
+                .map(|(header, width)| format!("{:width$}", header, width = width))
+                .collect::<Vec<_>>()
+                .join("  ");
+            println!("{}", header.trim_end().bold());
+            for row in cells {
+                // Like npm, highlight updates that package.json allows in red and updates that need
+                // a new range in yellow
+                let color = if row[2] != row[3] {
+                    Color::Red
+                } else {
+                    Color::Yellow
+                };
+                let line = row
+                    .iter()
+                    .zip(&widths)
+                    .enumerate()
+                    .map(|(column, (cell, width))| {
+                        let cell = format!("{:width$}", cell, width = width);
+                        if column == 1 {
+                            cell.color(color).to_string()
+                        } else {
+                            cell
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join("  ");
+                println!("{}", line.trim_end());
+            }
+            Ok(())
+        }
+
+        Command::Scripts { server } => {
+            let server_store = load_store()?;
+            let server = prompt::choose_server(
+                &server_store,
+                server,
+                "Which server's scripts do you want to list?",
+            )?;
+            let project = Project::from_path(
+                server.get_project_dir(),
+                load_config()?.get_package_manager(),
+            )?;
+            let mut scripts = project.get_start_scripts()?;
+            scripts.sort_by(|script1, script2| script1.name.cmp(&script2.name));
+            println!("{}", format!("Scripts in {}:", project.name).bold());
+            for script in scripts {
+                // Mark the script that the server is configured to run
+                if script.start_command == server.start_command {
+                    println!(
+                        "{} {} {}",
+                        "*".green(),
+                        script.to_string().bold().green(),
+                        "(start script)".dimmed()
+                    );
+                } else {
+                    println!("  {}", script);
+                }
+            }
+            Ok(())
+        }
+
+        Command::Open { server } => {
+            let server_store = load_store()?;
+            let server =
+                prompt::choose_server(&server_store, server, "Which server do you want to open?")?;
+            let url = server.get_url();
+            browser::open(&url)?;
+            println!("Opened {}", url.bold().green());
+            Ok(())
+        }
+
+        Command::Wait { server, timeout } => {
+            let server_store = load_store()?;
+            let server = prompt::choose_server(
+                &server_store,
+                server,
+                "Which server do you want to wait for?",
+            )?;
+            let elapsed = health::wait_until_ready(
+                server,
+                Some(Duration::from_secs(timeout)),
+                &AtomicBool::new(false),
+            )
+            .ok_or_else(|| ApplicationError::NotReady {
+                server: server.name.clone(),
+                timeout,
+            })?;
+            println!(
+                "{} ready in {}",
+                server.name.bold().green(),
+                health::format_elapsed(elapsed)
+            );
+            Ok(())
+        }
+
+        Command::Stop { server, force } => {
+            let server_store = load_store()?;
+            let runtime_state = load_runtime_state()?;
+            let server =
+                prompt::choose_server(&server_store, server, "Which server do you want to stop?")?;
+            if prompt::confirm(force, "Are you sure you want to stop the server?")? {
+                let phase = match connect_daemon()? {
+                    Some(mut client) => client.stop(&server.name)?,
+                    None => Some(server_store.stop_server(&server.name, &runtime_state)?),
+                };
+                match phase {
+                    Some(phase) => println!("Stopped {} ({})", server.name.bold().green(), phase),
+                    None => println!("Stopped {}", server.name.bold().green()),
+                }
+            }
+            Ok(())
+        }
+
+        Command::KillPort {
+            port,
+            server,
+            force,
+        } => {
+            let server_store = load_store()?;
+            let port = match port {
+                Some(port) => port,
+                None => {
+                    prompt::choose_server(
+                        &server_store,
+                        server,
+                        "Which server's port do you want to free?",
+                    )?
+                    .port
+                }
+            };
+            // Give the owners as long to exit as the server on the port would get
+            let grace_period = server_store
+                .get_all()
+                .into_iter()
+                .find(|server| server.port == port)
+                .map_or(
+                    Duration::from_secs(server::DEFAULT_GRACE_PERIOD),
+                    |server| server.get_grace_period(),
+                );
+
+            let owners = port::find_owners(port);
+            if owners.is_empty() {
+                return Err(ApplicationError::PortNotInUse(port));
+            }
+            for owner in &owners {
+                println!("Port {} is in use by {}", port, owner.to_string().bold());
+            }
+            if prompt::confirm(force, "Are you sure you want to stop these processes?")? {
+                for owner in &owners {
+                    process::kill(owner.pid, grace_period)?;
+                    println!("Stopped {}", owner.to_string().bold().green());
+                }
+            }
+            Ok(())
+        }
+
+        Command::Logs {
+            server,
+            follow,
+            lines,
+        } => {
+            let server_store = load_store()?;
+            let server = prompt::choose_server(
+                &server_store,
+                server,
+                "Which server's logs do you want to see?",
+            )?;
+            if !follow {
+                if let Some(mut client) = connect_daemon()? {
+                    for line in client.logs(&server.name, lines)? {
+                        println!("{}", line);
+                    }
+                    return Ok(());
+                }
+            }
+
+            let log_path = logging::get_log_path(&get_logs_dir()?, &server.name);
+            if !log_path.is_file() {
+                return Err(ApplicationError::NoLogs(server.name.clone()));
+            }
+            let offset = logging::print_log(&log_path, lines)?;
+            if follow {
+                logging::follow_log(&log_path, offset)?;
+            }
+            Ok(())
+        }
+
+        Command::History { server, since } => {
+            let cutoff = since.map(|since| server::get_now_secs().saturating_sub(since.seconds));
+            // Loading the runtime state records the runs that ended since it was last loaded
+            let runtime_state = RuntimeState::load(get_runtime_state_path()?)?;
+            let running = runtime_state
+                .get_all()
+                .into_iter()
+                .filter(|process| {
+                    server
+                        .as_ref()
+                        .is_none_or(|server| &process.server == server)
+                })
+                .collect::<Vec<_>>();
+            let mut runs = run_history::load(&get_history_path()?)?
+                .into_iter()
+                .filter(|run| server.as_ref().is_none_or(|server| &run.server == server))
+                .filter(|run| cutoff.is_none_or(|cutoff| run.exited_at >= cutoff))
+                .collect::<Vec<_>>();
+            if running.is_empty() && runs.is_empty() {
+                println!("No runs found");
+                return Ok(());
+            }
+
+            // Display the runs that haven't ended yet first, then the most recent runs
+            let now = server::get_now_secs();
+            for process in running {
+                println!(
+                    "{} {} has been running in the background for {}",
+                    run_history::format_time(process.started_at).dimmed(),
+                    process.server.bold(),
+                    server::format_duration(now.saturating_sub(process.started_at))
+                );
+            }
+            runs.reverse();
+            for run in runs {
+                let outcome = if run.is_crash() {
+                    format!("crashed {}", run.get_cause()).red()
+                } else {
+                    format!("exited {}", run.get_cause()).normal()
+                };
+                println!(
+                    "{} {} ran for {}, {}",
+                    run.get_start_time().dimmed(),
+                    run.server.bold(),
+                    run.get_uptime(),
+                    outcome
+                );
+            }
+            Ok(())
+        }
+
+        Command::Remove {
+            force, multi: true, ..
+        } => {
+            let server_store = load_store()?;
+            let servers =
+                prompt::choose_servers(&server_store, "Which servers do you want to remove?")?;
+            if servers.is_empty() {
+                return Ok(());
+            }
+
+            println!("{}", "Servers to remove:".bold());
+            for server in &servers {
+                println!("{} ({:?})", server.name.bold().red(), server.dir);
+            }
+            let diff = diff::render_servers(&servers, &[])?;
+            if prompt::confirm_change(
+                force,
+                &diff,
+                "Are you sure you want to remove these servers?",
+            )? {
+                let server_names = servers
+                    .iter()
+                    .map(|server| server.name.as_str())
+                    .collect::<Vec<_>>();
+                server_store.remove_servers(&server_names)?;
+                remove_host_entries(&servers)?;
+            }
+            Ok(())
+        }
+
+        Command::Remove {
+            server,
+            force,
+            multi: false,
+        } => {
+            let server_store = load_store()?;
+            let server = prompt::choose_server(
+                &server_store,
+                server.get(),
+                "Which server do you want to remove?",
+            )?;
+            let diff = diff::render_servers(&[server], &[])?;
+            if prompt::confirm_change(force, &diff, "Are you sure you want to remove the server?")?
+            {
+                server_store.remove_server(&server.name)?;
+                remove_host_entries(&[server])?;
+            }
+            Ok(())
+        }
+
+        Command::Clone { server, name, port } => {
+            let server_store = load_store()?;
+            let server =
+                prompt::choose_server(&server_store, server, "Which server do you want to copy?")?;
+            let new_name =
+                prompt::choose_server_new_name(server, name, "What is the new server's name?")?;
+            let port = match port {
+                Some(PortChoice::Fixed(port)) => port,
+                _ => server_store
+                    .get_free_ports(load_config()?.get_port_range(), 1)
+                    .first()
+                    .copied()
+                    .ok_or(ApplicationError::NoFreePort)?,
+            };
+            server_store.clone_server(&server.name, new_name.clone(), port)?;
+            println!(
+                "Copied {} to {} on port {}",
+                server.name.bold(),
+                new_name.bold().green(),
+                port.to_string().yellow()
+            );
+            Ok(())
+        }
+
+        Command::Archive { server } => {
+            let server_store = load_store()?;
+            let server = prompt::choose_server(
+                &server_store,
+                server,
+                "Which server do you want to archive?",
+            )?;
+            server_store.set_server_enabled(&server.name, false)?;
+            println!("Archived {}", server.name.bold().green());
+            Ok(())
+        }
+
+        Command::Unarchive { server } => {
+            let server_store = load_store()?;
+            let server = prompt::choose_archived_server(
+                &server_store,
+                server,
+                "Which server do you want to restore?",
+            )?;
+            server_store.set_server_enabled(&server.name, true)?;
+            println!("Restored {}", server.name.bold().green());
+            Ok(())
+        }
+
+        Command::Pin { server } => {
+            let server_store = load_store()?;
+            let server =
+                prompt::choose_server(&server_store, server, "Which server do you want to pin?")?;
+            server_store.set_server_pinned(&server.name, true)?;
+            println!("Pinned {}", server.name.bold().green());
+            Ok(())
+        }
+
+        Command::Unpin { server } => {
+            let server_store = load_store()?;
+            let server =
+                prompt::choose_server(&server_store, server, "Which server do you want to unpin?")?;
+            server_store.set_server_pinned(&server.name, false)?;
+            println!("Unpinned {}", server.name.bold().green());
+            Ok(())
+        }
+
+        Command::Prune { force } => {
+            let server_store = load_store()?;
+            let dead_servers = server_store.get_dead_servers();
+            if dead_servers.is_empty() {
+                println!("No servers need to be pruned");
+                return Ok(());
+            }
+
+            println!("{}", "Servers whose projects no longer exist:".bold());
+            for server in &dead_servers {
+                println!("{} ({:?})", server.name.bold().red(), server.dir);
+            }
+            let diff = diff::render_servers(&dead_servers, &[])?;
+            if prompt::confirm_change(
+                force,
+                &diff,
+                "Are you sure you want to remove these servers?",
+            )? {
+                let server_names = dead_servers
+                    .iter()
+                    .map(|server| server.name.as_str())
+                    .collect::<Vec<_>>();
+                server_store.remove_servers(&server_names)?;
+                remove_host_entries(&dead_servers)?;
+            }
+            Ok(())
+        }
+
+        Command::List {
+            tag,
+            format,
+            verbose,
+            archived,
+        } => {
+            let server_store = load_store()?;
+            let mut servers = if archived {
+                server_store.get_with_tag(tag.as_deref())
+            } else {
+                server_store.get_enabled_with_tag(tag.as_deref())
+            };
+            servers.sort_by(|server1, server2| server1.name.cmp(&server2.name));
+            match format {
+                ListFormat::Text => {
+                    let last_exits = if verbose {
+                        run_history::get_last_exits(&get_history_path()?)?
+                    } else {
+                        HashMap::new()
+                    };
+                    let git_statuses = git::get_statuses(
+                        &servers
+                            .iter()
+                            .map(|server| server.dir.as_path())
+                            .collect::<Vec<_>>(),
+                    );
+                    println!("{}", "Servers:".bold());
+                    servers
+                        .iter()
+                        .zip(git_statuses)
+                        .for_each(|(server, git_status)| {
+                            let tags = server
+                                .tags
+                                .iter()
+                                .map(|tag| format!(" #{}", tag))
+                                .collect::<String>();
+                            let git_status = git_status
+                                .map(|git_status| {
+                                    format!(" on {}", git_status.to_string().magenta())
+                                })
+                                .unwrap_or_default();
+                            let archived = if server.enabled { "" } else { " (archived)" };
+                            println!(
+                                "{} ({}) on port {}{}{}{}",
+                                server.name.bold().green(),
+                                server.start_command.cyan(),
+                                server.port.to_string().yellow(),
+                                git_status,
+                                tags.dimmed(),
+                                archived.dimmed()
+                            );
+                            if verbose {
+                                if let Some(description) = &server.description {
+                                    println!("    {}", description.italic());
+                                }
+                                println!("    {}", server.get_usage_summary().dimmed());
+                                if let Some(last_exit) = last_exits.get(&server.name) {
+                                    println!("    last {}", last_exit.get_summary().dimmed());
+                                }
+                            }
+                        });
+                }
+                ListFormat::Json => {
+                    let stringified = serde_json::to_string_pretty(&servers)
+                        .map_err(|_| ApplicationError::StringifyStore)?;
+                    println!("{}", stringified);
+                }
+                ListFormat::Toml => {
+                    // TOML documents must be tables, so wrap the servers like the store file does
+                    let stringified =
+                        toml::to_string_pretty(&HashMap::from([("servers", servers)]))
+                            .map_err(|_| ApplicationError::StringifyStore)?;
+                    print!("{}", stringified);
+                }
+            }
+            Ok(())
+        }
+
+        Command::Find { query } => {
+            let server_store = load_store()?;
+            let matches = search::find(server_store.get_all(), &query);
+            if matches.is_empty() {
+                println!("No servers match \"{}\"", query);
+                return Ok(());
+            }
+            for search_match in matches {
+                let server = search_match.server;
+                let highlight = |field: search::Field, text: &str, color: Option<Color>| match color
+                {
+                    _ if search_match.field == field => search::highlight(text, &query, color),
+                    Some(color) => text.color(color).to_string(),
+                    None => text.to_string(),
+                };
+                println!(
+                    "{} ({}) in {}",
+                    highlight(search::Field::Name, &server.name, Some(Color::Green)),
+                    highlight(
+                        search::Field::StartCommand,
+                        &server.start_command,
+                        Some(Color::Cyan)
+                    ),
+                    highlight(search::Field::Dir, &server.dir.display().to_string(), None)
+                );
+                if let Some(description) = &server.description {
+                    println!(
+                        "    {}",
+                        highlight(search::Field::Description, description, None)
+                    );
+                }
+            }
+            Ok(())
+        }
+
+        Command::Info { server } => {
+            let server_store = load_store()?;
+            let server =
+                prompt::choose_server(&server_store, server, "Which server do you want to see?")?;
+            let pid = match connect_daemon()? {
+                Some(mut client) => client
+                    .status()?
+                    .into_iter()
+                    .find(|process| process.server == server.name)
+                    .map(|process| process.pid),
+                None => load_runtime_state()?
+                    .get(&server.name)
+                    .map(|process| process.pid),
+            };
+            let or_none = |values: Vec<String>| {
+                if values.is_empty() {
+                    "none".to_string()
+                } else {
+                    values.join(", ")
+                }
+            };
+
+            println!("{}", server.name.bold().green());
+            if let Some(description) = &server.description {
+                println!("{}", description.italic());
+            }
+            println!("Directory: {:?}", server.dir);
+            println!("Start command: {}", server.start_command.cyan());
+            println!("Port: {}", server.port.to_string().yellow());
+            println!(
+                "Domain: {}",
+                server.get_domain(&load_config()?.get_domain_suffix())
+            );
+            if let Some(package_manager) = server.package_manager {
+                println!("Package manager: {}", package_manager);
+            }
+            if let Some(shell) = server.shell {
+                println!("Shell: {}", shell);
+            }
+            println!("Restart policy: {}", server.restart);
+            match &server.health_check {
+                Some(HealthCheck::Http { url }) => println!("Health check: request {}", url),
+                Some(HealthCheck::Command { command }) => {
+                    println!("Health check: run {}", command)
+                }
+                None => println!("Health check: wait for the port"),
+            }
+            println!(
+                "Env files: {}",
+                or_none(
+                    server
+                        .env_files
+                        .iter()
+                        .map(|env_file| env_file.display().to_string())
+                        .collect()
+                )
+            );
+            if server.env.is_empty() {
+                println!("Env vars: none");
+            } else {
+                println!("Env vars:");
+                for (key, value) in &server.env {
+                    println!("    {}={}", key, value);
+                }
+            }
+            println!("Depends on: {}", or_none(server.depends_on.clone()));
+            println!("Tags: {}", or_none(server.tags.clone()));
+            println!("Frecency: {:.2}", server.get_frecency_score());
+            println!(
+                "Last run: {}",
+                server
+                    .last_run
+                    .map(|last_run| server::format_time_ago(
+                        server::get_now_secs().saturating_sub(last_run)
+                    ))
+                    .unwrap_or_else(|| "never".to_string())
+            );
+            println!("Run count: {}", server.run_count);
+            if let Some(startup_summary) = server.get_startup_summary() {
+                println!("Startup time: {}", startup_summary);
+            }
+            let status = match pid {
+                Some(pid) => format!("running (PID {})", pid).green(),
+                None => "stopped".normal(),
+            };
+            let mut flags = vec![];
+            if !server.enabled {
+                flags.push("archived");
+            }
+            if server.pinned {
+                flags.push("pinned");
+            }
+            if flags.is_empty() {
+                println!("Status: {}", status);
+            } else {
+                println!("Status: {} ({})", status, flags.join(", "));
+            }
+            let log_path = logging::get_log_path(&get_logs_dir()?, &server.name);
+            if log_path.is_file() {
+                println!("Log file: {:?}", log_path);
+            } else {
+                println!("Log file: {:?} (no logs yet)", log_path);
+            }
+            Ok(())
+        }
+
+        Command::Stats => {
+            const MONTH_SECS: u64 = 30 * 24 * 60 * 60;
+
+            let server_store = load_store()?;
+            let mut servers = server_store.get_all();
+            if servers.is_empty() {
+                return Err(ApplicationError::NoServers);
+            }
+            // Put the most frecent servers first
+            servers.sort_by(|server1, server2| {
+                server1
+                    .get_weight()
+                    .partial_cmp(&server2.get_weight())
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .reverse()
+                    .then_with(|| server1.name.cmp(&server2.name))
+            });
+
+            let name_width = servers
+                .iter()
+                .map(|server| server.name.len())
+                .max()
+                .unwrap_or(0);
+            println!("{}", "Servers:".bold());
+            for server in &servers {
+                println!(
+                    "{} {} {}",
+                    format!("{:width$}", server.name, width = name_width)
+                        .bold()
+                        .green(),
+                    format!("{:>8.2}", server.get_frecency_score()).yellow(),
+                    server.get_usage_summary().dimmed()
+                );
+            }
+
+            let month_start = server::get_now_secs().saturating_sub(MONTH_SECS);
+            let total_runs = servers
+                .iter()
+                .map(|server| server.run_count as u64)
+                .sum::<u64>();
+            let run_this_month = servers
+                .iter()
+                .filter(|server| {
+                    server
+                        .last_run
+                        .is_some_and(|last_run| last_run >= month_start)
+                })
+                .collect::<Vec<_>>();
+            let never_run = servers
+                .iter()
+                .filter(|server| server.run_count == 0)
+                .count();
+            println!();
+            println!("{}", "Summary:".bold());
+            println!("Total runs: {}", total_runs.to_string().yellow());
+            println!(
+                "Servers run this month: {} of {}",
+                run_this_month.len().to_string().yellow(),
+                servers.len()
+            );
+            // Servers are sorted by frecency, so the first recent server is the most used one
+            if let Some(server) = run_this_month.first() {
+                println!("Most used this month: {}", server.name.bold().green());
+            }
+            if let Some(server) = servers
+                .iter()
+                .filter(|server| server.run_count > 0)
+                .max_by_key(|server| server.run_count)
+            {
+                let runs = if server.run_count == 1 { "run" } else { "runs" };
+                println!(
+                    "Most used overall: {} ({} {})",
+                    server.name.bold().green(),
+                    server.run_count,
+                    runs
+                );
+            }
+            println!("Never run: {}", never_run.to_string().yellow());
+            Ok(())
+        }
+
+        Command::Ui => tui::run(tui::DashboardPaths {
+            store_path: get_store_path()?,
+            runtime_state_path: get_runtime_state_path()?,
+            logs_dir: get_logs_dir()?,
+        }),
+
+        Command::Top => top::run(top::TopPaths {
+            runtime_state_path: get_runtime_state_path()?,
+            logs_dir: get_logs_dir()?,
+            socket_path: get_socket_path()?,
+        }),
+
+        Command::Daemon { http_port } => {
+            let socket_path = get_socket_path()?;
+            let daemon = Daemon::new(
+                DaemonPaths {
+                    store_path: get_store_path()?,
+                    runtime_state_path: get_runtime_state_path()?,
+                    logs_dir: get_logs_dir()?,
+                    socket_path: socket_path.clone(),
+                },
+                load_config()?.get_domain_suffix(),
+            );
+            println!("Listening on {}", socket_path.display().to_string().bold());
+            if let Some(http_port) = http_port {
+                let token_path = socket_path.with_file_name("http-token");
+                http_api::listen(daemon.clone(), http_port, &token_path)?;
+                println!(
+                    "Serving the REST API on {}, with the token for changing servers in {}",
+                    format!("http://localhost:{}", http_port).bold(),
+                    token_path.display().to_string().bold()
+                );
+            }
+            daemon::serve(daemon)
+        }
+
+        Command::WriteLog { log_path } => logging::write_stdin(&log_path),
+
+        Command::Status => {
+            let processes = match connect_daemon()? {
+                Some(mut client) => client
+                    .status()?
+                    .into_iter()
+                    .map(|process| {
+                        let restarts = match process.restarts {
+                            0 => String::new(),
+                            1 => " [1 restart]".to_string(),
+                            restarts => format!(" [{} restarts]", restarts),
+                        };
+                        (process.server, process.pid, restarts)
+                    })
+                    .collect::<Vec<_>>(),
+                None => load_runtime_state()?
+                    .get_all()
+                    .into_iter()
+                    .map(|process| (process.server.clone(), process.pid, String::new()))
+                    .collect(),
+            };
+            if processes.is_empty() {
+                println!("No servers are running in the background");
+            }
+            let last_exits = run_history::get_last_exits(&get_history_path()?)?;
+            for (server_name, pid, restarts) in &processes {
+                println!("{} (PID {}){}", server_name.bold().green(), pid, restarts);
+                // Restarted servers have exited at least once while supervised
+                if let Some(last_exit) = last_exits.get(server_name) {
+                    println!("    last {}", last_exit.get_summary().dimmed());
+                }
+            }
+
+            // Also report servers that crashed and are no longer running
+            let mut crashes = last_exits
+                .values()
+                .filter(|last_exit| {
+                    last_exit.is_crash()
+                        && !processes
+                            .iter()
+                            .any(|(server_name, _, _)| server_name == &last_exit.server)
+                })
+                .collect::<Vec<_>>();
+            crashes.sort_by(|crash1, crash2| crash1.server.cmp(&crash2.server));
+            for crash in crashes {
+                println!("{} {}", crash.server.bold().red(), crash.get_summary());
+            }
+            Ok(())
+        }
+
+        Command::Proxy { port, tls_port } => {
+            let config = load_config()?;
+            println!(
+                "Proxying server domains on port {}",
+                port.to_string().bold()
+            );
+            if let Some(tls_port) = tls_port {
+                println!("Proxying HTTPS on port {}", tls_port.to_string().bold());
+            }
+            proxy::run(
+                proxy::ProxyPaths {
+                    store_path: get_store_path()?,
+                    runtime_state_path: get_runtime_state_path()?,
+                    logs_dir: get_logs_dir()?,
+                },
+                port,
+                tls_port,
+                &config,
+            )
+        }
+
+        Command::Doctor => {
+            let mut server_store = load_store()?;
+            let server_names = server_store
+                .get_all()
+                .into_iter()
+                .map(|server| server.name.clone())
+                .collect::<Vec<_>>();
+            for server_name in server_names {
+                relink_moved_server(&mut server_store, &server_name)?;
+            }
+            let runtime_state = load_runtime_state()?;
+            let diagnoses = doctor::diagnose(&server_store, &runtime_state);
+            let mut failures = 0;
+            for (server, problems) in &diagnoses {
+                if problems.is_empty() {
+                    println!("{} {}", "✔".green(), server.name.bold());
+                    continue;
+                }
+
+                failures += 1;
+                println!("{} {}", "✘".red(), server.name.bold());
+                for problem in problems {
+                    println!("    {}", problem.message);
+                    println!("    {} {}", "Fix:".dimmed(), problem.fix.cyan());
+                }
+            }
+
+            if failures > 0 {
+                Err(ApplicationError::DoctorFailed(failures))
+            } else {
+                Ok(())
+            }
+        }
+
+        Command::Caddy {
+            tag,
+            output,
+            reload,
+            push,
+            admin,
+        } => {
+            if reload {
+                return sync_caddy(tag.as_deref(), output);
+            }
+            let server_store = load_store()?;
+            let servers = server_store.get_enabled_with_tag(tag.as_deref());
+            let domain_suffix = load_config()?.get_domain_suffix();
+            if push {
+                caddy::push_config(&servers, &domain_suffix, &admin)?;
+                println!("Loaded {} route(s) into Caddy", servers.len());
+                return Ok(());
+            }
+            match output {
+                Some(output_path) => caddy::write_caddyfile(&servers, &domain_suffix, &output_path),
+                None => {
+                    print!("{}", caddy::generate_caddyfile(&servers, &domain_suffix));
+                    Ok(())
+                }
+            }
+        }
+
+        Command::Dnsmasq { tag, output } => {
+            let server_store = load_store()?;
+            let servers = server_store.get_enabled_with_tag(tag.as_deref());
+            let domain_suffix = load_config()?.get_domain_suffix();
+            match output {
+                Some(output_path) => dnsmasq::write_config(&servers, &domain_suffix, &output_path),
+                None => {
+                    print!("{}", dnsmasq::generate_config(&servers, &domain_suffix));
+                    Ok(())
+                }
+            }
+        }
+
+        Command::VscodeLaunch {
+            server,
+            tag,
+            output,
+        } => {
+            let server_store = load_store()?;
+            let servers = if let Some(tag) = tag {
+                let servers = server_store.get_with_tag(Some(tag.as_str()));
+                if servers.is_empty() {
+                    return Err(ApplicationError::NoTaggedServers(tag));
+                }
+                servers
+            } else if server.is_empty() {
+                prompt::choose_servers(&server_store, "Which servers do you want to launch?")?
+            } else {
+                server
+                    .iter()
+                    .map(|server_name| server_store.get_one(server_name))
+                    .collect::<Result<Vec<_>, _>>()?
+            };
+            vscode::write_launch_config(&servers, &output)?;
+            println!(
+                "Added {} server(s) to {}. Start the \"server-room\" configuration to debug them together.",
+                servers.len(),
+                output.display().to_string().bold()
+            );
+            Ok(())
+        }
+
+        Command::Share { server, provider } => {
+            let server_store = load_store()?;
+            let server =
+                prompt::choose_server(&server_store, server, "Which server do you want to share?")?;
+            let provider = provider
+                .or_else(TunnelProvider::detect)
+                .ok_or(ApplicationError::NoTunnelProvider)?;
+            share::share(server, provider)
+        }
+
+        Command::Qr { server } => {
+            let server_store = load_store()?;
+            let server =
+                prompt::choose_server(&server_store, server, "Which server do you want to open?")?;
+            let url = format!("http://{}:{}", qr::get_lan_ip()?, server.port);
+            print!("{}", qr::render(&url)?);
+            println!("\n{}", url.bold().green());
+            Ok(())
+        }
+
+        Command::Tls(tls) => {
+            let server_store = load_store()?;
+            match tls {
+                Tls::Setup { tag } => {
+                    let domain_suffix = load_config()?.get_domain_suffix();
+                    let certs_dir = get_store_path()?.with_file_name("certs");
+                    let mut servers = server_store.get_with_tag(tag.as_deref());
+                    servers.sort_by_key(|server| server.name.as_str());
+                    let mut tls_certs = vec![];
+                    for server in servers {
+                        let tls_cert = tls::mint_cert(server, &domain_suffix, &certs_dir)?;
+                        println!(
+                            "Minted a certificate for {}",
+                            server.get_domain(&domain_suffix).bold().green()
+                        );
+                        tls_certs.push((server.name.clone(), Some(tls_cert)));
+                    }
+                    server_store.set_server_tls_certs(tls_certs)
+                }
+                Tls::Clear => server_store.set_server_tls_certs(
+                    server_store
+                        .get_all()
+                        .into_iter()
+                        .map(|server| (server.name.clone(), None))
+                        .collect(),
+                ),
+            }
+        }
+
+        Command::Hosts(hosts) => {
+            let config = load_config()?;
+            let hosts_file = config.get_hosts_file();
+            match hosts {
+                Hosts::Sync => {
+                    let domain_suffix = config.get_domain_suffix();
+                    let mut domains = load_store()?
+                        .get_enabled_with_tag(None)
+                        .into_iter()
+                        .map(|server| server.get_domain(&domain_suffix))
+                        .collect::<Vec<_>>();
+                    domains.sort();
+                    hosts::sync(&hosts_file, &domains)?;
+                    println!(
+                        "Synced {} domain(s) to {}",
+                        domains.len(),
+                        hosts_file.display().to_string().bold()
+                    );
+                }
+                Hosts::Clear => {
+                    hosts::sync(&hosts_file, &[])?;
+                    println!(
+                        "Cleared the entries in {}",
+                        hosts_file.display().to_string().bold()
+                    );
+                }
+            }
+            Ok(())
+        }
+
+        Command::Path { server } => {
+            let server_store = load_store()?;
+            // Fall back to the closest server name so that `sr cd` tolerates typos
+            let server = match server_store.get_one(&server) {
+                Ok(server) => server,
+                Err(err) => {
+                    let closest = server_store.get_closest_server_name(&server).ok_or(err)?;
+                    eprintln!("Using closest match {}", closest.bold().green());
+                    server_store.get_one(&closest)?
+                }
+            };
+            println!("{}", server.get_project_dir().display());
+            Ok(())
+        }
+
+        Command::Init { shell } => {
+            print!("{}", init::get_init_script(shell));
+            Ok(())
+        }
+
+        Command::Completions { shell } => {
+            clap_complete::generate(
+                shell,
+                &mut Cli::command(),
+                "server-room",
+                &mut std::io::stdout(),
+            );
+            Ok(())
+        }
+
+        Command::Unknown(args) => Err(ApplicationError::InvalidCommand(args[0].clone())),
+    }
+}
+
+// Warn when something is already listening on the server's port and let the user decide how to
+// proceed, returning whether the server should still be started
+fn resolve_port_conflict(server: &Server) -> Result<bool, ApplicationError> {
+    if port::is_free(server.port) {
+        return Ok(true);
+    }
+
+    let owners = port::find_owners(server.port);
+    let owner_description = if owners.is_empty() {
+        "another process".to_string()
+    } else {
+        owners
+            .iter()
+            .map(|owner| owner.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    eprintln!(
+        "{}: port {} for {} is already in use by {}",
+        "Warning".bold().yellow(),
+        server.port,
+        server.name.bold(),
+        owner_description
+    );
+
+    match prompt::choose_port_conflict_action(!owners.is_empty(), "What do you want to do?")? {
+        prompt::PortConflictAction::Kill => {
+            for owner in &owners {
+                process::kill(owner.pid, server.get_grace_period())?;
+            }
+            Ok(true)
+        }
+        prompt::PortConflictAction::RunAnyway => Ok(true),
+        prompt::PortConflictAction::Cancel => Ok(false),
+    }
+}
+
+// Let the user edit the server's fields one at a time with the edit subcommands' prompts until
+// they are done
+fn edit_fields(server: Option<String>) -> Result<(), ApplicationError> {
+    let mut server_name =
+        prompt::choose_server(&load_store()?, server, "Which server do you want to edit?")?
+            .name
+            .clone();
+    while let Some(field) =
+        prompt::choose_edit_field(&format!("What do you want to edit in {}?", server_name))?
+    {
+        // Reload the store to see the previous edits
+        let server_store = load_store()?;
+        let name = server_name.as_str();
+        let result = match field {
+            "name" => edit_name(&server_store, name, None, false),
+            "path" => edit_path(&server_store, name, None, false),
+            "start-script" => edit_start_script(&server_store, name, None, None, false),
+            "port" => edit_port(&server_store, name, None, false),
+            "inject-port" => edit_inject_port(&server_store, name, None, false),
+            "env-file" => edit_env_files(&server_store, name, None, false),
+            "restart" => edit_restart(&server_store, name, None, false),
+            "shell" => edit_shell(&server_store, name, None, false, false),
+            "health-check" => edit_health_check(&server_store, name, None, None, false, false),
+            "domain" => edit_domain(&server_store, name, None, false, false),
+            "idle-timeout" => edit_idle_timeout(&server_store, name, None, false, false),
+            "grace-period" => edit_grace_period(&server_store, name, None, false, false),
+            "depends-on" => edit_depends_on(&server_store, name, None, false),
+            "description" => edit_description(&server_store, name, None, false, false),
+            "tags" => edit_tags(&server_store, name, None, false),
+            _ => Err(ApplicationError::InvalidCommand(field.to_string())),
+        };
+        // Report failed edits without leaving the menu
+        if let Err(err) = result {
+            eprintln!("{}: {}", "Error".bold().red(), err);
+            continue;
+        }
+
+        // Keep editing the server after it is renamed
+        let new_names = get_server_names()?;
+        if !new_names.contains(&server_name) {
+            if let Some(new_name) = new_names
+                .into_iter()
+                .find(|new_name| server_store.get_one(new_name).is_err())
+            {
+                server_name = new_name;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Rename the server, asking for the new name if it wasn't provided
+fn edit_name(
+    server_store: &ServerStore,
+    server_name: &str,
+    name: Option<String>,
+    force: bool,
+) -> Result<(), ApplicationError> {
+    let server = server_store.get_one(server_name)?;
+    let new_name = prompt::choose_server_new_name(server, name, "What is the server's new name?")?;
+    let mut new_server = server.clone();
+    new_server.name = new_name.clone();
+    if confirm_edit(
+        force,
+        server,
+        &new_server,
+        "Are you sure you want to change the server's name?",
+    )? {
+        server_store.set_server_name(&server.name, new_name)?;
+    }
+
+    Ok(())
+}
+
+// Change the command that starts the server, letting the user pick one of the project's scripts
+fn edit_start_script(
+    server_store: &ServerStore,
+    server_name: &str,
+    start_script: Option<String>,
+    command: Option<String>,
+    force: bool,
+) -> Result<(), ApplicationError> {
+    let server = server_store.get_one(server_name)?;
+    let project = Project::from_path(
+        server.get_project_dir(),
+        server
+            .package_manager
+            .unwrap_or_else(|| load_config().unwrap_or_default().get_package_manager()),
+    )?;
+
+    let new_start_script = match command {
+        Some(command) => command,
+        None => {
+            prompt::choose_start_command(&project, start_script, "Which script starts the server?")?
+        }
+    };
+
+    let mut new_server = server.clone();
+    new_server.start_command = new_start_script.clone();
+    new_server.package_manager = project.package_manager;
+    if confirm_edit(
+        force,
+        server,
+        &new_server,
+        "Are you sure you want to change the server's start script?",
+    )? {
+        server_store.set_server_start_command(
+            &server.name,
+            new_start_script,
+            project.package_manager,
+        )?;
+    }
+
+    Ok(())
+}
+
+// Point the server at its project's new directory after the project moves
+fn edit_path(
+    server_store: &ServerStore,
+    server_name: &str,
+    path: Option<PathBuf>,
+    force: bool,
+) -> Result<(), ApplicationError> {
+    let server = server_store.get_one(server_name)?;
+    let path =
+        prompt::choose_server_path(server, path, "Where is the server's project directory?")?;
+    let new_dir = fs::canonicalize(&path).map_err(|_| ApplicationError::ParsePath(path))?;
+    let project = Project::from_path(new_dir.clone(), load_config()?.get_package_manager())?;
+    // Make sure that the start scripts can still be read from the new directory
+    if project.has_manifest() || project.has_package_json() {
+        let scripts = project.get_start_scripts()?;
+        if !scripts
+            .iter()
+            .any(|script| script.start_command == server.start_command)
+        {
+            eprintln!(
+                "{}: {} isn't one of the scripts in {:?}",
+                "Warning".bold().yellow(),
+                server.start_command.cyan(),
+                new_dir
+            );
+        }
+    }
+    let mut new_server = server.clone();
+    new_server.dir = new_dir.clone();
+    if confirm_edit(
+        force,
+        server,
+        &new_server,
+        "Are you sure you want to change the server's directory?",
+    )? {
+        server_store.set_server_dir(&server.name, new_dir)?;
+    }
+
+    Ok(())
+}
+
+// Change the port that the server listens on
+fn edit_port(
+    server_store: &ServerStore,
+    server_name: &str,
+    port: Option<PortChoice>,
+    force: bool,
+) -> Result<(), ApplicationError> {
+    let server = server_store.get_one(server_name)?;
+    let port_range = load_config()?.get_port_range();
+    let auto_port = server_store.get_free_ports(port_range, 1).first().copied();
+    let new_port = prompt::choose_port(port, auto_port, "What port does the server listen on?")?;
+    let mut new_server = server.clone();
+    new_server.port = new_port;
+    if confirm_edit(
+        force,
+        server,
+        &new_server,
+        "Are you sure you want to change the server's port?",
+    )? {
+        server_store.set_server_port(&server.name, new_port)?;
+    }
+
+    Ok(())
+}
+
+// Change whether the PORT environment variable is set when the server starts
+fn edit_inject_port(
+    server_store: &ServerStore,
+    server_name: &str,
+    enabled: Option<bool>,
+    force: bool,
+) -> Result<(), ApplicationError> {
+    let server = server_store.get_one(server_name)?;
+    let inject_port = prompt::choose_inject_port(
+        server,
+        enabled,
+        "Should the PORT environment variable be set when the server starts?",
+    )?;
+    let mut new_server = server.clone();
+    new_server.inject_port = inject_port;
+    if confirm_edit(
+        force,
+        server,
+        &new_server,
+        "Are you sure you want to change whether the server receives a PORT?",
+    )? {
+        server_store.set_server_inject_port(&server.name, inject_port)?;
+    }
+
+    Ok(())
+}
+
+// Change the dotenv files that are loaded when the server starts
+fn edit_env_files(
+    server_store: &ServerStore,
+    server_name: &str,
+    env_files: Option<Vec<PathBuf>>,
+    force: bool,
+) -> Result<(), ApplicationError> {
+    let server = server_store.get_one(server_name)?;
+    let new_env_files = prompt::choose_env_files(
+        server,
+        env_files,
+        "Which env files should be loaded when the server starts?",
+    )?;
+    let mut new_server = server.clone();
+    new_server.env_files = new_env_files.clone();
+    if confirm_edit(
+        force,
+        server,
+        &new_server,
+        "Are you sure you want to change the server's env files?",
+    )? {
+        server_store.set_server_env_files(&server.name, new_env_files)?;
+    }
+
+    Ok(())
+}
+
+// Change when the server is restarted after it exits
+fn edit_restart(
+    server_store: &ServerStore,
+    server_name: &str,
+    restart: Option<RestartPolicy>,
+    force: bool,
+) -> Result<(), ApplicationError> {
+    let server = server_store.get_one(server_name)?;
+    let new_restart = prompt::choose_restart_policy(
+        restart,
+        "When should the server be restarted after it exits?",
+    )?;
+    let mut new_server = server.clone();
+    new_server.restart = new_restart;
+    if confirm_edit(
+        force,
+        server,
+        &new_server,
+        "Are you sure you want to change the server's restart policy?",
+    )? {
+        server_store.set_server_restart_policy(&server.name, new_restart)?;
+    }
+
+    Ok(())
+}
+
+// Change the shell that runs the server's start command
+fn edit_shell(
+    server_store: &ServerStore,
+    server_name: &str,
+    shell: Option<shell::Shell>,
+    clear: bool,
+    force: bool,
+) -> Result<(), ApplicationError> {
+    let server = server_store.get_one(server_name)?;
+    let new_shell = if clear {
+        None
+    } else {
+        Some(prompt::choose_shell(
+            shell,
+            "Which shell should run the server's start command?",
+        )?)
+    };
+    let mut new_server = server.clone();
+    new_server.shell = new_shell;
+    if confirm_edit(
+        force,
+        server,
+        &new_server,
+        "Are you sure you want to change the server's shell?",
+    )? {
+        server_store.set_server_shell(&server.name, new_shell)?;
+    }
+
+    Ok(())
+}
+
+// Change how server-room tells that the server is ready
+fn edit_health_check(
+    server_store: &ServerStore,
+    server_name: &str,
+    url: Option<String>,
+    command: Option<String>,
+    clear: bool,
+    force: bool,
+) -> Result<(), ApplicationError> {
+    let server = server_store.get_one(server_name)?;
+    let cli_health_check = if clear {
+        Some(None)
+    } else if let Some(url) = url {
+        Some(Some(HealthCheck::Http { url }))
+    } else {
+        command.map(|command| Some(HealthCheck::Command { command }))
+    };
+    let new_health_check = prompt::choose_health_check(
+        server,
+        cli_health_check,
+        "How can server-room tell that the server is ready?",
+    )?;
+    let mut new_server = server.clone();
+    new_server.health_check = new_health_check.clone();
+    if confirm_edit(
+        force,
+        server,
+        &new_server,
+        "Are you sure you want to change the server's health check?",
+    )? {
+        server_store.set_server_health_check(&server.name, new_health_check)?;
+    }
+
+    Ok(())
+}
+
+// Change the hostname that proxies route to the server
+fn edit_domain(
+    server_store: &ServerStore,
+    server_name: &str,
+    domain: Option<String>,
+    clear: bool,
+    force: bool,
+) -> Result<(), ApplicationError> {
+    let server = server_store.get_one(server_name)?;
+    let cli_domain = if clear { Some(None) } else { domain.map(Some) };
+    let domain_suffix = load_config()?.get_domain_suffix();
+    let new_domain = prompt::choose_server_domain(
+        server,
+        &domain_suffix,
+        cli_domain,
+        "What domain should the server be exposed at?",
+    )?;
+    let mut new_server = server.clone();
+    new_server.domain = new_domain.clone();
+    if confirm_edit(
+        force,
+        server,
+        &new_server,
+        "Are you sure you want to change the server's domain?",
+    )? {
+        server_store.set_server_domain(&server.name, new_domain, &domain_suffix)?;
+    }
+
+    Ok(())
+}
+
+// Change how long the server may be idle before the proxy stops it
+fn edit_idle_timeout(
+    server_store: &ServerStore,
+    server_name: &str,
+    minutes: Option<u64>,
+    clear: bool,
+    force: bool,
+) -> Result<(), ApplicationError> {
+    let server = server_store.get_one(server_name)?;
+    let cli_idle_timeout = if clear { Some(None) } else { minutes.map(Some) };
+    let new_idle_timeout = prompt::choose_idle_timeout(
+        server,
+        cli_idle_timeout,
+        "Should the server use the config's idle timeout?",
+    )?;
+    let mut new_server = server.clone();
+    new_server.idle_timeout = new_idle_timeout;
+    if confirm_edit(
+        force,
+        server,
+        &new_server,
+        "Are you sure you want to change the server's idle timeout?",
+    )? {
+        server_store.set_server_idle_timeout(&server.name, new_idle_timeout)?;
+    }
+
+    Ok(())
+}
+
+// Change how long the server gets to exit after SIGTERM before it is killed
+fn edit_grace_period(
+    server_store: &ServerStore,
+    server_name: &str,
+    seconds: Option<u64>,
+    clear: bool,
+    force: bool,
+) -> Result<(), ApplicationError> {
+    let server = server_store.get_one(server_name)?;
+    let cli_grace_period = if clear { Some(None) } else { seconds.map(Some) };
+    let new_grace_period = prompt::choose_grace_period(
+        server,
+        cli_grace_period,
+        "Should the server use the default grace period?",
+    )?;
+    let mut new_server = server.clone();
+    new_server.grace_period = new_grace_period;
+    if confirm_edit(
+        force,
+        server,
+        &new_server,
+        "Are you sure you want to change the server's grace period?",
+    )? {
+        server_store.set_server_grace_period(&server.name, new_grace_period)?;
+    }
+
+    Ok(())
+}
+
+// Change the servers that are started before the server
+fn edit_depends_on(
+    server_store: &ServerStore,
+    server_name: &str,
+    depends_on: Option<Vec<String>>,
+    force: bool,
+) -> Result<(), ApplicationError> {
+    let server = server_store.get_one(server_name)?;
+    let new_depends_on = prompt::choose_server_dependencies(
+        server_store,
+        server,
+        depends_on,
+        "Which servers need to be started first?",
+    )?;
+    let mut new_server = server.clone();
+    new_server.depends_on = server::normalize_names(&new_depends_on);
+    if confirm_edit(
+        force,
+        server,
+        &new_server,
+        "Are you sure you want to change the server's dependencies?",
+    )? {
+        server_store.set_server_dependencies(&server.name, new_depends_on)?;
+    }
+
+    Ok(())
+}
+
+// Change the note describing the server
+fn edit_description(
+    server_store: &ServerStore,
+    server_name: &str,
+    description: Option<String>,
+    clear: bool,
+    force: bool,
+) -> Result<(), ApplicationError> {
+    let server = server_store.get_one(server_name)?;
+    let cli_description = if clear {
+        Some(None)
+    } else {
+        description.map(Some)
+    };
+    let new_description = prompt::choose_server_description(
+        server,
+        cli_description,
+        "What is the server's description?",
+    )?;
+    let mut new_server = server.clone();
+    new_server.description = new_description.clone();
+    if confirm_edit(
+        force,
+        server,
+        &new_server,
+        "Are you sure you want to change the server's description?",
+    )? {
+        server_store.set_server_description(&server.name, new_description)?;
+    }
+
+    Ok(())
+}
+
+// Change the server's tags
+fn edit_tags(
+    server_store: &ServerStore,
+    server_name: &str,
+    tags: Option<Vec<String>>,
+    force: bool,
+) -> Result<(), ApplicationError> {
+    let server = server_store.get_one(server_name)?;
+    let new_tags = prompt::choose_server_tags(server, tags, "What are the server's tags?")?;
+    let mut new_server = server.clone();
+    new_server.tags = server::normalize_names(&new_tags);
+    if confirm_edit(
+        force,
+        server,
+        &new_server,
+        "Are you sure you want to change the server's tags?",
+    )? {
+        server_store.set_server_tags(&server.name, new_tags)?;
+    }
+
+    Ok(())
+}
+
+// Let the user edit the server store file, restoring the previous version if the changes introduce
+// problems that the store didn't already have
+fn edit_raw() -> Result<(), ApplicationError> {
+    let store_path = get_store_path()?;
+    if store_path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        != Some("toml")
+    {
+        return Err(ApplicationError::RawEditUnsupported(store_path));
+    }
+    let old_servers = load_store()?
+        .get_all()
+        .into_iter()
+        .cloned()
+        .collect::<Vec<_>>();
+    let old_conflicts = ServerStore::find_conflicts(&old_servers);
+    // Make sure that the file exists so that the editor opens it instead of a blank buffer
+    if !store_path.is_file() {
+        load_store()?.flush()?;
+    }
+    let old_contents = fs::read_to_string(&store_path)
+        .map_err(|_| ApplicationError::ParseStore(store_path.clone()))?;
+    backup::create(&store_path)?;
+
+    let restore = || {
+        fs::write(&store_path, &old_contents)
+            .map_err(|_| ApplicationError::WriteStore(store_path.clone()))
+    };
+    if let Err(err) = editor::edit_file(&store_path) {
+        restore()?;
+        return Err(err);
+    }
+    let new_servers = match store::open(store_path.clone())?.load() {
+        Ok(new_servers) => new_servers,
+        Err(err) => {
+            restore()?;
+            return Err(err);
+        }
+    };
+    let new_conflicts = ServerStore::find_conflicts(&new_servers)
+        .into_iter()
+        .filter(|conflict| !old_conflicts.contains(conflict))
+        .collect::<Vec<_>>();
+    if !new_conflicts.is_empty() {
+        restore()?;
+        return Err(ApplicationError::InvalidStoreEdit(new_conflicts));
+    }
+    println!(
+        "Updated {}",
+        store_path.display().to_string().bold().green()
+    );
+    Ok(())
+}
+
+// Return the names of every server in the store
+fn get_server_names() -> Result<HashSet<String>, ApplicationError> {
+    Ok(load_store()?
+        .get_all()
+        .into_iter()
+        .map(|server| server.name.clone())
+        .collect())
+}
+
+// Add a server for each of a monorepo's workspace packages using its default start script
+// Fixed ports are assigned consecutively, starting at the provided port
+fn add_packages(
+    server_store: &ServerStore,
+    packages: Vec<Project>,
+    port: Option<PortChoice>,
+    strict: bool,
+) -> Result<(), ApplicationError> {
+    let free_ports = server_store.get_free_ports(load_config()?.get_port_range(), packages.len());
+    let mut servers = vec![];
+    for (index, package) in packages.into_iter().enumerate() {
+        check_node_version(&package, strict)?;
+        let start_script = package.get_default_start_script()?;
+        let start_command = package.get_start_command(&start_script);
+        let port = match port {
+            Some(PortChoice::Fixed(port)) => index
+                .try_into()
+                .ok()
+                .and_then(|index| port.checked_add(index))
+                .ok_or(ApplicationError::NoFreePort)?,
+            _ => prompt::choose_port(
+                port,
+                free_ports.get(index).copied(),
+                format!("What port does {} listen on?", package.name).as_str(),
+            )?,
+        };
+        let existing = server_store
+            .get_all()
+            .into_iter()
+            .chain(servers.iter())
+            .find(|server| server.port == port);
+        if let Some(existing) = existing {
+            return Err(ApplicationError::DuplicateServerPort {
+                port,
+                existing: existing.name.clone(),
+            });
+        }
+        servers.push(Server::from_project(package, start_command, port));
+    }
+    for server in servers.iter() {
+        println!(
+            "Adding {} ({}) on port {}",
+            server.name.bold().green(),
+            server.start_command.cyan(),
+            server.port.to_string().yellow()
+        );
+    }
+    server_store.add_servers(servers)
+}
+
+// Warn when the installed Node version doesn't satisfy the project's "engines" field, or fail if
+// strict is true
+fn check_node_version(project: &Project, strict: bool) -> Result<(), ApplicationError> {
+    let mismatch = match engines::check_node(project) {
+        Some(mismatch) => mismatch,
+        None => return Ok(()),
+    };
+    if strict {
+        return Err(ApplicationError::UnsupportedNodeVersion {
+            project: project.name.clone(),
+            required: mismatch.required,
+            installed: mismatch.installed,
+        });
+    }
+    eprintln!(
+        "{}: {} for {}",
+        "Warning".bold().yellow(),
+        mismatch,
+        project.name.bold()
+    );
+    Ok(())
+}
+
+// Tell the user how long the servers usually take to be ready
+fn print_startup_times(
+    server_store: &ServerStore,
+    server_names: &[&str],
+) -> Result<(), ApplicationError> {
+    for server_name in server_names {
+        if let Some(startup_summary) = server_store.get_one(server_name)?.get_startup_summary() {
+            println!(
+                "Waiting for {}, which is {}",
+                server_name.bold(),
+                startup_summary
+            );
+        }
+    }
+    Ok(())
+}
+
+// Look for the server's project in the servers directory if its directory is missing, and offer to
+// move the server to the project's new location
+fn relink_moved_server(
+    server_store: &mut ServerStore,
+    server_name: &str,
+) -> Result<(), ApplicationError> {
+    let server = server_store.get_one(server_name)?;
+    if server.dir.is_dir() {
+        return Ok(());
+    }
+    let config = load_config()?;
+    let servers_dir = match config.get_servers_dir() {
+        Some(servers_dir) => servers_dir,
+        None => return Ok(()),
+    };
+    // Skip the directories of other servers
+    let new_dir = scan::find_moved_project(&servers_dir, server, config.get_package_manager())
+        .into_iter()
+        .find(|dir| server_store.get_all().iter().all(|other| &other.dir != dir));
+    let new_dir = match new_dir {
+        Some(new_dir) => new_dir,
+        None => return Ok(()),
+    };
+
+    eprintln!(
+        "{}: {:?} doesn't exist, but {} might have moved to {:?}",
+        "Warning".bold().yellow(),
+        server.dir,
+        server.name.bold(),
+        new_dir
+    );
+    if prompt::confirm_relink("Do you want to move the server there?")? {
+        backup::set_pending(true);
+        server_store.set_server_dir(server_name, new_dir.clone())?;
+        server_store.get_one_mut(server_name)?.dir = new_dir;
+    }
+    Ok(())
+}
+
+// Offer to install the server's dependencies when they are missing or out of date
+fn install_missing_dependencies(server: &Server) -> Result<(), ApplicationError> {
+    let reason = match server.get_install_reason() {
+        Some(reason) => reason,
+        None => return Ok(()),
+    };
+    let install_command = server
+        .package_manager
+        .unwrap_or_default()
+        .get_install_command();
+    eprintln!(
+        "{}: {} for {}",
+        "Warning".bold().yellow(),
+        reason,
+        server.name.bold()
+    );
+    if prompt::confirm_install(&format!(
+        "Do you want to run {} first?",
+        install_command.bold().cyan()
+    ))? {
+        server.install_dependencies()?;
+    }
+    Ok(())
+}
+
+// Remove the hosts file entries of servers that were removed
+fn remove_host_entries(servers: &[&Server]) -> Result<(), ApplicationError> {
+    let config = load_config()?;
+    let domain_suffix = config.get_domain_suffix();
+    let domains = servers
+        .iter()
+        .map(|server| server.get_domain(&domain_suffix))
+        .collect::<Vec<_>>();
+    if let Err(err) = hosts::remove(&config.get_hosts_file(), &domains) {
+        eprintln!("{}: {}", "Warning".bold().yellow(), err);
+    }
+    Ok(())
+}
+
+// Set or unset a config value
+// Changing the storage backend changes the default store file, so the servers are moved to the new
+// file instead of seeming to disappear
+fn set_config_value(key: &str, value: Option<&str>) -> Result<(), ApplicationError> {
+    let config_path = get_config_path()?;
+    // The storage backend doesn't affect store files provided on the command line
+    if key != "storage" || STORE_PATH.get().is_some() {
+        return Config::set_value(&config_path, key, value);
+    }
+
+    let previous_value = Config::get_value(&config_path, key)?;
+    let old_store_path = get_default_store_path()?;
+    Config::set_value(&config_path, key, value)?;
+    let new_store_path = get_default_store_path()?;
+    if new_store_path == old_store_path {
+        return Ok(());
+    }
+    if let Err(err) = move_servers(&old_store_path, &new_store_path) {
+        // Keep using the old store file because its servers couldn't be moved
+        Config::set_value(&config_path, key, previous_value.as_deref())?;
+        return Err(err);
+    }
+    Ok(())
+}
+
+// Copy the servers from the old store file to the new one unless the new one already has servers
+fn move_servers(old_store_path: &Path, new_store_path: &Path) -> Result<(), ApplicationError> {
+    let servers = store::open(old_store_path.to_path_buf())?.load()?;
+    let new_store = store::open(new_store_path.to_path_buf())?;
+    if servers.is_empty() {
+        return Ok(());
+    }
+    if !new_store.load()?.is_empty() {
+        eprintln!(
+            "{}: {:?} already contains servers, so the servers in {:?} were left there",
+            "Warning".bold().yellow(),
+            new_store_path,
+            old_store_path
+        );
+        return Ok(());
+    }
+    let count = servers.len();
+    new_store.save(servers)?;
+    println!(
+        "Moved {} servers from {:?} to {:?}",
+        count.to_string().bold(),
+        old_store_path,
+        new_store_path
+    );
+    Ok(())
+}
+
+// Load the server store
+fn load_store() -> Result<ServerStore, ApplicationError> {
+    ServerStore::load(get_store_path()?)
+}
+
+// Load the operation journal
+fn load_journal() -> Result<Journal, ApplicationError> {
+    Journal::load(get_store_path()?.with_file_name("journal.toml"))
+}
+
+// Load the config
+fn load_config() -> Result<Config, ApplicationError> {
+    Config::load(get_config_path()?)
+}
+
+// Ask the user to confirm an edit to a server after showing how its record will change
+fn confirm_edit(
+    force: bool,
+    server: &Server,
+    new_server: &Server,
+    prompt: &str,
+) -> Result<bool, ApplicationError> {
+    let diff = diff::render_servers(&[server], &[new_server])?;
+    prompt::confirm_change(force, &diff, prompt)
+}
+
+// Load the runtime state
+fn load_runtime_state() -> Result<RuntimeState, ApplicationError> {
+    RuntimeState::load(get_runtime_state_path()?)
+}
+
+pub fn main() {
+    let exit_code = match run() {
+        Ok(_) => 0,
+        Err(err) => {
+            // Generate user-facing suggestions based on the error
+            let suggestion: Option<String> = match &err {
+                ApplicationError::ProjectDirs => None,
+                #[cfg(not(feature = "sqlite"))]
+                ApplicationError::SqliteUnsupported(_) => Some(format!("Reinstall server-room with the {} feature or change the {} in the config file.", "sqlite".bold().cyan(), "storage".bold().cyan())),
+                ApplicationError::ParseConfig(_) => Some("Make sure that the config file contains valid TOML.".to_string()),
+                ApplicationError::WriteConfig(_) => Some("Make sure that the config file is writable.".to_string()),
+                ApplicationError::InvalidConfigValue { key, .. } => Some(format!("Check the type of the value that {} expects.", key.bold().cyan())),
+                ApplicationError::NoServersDir => Some(format!(
+                    "Try adding a servers directory to the config file or passing the directory to scan.\n\n    {}",
+                    "server-room scan ~/code".bold().cyan()
+                )),
+                ApplicationError::WriteStore(_) => Some("Make sure that the server store file is writable.".to_string()),
+                ApplicationError::ParseStore(_) => Some("Make sure that the server store file contains valid TOML.".to_string()),
+                ApplicationError::StringifyStore => None,
+                ApplicationError::RawEditUnsupported(_) => Some(format!("Export the servers to a TOML store with `{}`", "server-room export".bold().cyan())),
+                ApplicationError::RunEditor(_) => Some(format!("Set the {} environment variable to the command that opens your editor.", "EDITOR".bold().cyan())),
+                ApplicationError::InvalidStoreEdit(_) => Some(format!("Fix the problems and try again with `{}`", "server-room edit --raw".bold().cyan())),
+                ApplicationError::ReadPackageJson(project) => Some(format!(
+                    "Try creating a new npm project in this project directory.\n\n    cd {:?}\n    npm init\n\nOr provide a shell command that starts the server with `{}`.",
+                    project.dir,
+                    "--command".bold().cyan()
+                )),
+                ApplicationError::MalformedPackageJson { .. } => Some("Try making sure that your package.json contains valid JSON and that the \"scripts\" property is an object with at least one key. For example:\n\n    \"scripts\": {\n        \"start\": \"node app.js\"\n    }".to_string()),
+                ApplicationError::WriteRuntimeState(_) => Some("Make sure that the runtime state file is writable.".to_string()),
+                ApplicationError::ParseRuntimeState(_) => Some("Make sure that the runtime state file contains valid TOML.".to_string()),
+                ApplicationError::WriteLog(_) => Some(format!("Make sure that the log file is writable or run the server with `{}`.", "--no-log".bold().cyan())),
+                ApplicationError::ReadLog(_) => Some("Make sure that the log file is readable.".to_string()),
+                ApplicationError::NoLogs(server) => Some(format!(
+                    "Try running the server first.\n\n    {}",
+                    format!("server-room run {}", server).bold().cyan()
+                )),
+                ApplicationError::MalformedCargoToml { .. } => Some("Try making sure that your Cargo.toml contains valid TOML.".to_string()),
+                ApplicationError::ReadMakefile(_) => Some("Make sure that the Makefile is readable.".to_string()),
+                ApplicationError::ReadProcfile(_) => Some("Make sure that the project has a readable Procfile.".to_string()),
+                ApplicationError::ReadEnvFile(_) => Some("Make sure that the env file exists and is readable.".to_string()),
+                ApplicationError::ParseEnvFile { .. } => Some("Make sure that each line of the env file looks like KEY=value.".to_string()),
+                ApplicationError::NoEnvFiles(_) => Some(format!("Try creating a {} file in the project directory.", ".env".bold().cyan())),
+                ApplicationError::ParsePath(_) => None,
+                ApplicationError::NonExistentScript {
+                    project,
+                    script,
+                } => {
+                    let mut corpus = CorpusBuilder::new().finish();
+                    project.get_start_scripts().unwrap_or_else(|_| vec![]).iter().for_each(|script| {
+                        corpus.add_text(script.name.as_str())
+                    });
+                    let results = corpus.search(script, 0f32);
+                    let suggestion = results.first().map(|result| result.text.clone());
+                    Some(match suggestion {
+                        Some(suggestion) if suggestion.contains(' ') => format!("Did you mean `{}`?", format!("--start-script \"{}\"", suggestion).bold().cyan()),
+                        Some(suggestion) => format!("Did you mean `{}`?", format!("--start-script {}", suggestion).bold().cyan()),
+                        None => format!("Try adding the script {} to your package.json.", script)
+                    })
+                },
+                ApplicationError::NoStartScripts(_) => Some(format!("Provide the start command with {}, or declare it as {} in {}.", "server-room add --command".bold().cyan(), "start_command".bold(), project_config::FILE_NAME.bold())),
+                ApplicationError::ParseProjectConfig(_) => Some(format!("Make sure that the file is valid TOML and only contains the keys {}.", "name, start_command, port, env, env_files, and health_check".bold())),
+                ApplicationError::RunScript(_) => Some("Make sure that the command is spelled correctly and is in the path.".to_string()),
+                ApplicationError::RunSubcommand(_) => Some("Make sure that the server-room executable hasn't been moved or deleted.".to_string()),
+                ApplicationError::UnsupportedNodeVersion { required, .. } => Some(format!("Install a matching version with a Node version manager like nvm with `{}`, or remove the {} flag to run it anyway.", format!("nvm install \"{}\"", required).bold().cyan(), "--strict".bold().cyan())),
+                ApplicationError::CheckOutdated(_) => Some("Make sure that the server's dependencies are installed and that the package registry is reachable.".to_string()),
+                ApplicationError::OutdatedUnsupported(package_manager) => Some(format!("Run `{}` in the project instead.", format!("{} outdated", package_manager).bold().cyan())),
+                ApplicationError::InstallFailures(servers) => Some(format!("Fix the errors above, then try again with `{}`", format!("server-room install {}", servers.join(" ")).bold().cyan())),
+                ApplicationError::InstallDependencies(server) => Some(format!("Fix the errors above, or decline to install the dependencies to run {} anyway.", server.bold())),
+                ApplicationError::NonExistentServer(server) => {
+                    let suggested_server = load_store().ok().and_then(|server_store| {
+                        server_store.get_closest_server_name(server)
+                    });
+                    Some(match suggested_server {
+                        Some(suggestion) => format!("Did you mean `{}`?", suggestion.bold().cyan()),
+                        None => "Try a different server name.".to_string(),
+                    })
+                },
+                ApplicationError::ServerAlreadyRunning(server) => Some(format!(
+                    "Try stopping the server first.\n\n    {}",
+                    format!("server-room stop --server {}", server).bold().cyan()
+                )),
+                ApplicationError::ServerNotRunning(server) => Some(format!(
+                    "Try starting the server in the background first.\n\n    {}",
+                    format!("server-room run --detach --server {}", server).bold().cyan()
+                )),
+                ApplicationError::StopProcess(pid) => Some(format!("Make sure that you have permission to signal process {}.", pid)),
+                ApplicationError::InvalidPid(_) => Some("Try deleting the runtime state file, which forgets which servers are running in the background.".to_string()),
+                ApplicationError::PortNotInUse(_) => Some("Make sure that lsof is installed so that the process can be found.".to_string()),
+                ApplicationError::DependencyCycle(cycle) => Some(format!("Try removing one of the dependencies.\n\n    {}", format!("server-room edit depends-on {}", cycle[0]).bold().cyan())),
+                ApplicationError::InvalidHealthCheckUrl(_) => Some(format!("Health check URLs must look like `{}`", "http://localhost:3000/health".bold().cyan())),
+                ApplicationError::NotReady { server, .. } => Some(format!("Make sure that the server is running and check its output.\n\n    {}", format!("server-room logs --server {}", server).bold().cyan())),
+                ApplicationError::OpenBrowser(url) => Some(format!("Try opening {} manually.", url.bold().cyan())),
+                ApplicationError::NoFreePort => Some(format!(
+                    "Try widening the range of ports with {} and {} in the config file or providing a port with `{}`.",
+                    "min_port".bold().cyan(),
+                    "max_port".bold().cyan(),
+                    "--port".bold().cyan()
+                )),
+                ApplicationError::EmptyServerName => Some(format!("Try providing a non-empty server name with `{}`", "--name".bold().cyan())),
+                ApplicationError::DuplicateServerName(_) => Some(format!("Try giving the new server a unique name with `{}`", "--name".bold().cyan())),
+                ApplicationError::DuplicateServerPort { .. } => Some(format!("Try giving the server a different port with `{}`", "--port".bold().cyan())),
+                ApplicationError::DuplicateServerDomain(_) => Some(format!("Try giving the server a unique domain with `{}`", "--domain".bold().cyan())),
+                ApplicationError::InvalidDomain(_) => Some("Domains can only contain letters, numbers, hyphens, and dots, like api.myproject.test.".to_string()),
+                ApplicationError::MissingStartCommand => Some(format!("Try adding the server with `{}` instead.", "server-room add".bold().cyan())),
+                ApplicationError::DuplicateServerDir { existing, .. } => Some(format!(
+                    "Try editing the existing server instead.\n\n    {}",
+                    format!("server-room edit --server {}", existing.name).bold().cyan()
+                )),
+                ApplicationError::WriteCaddyfile(_) => Some("Make sure that the Caddyfile's directory exists and is writable.".to_string()),
+                ApplicationError::WriteDnsmasqConfig(_) => Some("Make sure that the dnsmasq config's directory exists and is writable.".to_string()),
+                ApplicationError::ParseLaunchConfig(_) => Some(format!("Remove any comments from the launch config, or write to a different file with {}.", "--output".bold().cyan())),
+                ApplicationError::WriteLaunchConfig(_) => Some("Make sure that the launch config's directory is writable.".to_string()),
+                ApplicationError::NoCaddyfile => Some(format!(
+                    "Try adding a {} path to the config file or passing the path with `{}`.",
+                    "caddyfile".bold().cyan(),
+                    "--output".bold().cyan()
+                )),
+                ApplicationError::ReloadCaddy(_) => Some(format!("Make sure that Caddy is running and installed, or change the {} in the config file.", "caddy_reload_command".bold().cyan())),
+                ApplicationError::PushCaddyConfig(_) => Some(format!("Make sure that Caddy is running with its admin API enabled.\n\n    {}", "caddy run".bold().cyan())),
+                ApplicationError::NoTunnelProvider => Some("Try installing cloudflared or ngrok.".to_string()),
+                ApplicationError::RunTunnel(provider) => Some(format!("Make sure that {} is installed and configured.", provider)),
+                ApplicationError::NoLanIp => Some("Make sure that this machine is connected to a network.".to_string()),
+                ApplicationError::GenerateQrCode => None,
+                ApplicationError::RunMkcert(_) => Some(format!("Make sure that mkcert is installed and its CA is set up.\n\n    {}", "mkcert -install".bold().cyan())),
+                ApplicationError::LoadCert(_) => Some(format!("Try minting the certificates again.\n\n    {}", "server-room tls setup".bold().cyan())),
+                ApplicationError::ConfigureTls => None,
+                ApplicationError::ReadHosts(_) => Some(format!("Make sure that the hosts file exists or change the {} in the config file.", "hosts_file".bold().cyan())),
+                ApplicationError::WriteHosts(_) => Some("Make sure that you can write the hosts file with sudo.".to_string()),
+                ApplicationError::UnterminatedHostsBlock(_) => Some(format!("Add a `{}` line after server-room's entries in the hosts file, or remove its begin marker.", "# END server-room managed block".bold().cyan())),
+                ApplicationError::ReadPm2Config(_) => Some("Make sure that the ecosystem file exists and is readable.".to_string()),
+                ApplicationError::ParsePm2Config(_) => Some("Make sure that the ecosystem file defines an array of apps and that node is installed to evaluate JavaScript ecosystem files.".to_string()),
+                ApplicationError::ReadComposeFile(_) => Some("Make sure that the compose file exists and is readable.".to_string()),
+                ApplicationError::ParseComposeFile(_) => Some("Make sure that the compose file is valid YAML and defines services.".to_string()),
+                ApplicationError::ReadCaddyfile(_) => Some("Make sure that the Caddyfile exists and is readable.".to_string()),
+                ApplicationError::ReadExport(_) => Some("Make sure that the exported file exists and is readable.".to_string()),
+                ApplicationError::ParseExport(_) => Some(format!("Make sure that the file was created by `{}`", "server-room export".bold().cyan())),
+                ApplicationError::WriteBackup(_) => Some("Make sure that the backups directory next to the server store is writable.".to_string()),
+                ApplicationError::RestoreBackup(_) => Some("Make sure that the backup is readable and that the server store is writable.".to_string()),
+                ApplicationError::NonExistentBackup(_) => Some(format!("List the available backups with `{}`", "server-room restore --list".bold().cyan())),
+                ApplicationError::NoBackups => Some("Backups are created automatically whenever the servers change.".to_string()),
+                ApplicationError::ParseJournal(_) => Some("Try deleting the journal file, which forgets the operations that can be undone.".to_string()),
+                ApplicationError::WriteJournal(_) => Some("Make sure that the journal file is writable.".to_string()),
+                ApplicationError::NothingToUndo => None,
+                ApplicationError::ParseRunHistory(_) => Some("Try deleting the run history file, which forgets how servers exited.".to_string()),
+                ApplicationError::WriteRunHistory(_) => Some("Make sure that the run history file is writable.".to_string()),
+                ApplicationError::RunGit(_) => Some("Make sure that git is installed, that your git user is configured, and that you can access the remote.".to_string()),
+                ApplicationError::SyncConflict(_) => Some(format!("Edit the servers on one machine to match the other, or overwrite the local servers with the remote ones.\n\n    {}", "git -C <store directory> reset --hard @{u}".bold().cyan())),
+                ApplicationError::SyncUnsupported(_) => Some(format!("Export the servers to a TOML store with `{}`", "server-room export".bold().cyan())),
+                ApplicationError::NoTaggedServers(_) => Some(format!("Try adding the tag to a server first with `{}`", "server-room edit tags".bold().cyan())),
+                ApplicationError::NoRunHistory => Some("Try running a server first.\n\n    server-room run".to_string()),
+                ApplicationError::NoServers => Some("Try adding a new server first.\n\n    server-room add".to_string()),
+                ApplicationError::NoArchivedServers => Some(format!("Archive a server first with {}.", "server-room archive".bold().cyan())),
+                ApplicationError::DoctorFailed(_) => None,
+                ApplicationError::MissingInput(_) => Some(format!("Remove the {} flag, unset the {} environment variable, and unset the {} config key to answer prompts interactively.", "--no-input".bold().cyan(), "SERVER_ROOM_NONINTERACTIVE".bold().cyan(), "no_input".bold().cyan())),
+                ApplicationError::BindSocket(_) => Some("Make sure that the control socket's directory is writable.".to_string()),
+                ApplicationError::BindPort(port) => Some(format!("Make sure that nothing else is listening on port {} and that you have permission to use it.\n\n    {}", port, format!("server-room kill-port {}", port).bold().cyan())),
+                ApplicationError::WriteHttpToken(_) => Some("Make sure that the control socket's directory is writable.".to_string()),
+                ApplicationError::DaemonAlreadyRunning(_) => Some(format!("Check which servers the daemon is supervising with `{}`", "server-room status".bold().cyan())),
+                ApplicationError::DaemonRequest(_) => None,
+                ApplicationError::DaemonConnection => Some(format!("Try restarting the daemon.\n\n    {}", "server-room daemon".bold().cyan())),
+                ApplicationError::DrawDashboard => Some("Make sure that server-room is running in an interactive terminal.".to_string()),
+                ApplicationError::InquireError(_) => None,
+                ApplicationError::InvalidCommand(command) => {
+                    let mut corpus = CorpusBuilder::new().finish();
+                    corpus.add_text("config");
+                    corpus.add_text("add");
+                    corpus.add_text("adopt");
+                    corpus.add_text("scan");
+                    corpus.add_text("import");
+                    corpus.add_text("export");
+                    corpus.add_text("restore");
+                    corpus.add_text("undo");
+                    corpus.add_text("sync");
+                    corpus.add_text("edit");
+                    corpus.add_text("run");
+                    corpus.add_text("open");
+                    corpus.add_text("scripts");
+                    corpus.add_text("install");
+                    corpus.add_text("outdated");
+                    corpus.add_text("wait");
+                    corpus.add_text("stop");
+                    corpus.add_text("kill-port");
+                    corpus.add_text("logs");
+                    corpus.add_text("history");
+                    corpus.add_text("remove");
+                    corpus.add_text("rm");
+                    corpus.add_text("clone");
+                    corpus.add_text("archive");
+                    corpus.add_text("unarchive");
+                    corpus.add_text("find");
+                    corpus.add_text("info");
+                    corpus.add_text("pin");
+                    corpus.add_text("unpin");
+                    corpus.add_text("prune");
+                    corpus.add_text("list");
+                    corpus.add_text("ls");
+                    corpus.add_text("stats");
+                    corpus.add_text("ui");
+                    corpus.add_text("top");
+                    corpus.add_text("daemon");
+                    corpus.add_text("status");
+                    corpus.add_text("proxy");
+                    corpus.add_text("doctor");
+                    corpus.add_text("caddy");
+                    corpus.add_text("dnsmasq");
+                    corpus.add_text("vscode-launch");
+                    corpus.add_text("share");
+                    corpus.add_text("qr");
+                    corpus.add_text("tls");
+                    corpus.add_text("hosts");
+                    corpus.add_text("path");
+                    corpus.add_text("init");
+                    corpus.add_text("completions");
+                    let results = corpus.search(command.as_str(), 0.5f32);
+                    Some(match results.first() {
+                        Some(result) => format!("Did you mean `{}`?", format!("server-room {}", result.text).bold().cyan()),
+                        None => "Try `server-room --help` to see available subcommands.".to_string(),
+                    })
+                }
+            };
+
+            eprintln!("{}: {}", "Error".bold().red(), err);
+            if let Some(suggestion) = suggestion {
+                eprintln!("{}", suggestion);
+            }
+            err.get_category().get_exit_code()
+        }
+    };
+
+    std::process::exit(exit_code);
+}
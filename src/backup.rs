@@ -0,0 +1,136 @@
+use super::error::ApplicationError;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// The number of backups to keep before deleting the oldest ones
+const MAX_BACKUPS: usize = 50;
+
+// The format of the timestamp in backup file names, which sorts chronologically
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S%.3f";
+
+// Whether the next save of the store should back it up first
+static PENDING: AtomicBool = AtomicBool::new(false);
+
+// This struct represents a copy of the server store file, TOML or SQLite, from before it was changed
+#[derive(Clone)]
+pub struct Backup {
+    // The timestamp in the backup's file name, which identifies it on the command line
+    pub id: String,
+    pub path: PathBuf,
+    pub created_at: DateTime<Local>,
+}
+
+impl fmt::Display for Backup {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "{} ({})",
+            self.created_at.format("%Y-%m-%d %H:%M:%S"),
+            self.id
+        )
+    }
+}
+
+// Return the directory that holds the store's backups, which lives next to the store file
+fn get_backups_dir(store_path: &Path) -> PathBuf {
+    store_path.with_file_name("backups")
+}
+
+// Return the extension of the store's backups, which matches the store file's so that a backup of a
+// SQLite store is loaded as one
+fn get_extension(store_path: &Path) -> &str {
+    store_path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or("toml")
+}
+
+// Back up the store the next time that it is saved
+// Only commands that the user runs to change servers back up the store, so that bookkeeping like
+// run counts doesn't push the backups of real changes out of the retention limit
+pub fn set_pending(pending: bool) {
+    PENDING.store(pending, Ordering::Relaxed);
+}
+
+// Back up the store before it is saved if a backup is pending, which only happens once per command
+// so that the backup holds the store from before the command ran
+pub fn create_pending(store_path: &Path) -> Result<(), ApplicationError> {
+    if PENDING.swap(false, Ordering::Relaxed) {
+        create(store_path)?;
+    }
+    Ok(())
+}
+
+// Copy the store file into the backups directory if it exists and differs from the newest backup,
+// deleting the oldest backups beyond the retention limit
+pub fn create(store_path: &Path) -> Result<(), ApplicationError> {
+    let contents = match fs::read(store_path) {
+        Ok(contents) => contents,
+        Err(_) => return Ok(()),
+    };
+    let is_backed_up = list(store_path)
+        .first()
+        .and_then(|newest| fs::read(&newest.path).ok())
+        .is_some_and(|newest_contents| newest_contents == contents);
+    if is_backed_up {
+        return Ok(());
+    }
+
+    let backups_dir = get_backups_dir(store_path);
+    let backup_path = backups_dir.join(format!(
+        "servers-{}.{}",
+        Local::now().format(TIMESTAMP_FORMAT),
+        get_extension(store_path)
+    ));
+    fs::create_dir_all(&backups_dir)
+        .and_then(|_| fs::write(&backup_path, contents))
+        .map_err(|_| ApplicationError::WriteBackup(backup_path.clone()))?;
+
+    for backup in list(store_path).iter().skip(MAX_BACKUPS) {
+        let _ = fs::remove_file(&backup.path);
+    }
+    Ok(())
+}
+
+// Return the store's backups, newest first
+pub fn list(store_path: &Path) -> Vec<Backup> {
+    let entries = match fs::read_dir(get_backups_dir(store_path)) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+    let suffix = format!(".{}", get_extension(store_path));
+    let mut backups = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let id = path
+                .file_name()?
+                .to_str()?
+                .strip_prefix("servers-")?
+                .strip_suffix(suffix.as_str())?
+                .to_string();
+            let created_at = NaiveDateTime::parse_from_str(&id, TIMESTAMP_FORMAT).ok()?;
+            Some(Backup {
+                id,
+                created_at: Local.from_local_datetime(&created_at).earliest()?,
+                path,
+            })
+        })
+        .collect::<Vec<_>>();
+    backups.sort_by(|backup1, backup2| backup2.path.cmp(&backup1.path));
+    backups
+}
+
+// Replace the store file with the backup, backing up the current store first so that the
+// restore can be rolled back too
+pub fn restore(store_path: &Path, backup: &Backup) -> Result<(), ApplicationError> {
+    // Read the backup first because creating a new backup may delete it
+    let contents =
+        fs::read(&backup.path).map_err(|_| ApplicationError::RestoreBackup(backup.path.clone()))?;
+    create(store_path)?;
+    fs::write(store_path, contents)
+        .map_err(|_| ApplicationError::RestoreBackup(backup.path.clone()))
+}
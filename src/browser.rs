@@ -0,0 +1,21 @@
+use super::error::ApplicationError;
+use std::process::{Command, Stdio};
+
+// Open the URL in the user's default browser
+pub fn open(url: &str) -> Result<(), ApplicationError> {
+    let program = if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    let status = Command::new(program)
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    match status {
+        Ok(status) if status.success() => Ok(()),
+        _ => Err(ApplicationError::OpenBrowser(url.to_string())),
+    }
+}
@@ -0,0 +1,237 @@
+use super::error::ApplicationError;
+use super::health;
+use super::import::ImportedServer;
+use super::server::Server;
+use super::shell::Shell;
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::path::Path;
+use std::process::Stdio;
+
+// The command that reloads Caddy when the config doesn't specify one
+const DEFAULT_RELOAD_COMMAND: &str = "caddy reload --config \"$CADDYFILE\" --adapter caddyfile";
+
+// Generate a Caddyfile that reverse proxies each server's domain to its port
+pub fn generate_caddyfile(servers: &[&Server], domain_suffix: &str) -> String {
+    let mut servers = servers.to_vec();
+    servers.sort_by_key(|server| server.name.as_str());
+    servers
+        .iter()
+        .map(|server| {
+            // Use the server's own certificate instead of Caddy's internal CA if it has one
+            let tls = match &server.tls {
+                Some(tls_cert) => format!(
+                    "\ttls {} {}\n",
+                    tls_cert.cert.display(),
+                    tls_cert.key.display()
+                ),
+                None => String::new(),
+            };
+            format!(
+                "{} {{\n{}\treverse_proxy localhost:{}\n}}\n",
+                server.get_domain(domain_suffix),
+                tls,
+                server.port
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Write the generated Caddyfile to the specified path
+pub fn write_caddyfile(
+    servers: &[&Server],
+    domain_suffix: &str,
+    output_path: &Path,
+) -> Result<(), ApplicationError> {
+    fs::write(output_path, generate_caddyfile(servers, domain_suffix))
+        .map_err(|_| ApplicationError::WriteCaddyfile(output_path.to_path_buf()))
+}
+
+// Tell the running Caddy instance to load the Caddyfile
+pub fn reload(caddyfile: &Path, command: Option<&str>) -> Result<(), ApplicationError> {
+    let command = command.unwrap_or(DEFAULT_RELOAD_COMMAND);
+    let status = Shell::get_default()
+        .build_command(command)
+        .env("CADDYFILE", caddyfile)
+        .stdin(Stdio::null())
+        .status();
+    match status {
+        Ok(status) if status.success() => Ok(()),
+        _ => Err(ApplicationError::ReloadCaddy(command.to_string())),
+    }
+}
+
+// Generate Caddy JSON config that reverse proxies each server's domain to its port
+pub fn generate_json_config(servers: &[&Server], domain_suffix: &str) -> Value {
+    let mut servers = servers.to_vec();
+    servers.sort_by_key(|server| server.name.as_str());
+    let routes = servers
+        .iter()
+        .map(|server| {
+            json!({
+                "match": [{ "host": [server.get_domain(domain_suffix)] }],
+                "handle": [{
+                    "handler": "reverse_proxy",
+                    "upstreams": [{ "dial": format!("localhost:{}", server.port) }],
+                }],
+            })
+        })
+        .collect::<Vec<_>>();
+    // Caddy presents loaded certificates for their domains instead of managing its own
+    let certificates = servers
+        .iter()
+        .filter_map(|server| server.tls.as_ref())
+        .map(|tls_cert| json!({ "certificate": tls_cert.cert, "key": tls_cert.key }))
+        .collect::<Vec<_>>();
+    json!({
+        "apps": {
+            "http": {
+                "servers": {
+                    "server-room": {
+                        "listen": [":443"],
+                        "routes": routes,
+                    },
+                },
+            },
+            "tls": {
+                "certificates": { "load_files": certificates },
+            },
+        },
+    })
+}
+
+// Replace the config of the running Caddy instance with the generated JSON config
+pub fn push_config(
+    servers: &[&Server],
+    domain_suffix: &str,
+    admin_url: &str,
+) -> Result<(), ApplicationError> {
+    let push_error = || ApplicationError::PushCaddyConfig(admin_url.to_string());
+    let (address, _) = health::parse_url(admin_url).ok_or_else(push_error)?;
+    let body = generate_json_config(servers, domain_suffix).to_string();
+    let request = format!(
+        "POST /load HTTP/1.0\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        address,
+        body.len(),
+        body
+    );
+    let mut stream = TcpStream::connect(&address).map_err(|_| push_error())?;
+    stream
+        .write_all(request.as_bytes())
+        .map_err(|_| push_error())?;
+
+    // Caddy responds with 200 once the new config is loaded
+    let mut response = String::new();
+    stream
+        .read_to_string(&mut response)
+        .map_err(|_| push_error())?;
+    match response.split_whitespace().nth(1) {
+        Some("200") => Ok(()),
+        _ => Err(push_error()),
+    }
+}
+
+// Read the sites in an existing Caddyfile that reverse proxy to a local port
+// Caddyfiles don't say how to start the upstream servers, so the sites don't have a start command
+// yet, and they use the Caddyfile's directory as their project directory
+pub fn read_sites(
+    caddyfile: &Path,
+    domain_suffix: &str,
+) -> Result<Vec<ImportedServer>, ApplicationError> {
+    let caddyfile = fs::canonicalize(caddyfile)
+        .map_err(|_| ApplicationError::ReadCaddyfile(caddyfile.to_path_buf()))?;
+    let content = fs::read_to_string(&caddyfile)
+        .map_err(|_| ApplicationError::ReadCaddyfile(caddyfile.clone()))?;
+    let dir = caddyfile
+        .parent()
+        .ok_or_else(|| ApplicationError::ReadCaddyfile(caddyfile.clone()))?;
+
+    let mut sites = vec![];
+    let mut depth = 0;
+    // The hostname of the site block being read and whether its upstream has been found
+    let mut site: Option<(String, bool)> = None;
+    for line in content.lines() {
+        let line = line.split(" #").next().unwrap_or_default();
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+        let line = line.replace('{', " { ").replace('}', " } ");
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+        let mut directive = tokens.as_slice();
+        if depth == 0 {
+            if let Some(open) = tokens.iter().position(|token| *token == "{") {
+                // Global options blocks have no addresses and snippets are named like "(name)"
+                site = tokens[..open]
+                    .first()
+                    .filter(|address| !address.starts_with('('))
+                    .and_then(|address| parse_site_hostname(address))
+                    .map(|hostname| (hostname, false));
+                // Site blocks can be written on a single line
+                directive = &tokens[open + 1..];
+            } else {
+                directive = &[];
+            }
+        }
+        if directive.first() == Some(&"reverse_proxy") {
+            if let Some((hostname, found)) = site.as_mut().filter(|(_, found)| !*found) {
+                if let Some(port) = directive[1..]
+                    .iter()
+                    .find_map(|token| parse_upstream_port(token))
+                {
+                    *found = true;
+                    let name = hostname.split('.').next().unwrap_or_default().to_string();
+                    // Keep the site's address as a custom domain unless it is the default one
+                    let domain = (*hostname != format!("{}{}", name, domain_suffix))
+                        .then(|| hostname.clone());
+                    sites.push(ImportedServer {
+                        name,
+                        dir: dir.to_path_buf(),
+                        start_command: String::new(),
+                        port: Some(port),
+                        domain,
+                    });
+                }
+            }
+        }
+        for token in tokens {
+            match token {
+                "{" => depth += 1,
+                "}" => depth = (depth - 1).max(0),
+                _ => {}
+            }
+        }
+        if depth == 0 && line.contains('}') {
+            site = None;
+        }
+    }
+    Ok(sites)
+}
+
+// Extract the hostname from a site address like "https://api.example.com:443/path"
+fn parse_site_hostname(address: &str) -> Option<String> {
+    let address = address.trim_end_matches(',');
+    let address = address
+        .split_once("://")
+        .map_or(address, |(_, address)| address);
+    let hostname = address.split(['/', ':']).next()?;
+    // Addresses without a hostname like ":8080" and wildcards can't be named after a server
+    if hostname.is_empty() || hostname.contains('*') || hostname.starts_with('{') {
+        return None;
+    }
+    Some(hostname.to_string())
+}
+
+// Extract the port from a local upstream like "localhost:3000", ":3000", or "http://127.0.0.1:3000"
+fn parse_upstream_port(upstream: &str) -> Option<u16> {
+    let upstream = upstream
+        .split_once("://")
+        .map_or(upstream, |(_, upstream)| upstream);
+    let (host, port) = upstream.rsplit_once(':')?;
+    if !["", "localhost", "127.0.0.1", "[::1]", "0.0.0.0"].contains(&host) {
+        return None;
+    }
+    port.trim_end_matches('/').parse().ok()
+}
@@ -0,0 +1,110 @@
+use super::error::ApplicationError;
+use super::project::Project;
+use super::project_kind::ProjectKind;
+use super::script::Script;
+use std::fs;
+use std::path::Path;
+use toml::Value;
+
+// Profiles that cargo defines itself and that don't need to be passed with --profile
+const BUILTIN_PROFILES: [&str; 4] = ["dev", "release", "test", "bench"];
+
+// This struct represents Rust projects with a Cargo.toml file
+pub struct CargoProject;
+
+impl ProjectKind for CargoProject {
+    fn name(&self) -> &'static str {
+        "cargo"
+    }
+
+    fn detect(&self, dir: &Path) -> bool {
+        dir.join("Cargo.toml").is_file()
+    }
+
+    fn get_scripts(&self, project: &Project) -> Result<Vec<Script>, ApplicationError> {
+        get_scripts(&project.dir)
+    }
+}
+
+// Return the cargo commands that could start the Rust project in the directory, based on the
+// binaries and custom profiles declared in its Cargo.toml
+pub fn get_scripts(dir: &Path) -> Result<Vec<Script>, ApplicationError> {
+    let manifest_path = dir.join("Cargo.toml");
+    let malformed = |cause: &str| ApplicationError::MalformedCargoToml {
+        path: manifest_path.clone(),
+        cause: cause.to_string(),
+    };
+    let manifest_content =
+        fs::read_to_string(&manifest_path).map_err(|_| malformed("couldn't be read"))?;
+    let manifest: Value =
+        toml::from_str(&manifest_content).map_err(|_| malformed("contains invalid TOML"))?;
+
+    let mut binaries = vec![];
+    if let Some(package_name) = manifest
+        .get("package")
+        .and_then(|package| package.get("name"))
+        .and_then(|name| name.as_str())
+    {
+        if dir.join("src/main.rs").is_file() {
+            binaries.push(package_name.to_string());
+        }
+    }
+    if let Some(bins) = manifest.get("bin").and_then(|bins| bins.as_array()) {
+        binaries.extend(
+            bins.iter()
+                .filter_map(|bin| bin.get("name").and_then(|name| name.as_str()))
+                .map(|name| name.to_string()),
+        );
+    }
+    if let Ok(entries) = fs::read_dir(dir.join("src/bin")) {
+        binaries.extend(entries.filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()? == "rs" {
+                Some(path.file_stem()?.to_str()?.to_string())
+            } else if path.join("main.rs").is_file() {
+                Some(path.file_name()?.to_str()?.to_string())
+            } else {
+                None
+            }
+        }));
+    }
+    binaries.sort();
+    binaries.dedup();
+
+    let mut profiles = manifest
+        .get("profile")
+        .and_then(|profiles| profiles.as_table())
+        .map(|profiles| {
+            profiles
+                .keys()
+                .filter(|profile| !BUILTIN_PROFILES.contains(&profile.as_str()))
+                .cloned()
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    profiles.sort();
+
+    let mut commands = vec!["cargo run".to_string(), "cargo run --release".to_string()];
+    // Only offer specific binaries when there is more than one to choose from
+    if binaries.len() > 1 {
+        commands.extend(
+            binaries
+                .iter()
+                .map(|binary| format!("cargo run --bin {}", binary)),
+        );
+    }
+    commands.extend(
+        profiles
+            .iter()
+            .map(|profile| format!("cargo run --profile {}", profile)),
+    );
+
+    Ok(commands
+        .into_iter()
+        .map(|command| Script {
+            name: command.clone(),
+            command: command.clone(),
+            start_command: command,
+        })
+        .collect())
+}
@@ -1,13 +1,81 @@
-use clap::{Parser, Subcommand};
+use super::config;
+use super::init::InitShell;
+use super::install;
+use super::port::PortChoice;
+use super::run_history::Period;
+use super::server::RestartPolicy;
+use super::share::TunnelProvider;
+use super::shell;
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
 use std::path::PathBuf;
 
+// The formats that servers can be listed in
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum ListFormat {
+    #[default]
+    Text,
+    Json,
+    Toml,
+}
+
+// The formats that the server store can be exported in
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum ExportFormat {
+    #[default]
+    Toml,
+    Json,
+}
+
+// The server that a command operates on, which can be passed as a positional argument or with
+// -s/--server
+#[derive(Args)]
+#[clap(group(ArgGroup::new("server").args(&["server-name", "server-flag"])))]
+pub struct ServerArg {
+    /// Specifies the server
+    #[clap(value_name = "SERVER")]
+    server_name: Option<String>,
+    /// Specifies the server, like the positional argument
+    #[clap(short = 's', long = "server", value_name = "SERVER")]
+    server_flag: Option<String>,
+}
+
+impl ServerArg {
+    // Return the server, whichever way it was passed
+    pub fn get(self) -> Option<String> {
+        self.server_name.or(self.server_flag)
+    }
+}
+
+// The servers that a command operates on, which can be passed as positional arguments or by
+// repeating -s/--server
+#[derive(Args)]
+#[clap(group(ArgGroup::new("server").multiple(true).args(&["server-name", "server-flag"])))]
+pub struct ServersArg {
+    /// Specifies the servers
+    #[clap(value_name = "SERVER")]
+    server_name: Vec<String>,
+    /// Specifies a server, like the positional arguments, and can be repeated
+    #[clap(short = 's', long = "server", value_name = "SERVER")]
+    server_flag: Vec<String>,
+}
+
+impl ServersArg {
+    // Return the servers, whichever way they were passed
+    pub fn get(self) -> Vec<String> {
+        self.server_name
+            .into_iter()
+            .chain(self.server_flag)
+            .collect()
+    }
+}
+
 #[derive(Subcommand)]
 pub enum Edit {
     /// Edit the server's name
     Name {
-        /// Specifies the server to edit
-        #[clap(short, long)]
-        server: Option<String>,
+        #[clap(flatten)]
+        server: ServerArg,
         /// Specifies the server's new name
         #[clap(long, requires = "server")]
         name: Option<String>,
@@ -18,81 +86,780 @@ pub enum Edit {
 
     /// Edit the server's start script
     StartScript {
-        /// Specifies the server to edit
-        #[clap(short, long)]
-        server: Option<String>,
+        #[clap(flatten)]
+        server: ServerArg,
         /// Specifies the server's new start script
         #[clap(long, requires = "server")]
         start_script: Option<String>,
+        /// Specifies an arbitrary shell command that starts the server instead of a start script
+        #[clap(short, long, requires = "server", conflicts_with = "start-script")]
+        command: Option<String>,
         /// Don't prompt for confirmation,
         #[clap(short, long)]
         force: bool,
     },
 
+    /// Edit the server's project directory after the project moves
+    Path {
+        #[clap(flatten)]
+        server: ServerArg,
+        /// Specifies the server's new project directory
+        #[clap(long, parse(from_os_str), requires = "server")]
+        path: Option<PathBuf>,
+        /// Don't prompt for confirmation
+        #[clap(short, long)]
+        force: bool,
+    },
+
     /// Edit the server's port
     Port {
-        /// Specifies the server to edit
+        #[clap(flatten)]
+        server: ServerArg,
+        /// Specifies the server's new port, or "auto" to pick a free port
+        #[clap(long, requires = "server")]
+        port: Option<PortChoice>,
+        /// Don't prompt for confirmation
         #[clap(short, long)]
-        server: Option<String>,
-        /// Specifies the server's new port
+        force: bool,
+    },
+
+    /// Edit whether the PORT environment variable is set when the server starts
+    InjectPort {
+        #[clap(flatten)]
+        server: ServerArg,
+        /// Specifies whether to set the PORT environment variable
         #[clap(long, requires = "server")]
-        port: Option<u16>,
+        enabled: Option<bool>,
+        /// Don't prompt for confirmation
+        #[clap(short, long)]
+        force: bool,
+    },
+
+    /// Edit the dotenv files loaded when the server starts
+    EnvFile {
+        #[clap(flatten)]
+        server: ServerArg,
+        /// Specifies the comma-separated dotenv files relative to the project directory, or none to clear them
+        #[clap(
+            long = "env-file",
+            parse(from_os_str),
+            value_delimiter = ',',
+            min_values = 0,
+            requires = "server"
+        )]
+        env_files: Option<Vec<PathBuf>>,
+        /// Don't prompt for confirmation
+        #[clap(short, long)]
+        force: bool,
+    },
+
+    /// Edit the server's restart policy
+    Restart {
+        #[clap(flatten)]
+        server: ServerArg,
+        /// Specifies the server's new restart policy
+        #[clap(long, value_enum, requires = "server")]
+        restart: Option<RestartPolicy>,
+        /// Don't prompt for confirmation
+        #[clap(short, long)]
+        force: bool,
+    },
+
+    /// Edit the shell that runs the server's start command
+    Shell {
+        #[clap(flatten)]
+        server: ServerArg,
+        /// Specifies the server's new shell
+        #[clap(long, value_enum, requires = "server")]
+        shell: Option<shell::Shell>,
+        /// Run the server with the shell from the config instead
+        #[clap(long, requires = "server", conflicts_with = "shell")]
+        clear: bool,
+        /// Don't prompt for confirmation
+        #[clap(short, long)]
+        force: bool,
+    },
+
+    /// Edit how to tell that the server is ready
+    HealthCheck {
+        #[clap(flatten)]
+        server: ServerArg,
+        /// Specifies an HTTP URL that responds successfully once the server is ready
+        #[clap(long, requires = "server")]
+        url: Option<String>,
+        /// Specifies a shell command that succeeds once the server is ready
+        #[clap(short, long, requires = "server", conflicts_with = "url")]
+        command: Option<String>,
+        /// Remove the health check and wait for the server's port to accept connections instead
+        #[clap(long, requires = "server", conflicts_with_all = &["url", "command"])]
+        clear: bool,
+        /// Don't prompt for confirmation
+        #[clap(short, long)]
+        force: bool,
+    },
+
+    /// Edit the hostname that proxies route to the server
+    Domain {
+        #[clap(flatten)]
+        server: ServerArg,
+        /// Specifies the server's new domain, like api.myproject.test
+        #[clap(long, requires = "server")]
+        domain: Option<String>,
+        /// Remove the custom domain and derive the domain from the server's name instead
+        #[clap(long, requires = "server", conflicts_with = "domain")]
+        clear: bool,
+        /// Don't prompt for confirmation
+        #[clap(short, long)]
+        force: bool,
+    },
+
+    /// Edit how long the server may be idle before the proxy stops it
+    IdleTimeout {
+        #[clap(flatten)]
+        server: ServerArg,
+        /// Specifies the number of idle minutes, or 0 to never stop the server
+        #[clap(long, requires = "server")]
+        minutes: Option<u64>,
+        /// Remove the server's idle timeout and use the config's idle timeout instead
+        #[clap(long, requires = "server", conflicts_with = "minutes")]
+        clear: bool,
+        /// Don't prompt for confirmation
+        #[clap(short, long)]
+        force: bool,
+    },
+
+    /// Edit how long the server gets to exit after SIGTERM before it is killed
+    GracePeriod {
+        #[clap(flatten)]
+        server: ServerArg,
+        /// Specifies the number of seconds
+        #[clap(long, requires = "server")]
+        seconds: Option<u64>,
+        /// Remove the server's grace period and use the default grace period instead
+        #[clap(long, requires = "server", conflicts_with = "seconds")]
+        clear: bool,
         /// Don't prompt for confirmation
         #[clap(short, long)]
         force: bool,
     },
+
+    /// Edit the servers that are started before the server
+    DependsOn {
+        #[clap(flatten)]
+        server: ServerArg,
+        /// Specifies the comma-separated servers that the server depends on, or none to clear them
+        #[clap(long, value_delimiter = ',', min_values = 0, requires = "server")]
+        depends_on: Option<Vec<String>>,
+        /// Don't prompt for confirmation
+        #[clap(short, long)]
+        force: bool,
+    },
+
+    /// Edit the note describing the server
+    Description {
+        #[clap(flatten)]
+        server: ServerArg,
+        /// Specifies the server's new description
+        #[clap(long, requires = "server")]
+        description: Option<String>,
+        /// Remove the server's description
+        #[clap(long, requires = "server", conflicts_with = "description")]
+        clear: bool,
+        /// Don't prompt for confirmation
+        #[clap(short, long)]
+        force: bool,
+    },
+
+    /// Edit the server's tags
+    Tags {
+        #[clap(flatten)]
+        server: ServerArg,
+        /// Specifies the server's new comma-separated tags
+        #[clap(long, value_delimiter = ',', requires = "server")]
+        tags: Option<Vec<String>>,
+        /// Don't prompt for confirmation
+        #[clap(short, long)]
+        force: bool,
+    },
+}
+
+impl Edit {
+    // Return the server to edit, whichever way it was passed
+    pub fn get_server(&self) -> Option<String> {
+        let (Edit::Name { server, .. }
+        | Edit::StartScript { server, .. }
+        | Edit::Path { server, .. }
+        | Edit::Port { server, .. }
+        | Edit::InjectPort { server, .. }
+        | Edit::EnvFile { server, .. }
+        | Edit::Restart { server, .. }
+        | Edit::Shell { server, .. }
+        | Edit::HealthCheck { server, .. }
+        | Edit::Domain { server, .. }
+        | Edit::IdleTimeout { server, .. }
+        | Edit::GracePeriod { server, .. }
+        | Edit::DependsOn { server, .. }
+        | Edit::Description { server, .. }
+        | Edit::Tags { server, .. }) = self;
+        server
+            .server_name
+            .clone()
+            .or_else(|| server.server_flag.clone())
+    }
+}
+
+#[derive(Subcommand)]
+pub enum Config {
+    /// Prints the value of a config key, printing nothing if it isn't set
+    Get {
+        /// Specifies the config key
+        #[clap(possible_values = config::KEYS)]
+        key: String,
+    },
+
+    /// Sets the value of a config key
+    Set {
+        /// Specifies the config key
+        #[clap(possible_values = config::KEYS)]
+        key: String,
+        /// Specifies the new value
+        value: String,
+    },
+
+    /// Removes a config key so that its default is used
+    Unset {
+        /// Specifies the config key
+        #[clap(possible_values = config::KEYS)]
+        key: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum Import {
+    /// Adds the apps defined in a pm2 ecosystem file
+    Pm2 {
+        /// Specifies the ecosystem.config.js or ecosystem.json file
+        #[clap(parse(from_os_str))]
+        path: PathBuf,
+    },
+
+    /// Adds the services defined in a docker compose file
+    Compose {
+        /// Specifies the compose file
+        #[clap(parse(from_os_str))]
+        path: PathBuf,
+    },
+
+    /// Adds the sites that a Caddyfile reverse proxies to a local port
+    Caddy {
+        /// Specifies the Caddyfile
+        #[clap(parse(from_os_str))]
+        path: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum Hosts {
+    /// Adds an entry for each server's domain to the hosts file and removes stale entries
+    Sync,
+
+    /// Removes every entry that server-room added to the hosts file
+    Clear,
+}
+
+#[derive(Subcommand)]
+pub enum Tls {
+    /// Mints a locally-trusted certificate for each server's domain with mkcert
+    Setup {
+        /// Only include servers with this tag
+        #[clap(short, long)]
+        tag: Option<String>,
+    },
+
+    /// Forgets each server's certificate
+    Clear,
 }
 
 #[derive(Parser)]
 #[clap(about, version, author)]
-pub enum Cli {
-    /// Displays configuration
-    Config,
+pub struct Cli {
+    /// Fail instead of prompting when a value isn't provided on the command line
+    #[clap(long, global = true, env = "SERVER_ROOM_NONINTERACTIVE")]
+    pub no_input: bool,
+
+    /// Answer yes to every confirmation prompt, like passing --force to each subcommand
+    #[clap(short, long, global = true)]
+    pub yes: bool,
+
+    /// Rewrite the configured Caddyfile and reload Caddy after changing servers
+    #[clap(long, global = true)]
+    pub sync_caddy: bool,
+
+    /// Use this server store file instead of the default one, along with the config and state files next to it
+    #[clap(long, global = true, env = "SERVER_ROOM_STORE", parse(from_os_str))]
+    pub store: Option<PathBuf>,
+
+    /// Print diagnostic logs about what server-room is doing, repeating it like -vv for more detail
+    // There is no --verbose because list already uses it
+    #[clap(short = 'v', global = true, parse(from_occurrences))]
+    pub verbosity: u8,
+
+    /// Don't print diagnostic logs, not even warnings
+    #[clap(short, long, global = true, conflicts_with = "verbosity")]
+    pub quiet: bool,
+
+    #[clap(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Displays or changes the configuration
+    Config {
+        #[clap(subcommand)]
+        action: Option<Config>,
+    },
 
     /// Adds a new server
     Add {
-        /// Specifies the project path
+        /// Specifies the project path, defaulting to the current directory
         #[clap(parse(from_os_str))]
-        path: PathBuf,
+        path: Option<PathBuf>,
         /// Specifies the project name
         #[clap(short, long)]
         name: Option<String>,
         /// Specifies the new server's start script
         #[clap(short, long)]
         start_script: Option<String>,
-        /// Specifies the new server's port
-        #[clap(long, requires = "server")]
-        port: Option<u16>,
+        /// Specifies an arbitrary shell command that starts the new server instead of a start script
+        #[clap(short, long, conflicts_with = "start-script")]
+        command: Option<String>,
+        /// Add a separate server for each process in the project's Procfile
+        #[clap(long, conflicts_with_all = &["start-script", "command"])]
+        procfile: bool,
+        /// Specifies the new server's port, or "auto" to pick a free port
+        #[clap(short, long)]
+        port: Option<PortChoice>,
+        /// Refuse to add the project if the installed Node version doesn't satisfy package.json's "engines" field
+        #[clap(long)]
+        strict: bool,
     },
 
-    /// Changes a server's definition
-    #[clap(subcommand)]
-    Edit(Edit),
+    /// Adds the project in the current directory without prompting, using its .server-room.toml if it has one
+    Adopt,
+
+    /// Finds projects in the servers directory and adds them as servers
+    Scan {
+        /// Specifies the directory to scan instead of the configured servers directory
+        #[clap(parse(from_os_str))]
+        dir: Option<PathBuf>,
+    },
+
+    /// Prints every server's definition so that it can be imported on another machine
+    Export {
+        /// Specifies the output format
+        #[clap(long, value_enum, default_value_t)]
+        format: ExportFormat,
+    },
+
+    /// Adds servers from an exported store or from another tool's config file
+    #[clap(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+    Import {
+        #[clap(subcommand)]
+        source: Option<Import>,
+        /// Specifies the file created by `server-room export`
+        #[clap(parse(from_os_str), required = true)]
+        path: Option<PathBuf>,
+        /// Add the servers alongside the existing ones, updating servers with the same name and directory (the default)
+        #[clap(long, conflicts_with = "replace")]
+        merge: bool,
+        /// Replace every existing server with the imported ones
+        #[clap(long)]
+        replace: bool,
+        /// Don't prompt for confirmation before replacing the existing servers
+        #[clap(short, long)]
+        force: bool,
+    },
+
+    /// Rolls the servers back to an automatic backup
+    Restore {
+        /// Specifies the backup to restore, as printed by `--list`
+        #[clap(conflicts_with = "list")]
+        backup: Option<String>,
+        /// List the available backups instead of restoring one
+        #[clap(short, long)]
+        list: bool,
+        /// Don't prompt for confirmation
+        #[clap(short, long)]
+        force: bool,
+    },
+
+    /// Reverses the most recent edit, remove, or prune
+    Undo,
+
+    /// Commits the servers to a git repo and syncs them with a remote
+    Sync {
+        /// Specifies the git remote to sync with, overriding the sync_remote config key
+        #[clap(long)]
+        remote: Option<String>,
+    },
+
+    /// Changes a server's definition, letting you pick the fields to edit if none is provided
+    #[clap(args_conflicts_with_subcommands = true)]
+    Edit {
+        #[clap(subcommand)]
+        field: Option<Edit>,
+        #[clap(flatten)]
+        server: ServerArg,
+        /// Open the server store file in $EDITOR and validate the changes afterwards
+        #[clap(long, conflicts_with = "server")]
+        raw: bool,
+    },
 
     /// Runs a server
     Run {
-        /// Specifies the server to run
+        #[clap(flatten)]
+        server: ServersArg,
+        /// Run all servers at once
+        #[clap(short, long, conflicts_with = "server")]
+        all: bool,
+        /// Run all servers with this tag at once
+        #[clap(short, long, conflicts_with_all = &["server", "all"])]
+        tag: Option<String>,
+        /// Run the server that was run most recently
+        #[clap(short, long, conflicts_with_all = &["server", "all", "tag"])]
+        last: bool,
+        /// Run the server in the background
+        #[clap(short, long)]
+        detach: bool,
+        /// Don't capture the server's output in its log file
+        #[clap(long)]
+        no_log: bool,
+        /// Open the server in the browser once it is ready
+        #[clap(short, long)]
+        open: bool,
+        /// Override the server's restart policy when its start command exits
+        ///
+        /// Servers run in the background follow their own restart policy, but only the daemon can
+        /// restart them
+        #[clap(long, value_enum, conflicts_with = "detach")]
+        restart: Option<RestartPolicy>,
+        /// Start Node's inspector for debugging, listening on this port or the first free port after 9229
+        #[clap(
+            long,
+            value_name = "PORT",
+            require_equals = true,
+            conflicts_with = "detach"
+        )]
+        inspect: Option<Option<u16>>,
+        /// Refuse to run servers if the installed Node version doesn't satisfy package.json's "engines" field
+        #[clap(long)]
+        strict: bool,
+    },
+
+    /// Installs the dependencies of several servers at once
+    Install {
+        /// Specifies the servers to install dependencies for
+        server: Vec<String>,
+        /// Install the dependencies of all servers
+        #[clap(short, long, conflicts_with = "server")]
+        all: bool,
+        /// Install the dependencies of all servers with this tag
+        #[clap(short, long, conflicts_with_all = &["server", "all"])]
+        tag: Option<String>,
+        /// Specifies how many installs run at once
+        #[clap(short, long, default_value_t = install::DEFAULT_JOBS)]
+        jobs: usize,
+    },
+
+    /// Lists the outdated dependencies of every server
+    Outdated {
+        /// Only check servers with this tag
         #[clap(short, long)]
+        tag: Option<String>,
+    },
+
+    /// Lists the scripts that a server's project offers
+    Scripts {
+        /// Specifies the server whose scripts to list
         server: Option<String>,
     },
 
+    /// Opens a server in the browser
+    Open {
+        /// Specifies the server to open
+        #[clap(short, long)]
+        server: Option<String>,
+    },
+
+    /// Waits until a server is ready
+    Wait {
+        /// Specifies the server to wait for
+        #[clap(short, long)]
+        server: Option<String>,
+        /// Specifies how many seconds to wait before failing
+        #[clap(short, long, default_value_t = 60)]
+        timeout: u64,
+    },
+
+    /// Stops a server running in the background
+    Stop {
+        /// Specifies the server to stop
+        #[clap(short, long)]
+        server: Option<String>,
+        /// Don't prompt for confirmation
+        #[clap(short, long, requires = "server")]
+        force: bool,
+    },
+
+    /// Stops the process listening on a port
+    KillPort {
+        /// Specifies the port
+        port: Option<u16>,
+        /// Specifies the server whose port to free
+        #[clap(short, long, conflicts_with = "port")]
+        server: Option<String>,
+        /// Don't prompt for confirmation
+        #[clap(short, long)]
+        force: bool,
+    },
+
+    /// Displays a server's captured output
+    Logs {
+        /// Specifies the server whose logs to display
+        #[clap(short, long)]
+        server: Option<String>,
+        /// Keep printing new output as it is written
+        #[clap(short, long)]
+        follow: bool,
+        /// Only display the last N lines
+        #[clap(short = 'n', long)]
+        lines: Option<usize>,
+    },
+
+    /// Displays when servers ran and how they exited
+    History {
+        /// Only display the runs of this server
+        #[clap(short, long)]
+        server: Option<String>,
+        /// Only display runs that ended within this period, like "12h" or "7d"
+        #[clap(long)]
+        since: Option<Period>,
+    },
+
     /// Removes a server
     #[clap(alias = "rm")]
+    #[clap(group(ArgGroup::new("removed").multiple(true).args(&["server-name", "server-flag", "multi"])))]
     Remove {
-        /// Specifies the server to remove
+        #[clap(flatten)]
+        server: ServerArg,
+        /// Don't prompt for confirmation
+        #[clap(short, long, requires = "removed")]
+        force: bool,
+        /// Pick several servers to remove at once
+        #[clap(short, long, conflicts_with = "server")]
+        multi: bool,
+    },
+
+    /// Copies a server's settings to a new server
+    Clone {
+        /// Specifies the server to copy
+        server: Option<String>,
+        /// Specifies the new server's name
+        #[clap(short, long)]
+        name: Option<String>,
+        /// Specifies the new server's port, defaulting to a free port
         #[clap(short, long)]
+        port: Option<PortChoice>,
+    },
+
+    /// Archives a server, hiding it from prompts, listings, and proxies without removing it
+    Archive {
+        /// Specifies the server to archive
+        server: Option<String>,
+    },
+
+    /// Restores an archived server
+    Unarchive {
+        /// Specifies the server to restore
+        server: Option<String>,
+    },
+
+    /// Pins a server to the top of the server prompt
+    Pin {
+        /// Specifies the server to pin
         server: Option<String>,
+    },
+
+    /// Unpins a server
+    Unpin {
+        /// Specifies the server to unpin
+        server: Option<String>,
+    },
+
+    /// Removes servers whose projects no longer exist
+    Prune {
         /// Don't prompt for confirmation
-        #[clap(short, long, requires = "server")]
+        #[clap(short, long)]
         force: bool,
     },
 
     /// Displays all servers
     #[clap(alias = "ls")]
-    List,
+    List {
+        /// Only display servers with this tag
+        #[clap(short, long)]
+        tag: Option<String>,
+        /// Specifies the output format
+        #[clap(long, value_enum, default_value_t)]
+        format: ListFormat,
+        /// Display when and how often each server has been run (the short flag is -l because -v
+        /// prints diagnostic logs)
+        #[clap(short = 'l', long)]
+        verbose: bool,
+        /// Also display archived servers
+        #[clap(short, long)]
+        archived: bool,
+    },
+
+    /// Searches server names, start commands, directories, and descriptions
+    Find {
+        /// Specifies the text to search for
+        query: String,
+    },
+
+    /// Displays everything known about a server
+    Info {
+        /// Specifies the server to display
+        server: Option<String>,
+    },
+
+    /// Displays how often each server is used
+    Stats,
+
+    /// Displays a live dashboard of all servers
+    Ui,
+
+    /// Displays the CPU, memory, and uptime of running servers, refreshing continuously
+    Top,
+
+    /// Supervises background servers and listens for commands on a control socket
+    Daemon {
+        /// Also serve a REST API and Prometheus metrics at /metrics on this port, requiring the token
+        /// written next to the control socket to start or stop servers
+        #[clap(long)]
+        http_port: Option<u16>,
+    },
+
+    /// Copies output from stdin to a log file, prefixing each line with the current time
+    #[clap(hide = true)]
+    WriteLog {
+        /// Specifies the log file
+        #[clap(parse(from_os_str))]
+        log_path: PathBuf,
+    },
+
+    /// Displays the servers running in the background
+    Status,
+
+    /// Routes each server's domain to its port
+    Proxy {
+        /// Specifies the port to listen on
+        #[clap(short, long, default_value_t = 80)]
+        port: u16,
+        /// Also route HTTPS requests for servers with certificates on this port
+        #[clap(long)]
+        tls_port: Option<u16>,
+    },
+
+    /// Checks every server for problems
+    Doctor,
 
     /// Generates a Caddyfile
-    Caddy,
+    Caddy {
+        /// Only include servers with this tag
+        #[clap(short, long)]
+        tag: Option<String>,
+        /// Write the Caddyfile to this path instead of printing it
+        #[clap(short, long, alias = "write", parse(from_os_str))]
+        output: Option<PathBuf>,
+        /// Write the Caddyfile to the output path or the configured path, then reload Caddy
+        #[clap(long, conflicts_with = "push")]
+        reload: bool,
+        /// Load the routes into a running Caddy through its admin API instead
+        #[clap(long, conflicts_with = "output")]
+        push: bool,
+        /// Specifies the URL of Caddy's admin API
+        #[clap(long, default_value = "http://localhost:2019", requires = "push")]
+        admin: String,
+    },
+
+    /// Generates dnsmasq config that resolves server domains to localhost
+    Dnsmasq {
+        /// Only include servers with this tag
+        #[clap(short, long)]
+        tag: Option<String>,
+        /// Write the config to this path instead of printing it
+        #[clap(short, long, parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+
+    /// Writes a VS Code launch config that debugs several servers at once
+    VscodeLaunch {
+        /// Specifies the servers to launch
+        server: Vec<String>,
+        /// Launch all servers with this tag
+        #[clap(short, long, conflicts_with = "server")]
+        tag: Option<String>,
+        /// Specifies the launch config to update
+        #[clap(short, long, parse(from_os_str), default_value = ".vscode/launch.json")]
+        output: PathBuf,
+    },
+
+    /// Shares a server publicly through a tunnel
+    Share {
+        /// Specifies the server to share
+        #[clap(short, long)]
+        server: Option<String>,
+        /// Specifies the tunnel program to use instead of the first one installed
+        #[clap(short, long, value_enum)]
+        provider: Option<TunnelProvider>,
+    },
+
+    /// Prints a QR code for opening a server from another device on the local network
+    Qr {
+        /// Specifies the server to open
+        #[clap(short, long)]
+        server: Option<String>,
+    },
+
+    /// Manages HTTPS certificates for server domains
+    #[clap(subcommand)]
+    Tls(Tls),
+
+    /// Manages the hosts file entries for server domains
+    #[clap(subcommand)]
+    Hosts(Hosts),
+
+    /// Prints a server's project directory
+    Path {
+        /// Specifies the server, which can be misspelled slightly
+        server: String,
+    },
+
+    /// Prints a shell function that adds `sr cd <server>`
+    Init {
+        /// Specifies the shell to generate the function for
+        #[clap(value_enum)]
+        shell: InitShell,
+    },
+
+    /// Generates shell completions
+    Completions {
+        /// Specifies the shell to generate completions for
+        #[clap(value_enum)]
+        shell: Shell,
+    },
 
     #[clap(external_subcommand)]
     Unknown(Vec<String>),
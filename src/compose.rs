@@ -0,0 +1,119 @@
+use super::error::ApplicationError;
+use super::import::ImportedServer;
+use super::project::Project;
+use super::project_kind::ProjectKind;
+use super::script::Script;
+use serde_yaml::Value;
+use std::convert::TryInto;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// The file names that docker compose looks for when a file isn't specified
+const DEFAULT_FILE_NAMES: [&str; 4] = [
+    "compose.yaml",
+    "compose.yml",
+    "docker-compose.yaml",
+    "docker-compose.yml",
+];
+
+// This struct represents projects with a docker compose file, whose services are each started
+// with docker compose
+pub struct ComposeProject;
+
+impl ProjectKind for ComposeProject {
+    fn name(&self) -> &'static str {
+        "compose"
+    }
+
+    fn detect(&self, dir: &Path) -> bool {
+        find_compose_file(dir).is_some()
+    }
+
+    fn get_scripts(&self, project: &Project) -> Result<Vec<Script>, ApplicationError> {
+        let compose_file = find_compose_file(&project.dir)
+            .ok_or_else(|| ApplicationError::ReadComposeFile(project.dir.join("compose.yaml")))?;
+        Ok(read_services(&compose_file)?
+            .into_iter()
+            .map(|service| Script {
+                name: service.name,
+                command: service.start_command.clone(),
+                start_command: service.start_command,
+            })
+            .collect())
+    }
+}
+
+// Return the path to the compose file that docker compose would use in the directory, if any
+pub fn find_compose_file(dir: &Path) -> Option<PathBuf> {
+    DEFAULT_FILE_NAMES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+// Read the services defined in a docker compose file
+pub fn read_services(path: &Path) -> Result<Vec<ImportedServer>, ApplicationError> {
+    let path = fs::canonicalize(path)
+        .map_err(|_| ApplicationError::ReadComposeFile(path.to_path_buf()))?;
+    let parse_error = || ApplicationError::ParseComposeFile(path.clone());
+    let content =
+        fs::read_to_string(&path).map_err(|_| ApplicationError::ReadComposeFile(path.clone()))?;
+    let compose = serde_yaml::from_str::<Value>(&content).map_err(|_| parse_error())?;
+    let services = compose
+        .get("services")
+        .and_then(Value::as_mapping)
+        .ok_or_else(parse_error)?;
+    let dir = path.parent().ok_or_else(parse_error)?;
+
+    // Only pass the file to docker compose if it wouldn't find it on its own
+    let file_name = path
+        .file_name()
+        .and_then(|file_name| file_name.to_str())
+        .ok_or_else(parse_error)?;
+    let file_arg = if DEFAULT_FILE_NAMES.contains(&file_name) {
+        String::new()
+    } else {
+        format!(" -f {}", file_name)
+    };
+
+    services
+        .iter()
+        .map(|(name, service)| {
+            let name = name.as_str().ok_or_else(parse_error)?;
+            Ok(ImportedServer {
+                name: name.to_string(),
+                dir: dir.to_path_buf(),
+                start_command: format!("docker compose{} up {}", file_arg, name),
+                port: get_published_port(service),
+                domain: None,
+            })
+        })
+        .collect()
+}
+
+// Return the host port of the service's first published port
+fn get_published_port(service: &Value) -> Option<u16> {
+    let port = service.get("ports")?.as_sequence()?.first()?;
+    match port {
+        // Long syntax like "published: 8080"
+        Value::Mapping(_) => match port.get("published")? {
+            Value::Number(published) => published.as_u64()?.try_into().ok(),
+            Value::String(published) => parse_port_range(published),
+            _ => None,
+        },
+        // Short syntax like "8080:80", "127.0.0.1:8080:80/tcp", or "8080-8081:80-81"
+        // A lone container port like "80" isn't published to a fixed host port
+        Value::String(port) => {
+            let mapping = port.split('/').next()?;
+            let mut parts = mapping.rsplit(':');
+            parts.next()?;
+            parse_port_range(parts.next()?)
+        }
+        _ => None,
+    }
+}
+
+// Parse a port or the first port of a range like "8080-8081"
+fn parse_port_range(port: &str) -> Option<u16> {
+    port.split('-').next()?.parse().ok()
+}
@@ -0,0 +1,207 @@
+use super::error::ApplicationError;
+use super::package_manager::PackageManager;
+use super::shell::Shell;
+use directories::BaseDirs;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use toml_edit::DocumentMut;
+
+// This struct represents the user's global server-room configuration
+#[derive(Default, Deserialize, Serialize)]
+pub struct Config {
+    // The directory containing the user's projects, used to discover new servers
+    pub servers_dir: Option<PathBuf>,
+    // The directory that server directories inside it are stored relative to, like "~/code", so
+    // that the store can be shared between machines with different home directories
+    pub path_root: Option<PathBuf>,
+    // The range of ports that free ports are automatically picked from
+    pub min_port: Option<u16>,
+    pub max_port: Option<u16>,
+    // The number of minutes without activity after which the proxy stops servers
+    pub idle_timeout: Option<u64>,
+    // The hostname suffix appended to server names to derive their domains, like ".test"
+    pub domain_suffix: Option<String>,
+    // The hosts file that `hosts sync` manages, which defaults to /etc/hosts
+    pub hosts_file: Option<PathBuf>,
+    // The Caddyfile that `caddy --reload` and `--sync-caddy` write to
+    pub caddyfile: Option<PathBuf>,
+    // The shell command that reloads Caddy, which can read the Caddyfile path from $CADDYFILE
+    pub caddy_reload_command: Option<String>,
+    // The package manager for projects without a lockfile, which defaults to npm
+    pub package_manager: Option<PackageManager>,
+    // Whether to fail instead of prompting when a value isn't provided on the command line
+    pub no_input: Option<bool>,
+    // The backend that stores servers, which defaults to a TOML file
+    pub storage: Option<StorageBackend>,
+    // The git remote that `sync` pulls servers from and pushes them to
+    pub sync_remote: Option<String>,
+    // The shell that runs start commands, which defaults to sh
+    pub shell: Option<Shell>,
+    // Whether to show desktop notifications when servers crash, restart, or become ready
+    pub notifications: Option<bool>,
+    // Whether to show each server's git branch and uncommitted changes in lists and prompts
+    pub git_status: Option<bool>,
+}
+
+// This enum represents the formats that the default server store can be kept in
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    #[default]
+    Toml,
+    // Requires building server-room with the "sqlite" feature
+    Sqlite,
+}
+
+// The keys that `config get` and `config set` accept
+pub const KEYS: [&str; 16] = [
+    "servers_dir",
+    "path_root",
+    "min_port",
+    "max_port",
+    "idle_timeout",
+    "domain_suffix",
+    "hosts_file",
+    "caddyfile",
+    "caddy_reload_command",
+    "package_manager",
+    "no_input",
+    "storage",
+    "sync_remote",
+    "shell",
+    "notifications",
+    "git_status",
+];
+
+impl Config {
+    // Load the config from disk, falling back to the defaults if it doesn't exist
+    pub fn load(config_path: PathBuf) -> Result<Config, ApplicationError> {
+        match fs::read_to_string(&config_path) {
+            Ok(config_str) => toml::from_str(&config_str)
+                .map_err(|_| ApplicationError::ParseConfig(config_path.clone())),
+            Err(_) => Ok(Config::default()),
+        }
+    }
+
+    // Return the value of the key in the config file, if it is set
+    pub fn get_value(config_path: &Path, key: &str) -> Result<Option<String>, ApplicationError> {
+        let document = read_document(config_path)?;
+        Ok(document.get(key).map(|item| match item.as_str() {
+            // Print strings without quotes
+            Some(value) => value.to_string(),
+            None => item.to_string().trim().to_string(),
+        }))
+    }
+
+    // Permanently set the key in the config file, or remove it if there isn't a value, preserving
+    // the rest of the file's formatting
+    pub fn set_value(
+        config_path: &Path,
+        key: &str,
+        value: Option<&str>,
+    ) -> Result<(), ApplicationError> {
+        let mut document = read_document(config_path)?;
+        match value {
+            Some(value) => {
+                // Values like 3000 and true are numbers and booleans, and everything else is a string
+                let parsed = value
+                    .parse::<toml_edit::Value>()
+                    .ok()
+                    .filter(|parsed| !parsed.is_str());
+                let candidates = parsed.into_iter().chain(std::iter::once(value.into()));
+                let mut valid = false;
+                for candidate in candidates {
+                    document[key] = toml_edit::value(candidate);
+                    if toml::from_str::<Config>(&document.to_string()).is_ok() {
+                        valid = true;
+                        break;
+                    }
+                }
+                if !valid {
+                    return Err(ApplicationError::InvalidConfigValue {
+                        key: key.to_string(),
+                        value: value.to_string(),
+                    });
+                }
+            }
+            None => {
+                document.remove(key);
+            }
+        }
+
+        if let Some(parent_dir) = config_path.parent() {
+            fs::create_dir_all(parent_dir)
+                .map_err(|_| ApplicationError::WriteConfig(config_path.to_path_buf()))?;
+        }
+        fs::write(config_path, document.to_string())
+            .map_err(|_| ApplicationError::WriteConfig(config_path.to_path_buf()))
+    }
+
+    // Return the file name of the default server store, which depends on the storage backend
+    pub fn get_store_file_name(&self) -> &'static str {
+        match self.storage.unwrap_or_default() {
+            StorageBackend::Toml => "servers.toml",
+            StorageBackend::Sqlite => "servers.db",
+        }
+    }
+
+    // Return the package manager for projects without a lockfile
+    pub fn get_package_manager(&self) -> PackageManager {
+        self.package_manager.unwrap_or_default()
+    }
+
+    // Return the range of ports that free ports are automatically picked from
+    pub fn get_port_range(&self) -> RangeInclusive<u16> {
+        self.min_port.unwrap_or(3000)..=self.max_port.unwrap_or(3999)
+    }
+
+    // Return the hostname suffix used to derive server domains, including its leading dot
+    pub fn get_domain_suffix(&self) -> String {
+        match self.domain_suffix.as_deref() {
+            Some(suffix) if suffix.starts_with('.') => suffix.to_string(),
+            Some(suffix) => format!(".{}", suffix),
+            None => ".localhost".to_string(),
+        }
+    }
+
+    // Return the servers directory with a leading ~ expanded to the home directory
+    pub fn get_servers_dir(&self) -> Option<PathBuf> {
+        self.servers_dir.as_deref().map(expand_home)
+    }
+
+    // Return the path root with a leading ~ expanded to the home directory
+    pub fn get_path_root(&self) -> Option<PathBuf> {
+        self.path_root.as_deref().map(expand_home)
+    }
+
+    // Return the path to the hosts file that server-room manages
+    pub fn get_hosts_file(&self) -> PathBuf {
+        self.hosts_file
+            .as_deref()
+            .map(expand_home)
+            .unwrap_or_else(|| PathBuf::from("/etc/hosts"))
+    }
+
+    // Return the Caddyfile path with a leading ~ expanded to the home directory
+    pub fn get_caddyfile(&self) -> Option<PathBuf> {
+        self.caddyfile.as_deref().map(expand_home)
+    }
+}
+
+// Read the config file as an editable document, treating a missing file as an empty one
+fn read_document(config_path: &Path) -> Result<DocumentMut, ApplicationError> {
+    fs::read_to_string(config_path)
+        .unwrap_or_default()
+        .parse::<DocumentMut>()
+        .map_err(|_| ApplicationError::ParseConfig(config_path.to_path_buf()))
+}
+
+// Expand a leading ~ in the path to the home directory
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), BaseDirs::new()) {
+        (Ok(relative_path), Some(base_dirs)) => base_dirs.home_dir().join(relative_path),
+        _ => path.to_path_buf(),
+    }
+}
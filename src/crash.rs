@@ -0,0 +1,82 @@
+use super::run_history::RunRecord;
+use super::server::Server;
+use colored::*;
+
+// The number of output lines to show when a server crashes
+pub const TAIL_LINES: usize = 30;
+
+// Describe a server that crashed with its last lines of output and a suggested fix
+pub fn get_summary(server: &Server, record: &RunRecord, output: &[String]) -> String {
+    let mut summary = format!(
+        "{} crashed {} after {}",
+        server.name.bold().red(),
+        record.get_cause(),
+        record.get_uptime()
+    );
+    if !output.is_empty() {
+        summary.push_str(&format!("\n{}", "Last output:".bold()));
+        for line in output {
+            summary.push_str(&format!("\n    {}", line));
+        }
+    }
+    if let Some(suggestion) = diagnose(server, record, output) {
+        summary.push_str(&format!("\n{}", suggestion));
+    }
+    summary
+}
+
+// Suggest a fix based on the server's exit code and well-known error messages in its output
+fn diagnose(server: &Server, record: &RunRecord, output: &[String]) -> Option<String> {
+    let mentions = |patterns: &[&str]| {
+        output.iter().any(|line| {
+            let line = line.to_lowercase();
+            patterns.iter().any(|pattern| line.contains(pattern))
+        })
+    };
+
+    if mentions(&[
+        "eaddrinuse",
+        "address already in use",
+        "port is already in use",
+    ]) {
+        Some(format!(
+            "Port {} is already in use. Free it with `{}`",
+            server.port,
+            format!("server-room kill-port {}", server.port)
+                .bold()
+                .cyan()
+        ))
+    } else if mentions(&[
+        "cannot find module",
+        "err_module_not_found",
+        "module not found",
+    ]) {
+        let package_manager = server.package_manager.unwrap_or_default();
+        Some(format!(
+            "A module is missing. Try installing the project's dependencies with `{}`",
+            package_manager.get_install_command().bold().cyan()
+        ))
+    } else if mentions(&["heap out of memory"]) {
+        Some(format!(
+            "Node ran out of memory. Try raising its limit by prefixing the start command with `{}`",
+            "NODE_OPTIONS=--max-old-space-size=4096".bold().cyan()
+        ))
+    } else if mentions(&["eacces", "permission denied"]) {
+        Some("Make sure that the server has permission to access its files and port. Ports below 1024 require elevated privileges.".to_string())
+    } else if record.exit_code == Some(127) || mentions(&["command not found"]) {
+        Some(format!(
+            "The start command couldn't be found. Make sure that it is installed or change it with `{}`",
+            format!("server-room edit start-script {}", server.name)
+                .bold()
+                .cyan()
+        ))
+    } else if record.exit_code == Some(128 + libc::SIGKILL) || record.signal == Some(libc::SIGKILL)
+    {
+        Some(
+            "The server was killed with SIGKILL, which can mean that the system ran out of memory."
+                .to_string(),
+        )
+    } else {
+        None
+    }
+}
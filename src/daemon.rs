@@ -0,0 +1,496 @@
+use super::error::ApplicationError;
+use super::health;
+use super::logging;
+use super::metrics;
+use super::notification;
+use super::process::{self, RuntimeState, StopPhase};
+use super::server_store::ServerStore;
+use super::supervisor::{HEALTHY_UPTIME, INITIAL_BACKOFF, MAX_BACKOFF};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+use tracing::{info, warn};
+
+// The JSON-RPC error codes used by the control socket
+const PARSE_ERROR: i32 = -32700;
+const METHOD_NOT_FOUND: i32 = -32601;
+const SERVER_ERROR: i32 = -32000;
+
+// This struct contains the files that the daemon reads from and writes to
+pub struct DaemonPaths {
+    pub store_path: PathBuf,
+    pub runtime_state_path: PathBuf,
+    pub logs_dir: PathBuf,
+    pub socket_path: PathBuf,
+}
+
+// This struct represents a JSON-RPC request sent to the control socket
+#[derive(Deserialize, Serialize)]
+struct Request {
+    jsonrpc: String,
+    id: u64,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+// This struct represents a JSON-RPC response sent back over the control socket
+#[derive(Deserialize, Serialize)]
+struct Response {
+    jsonrpc: String,
+    id: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<ResponseError>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct ResponseError {
+    code: i32,
+    message: String,
+}
+
+// This struct represents a server process that the daemon is supervising
+#[derive(Clone, Deserialize, Serialize)]
+pub struct ManagedProcess {
+    pub server: String,
+    pub pid: u32,
+    pub restarts: u32,
+}
+
+// This struct represents a server in the store and the process supervising it, if any
+#[derive(Deserialize, Serialize)]
+pub struct ServerStatus {
+    pub name: String,
+    pub port: u16,
+    pub domain: String,
+    pub url: String,
+    pub process: Option<ManagedProcess>,
+}
+
+// This struct represents the daemon's state, which is shared between connections and the threads
+// that wait for each server to exit
+pub struct Daemon {
+    paths: DaemonPaths,
+    domain_suffix: String,
+    processes: Mutex<HashMap<String, ManagedProcess>>,
+    // The servers that are being stopped on purpose and shouldn't be restarted
+    stopping: Mutex<HashSet<String>>,
+}
+
+impl Daemon {
+    // Create a daemon that isn't supervising any servers yet
+    pub fn new(paths: DaemonPaths, domain_suffix: String) -> Arc<Self> {
+        Arc::new(Daemon {
+            paths,
+            domain_suffix,
+            processes: Mutex::new(HashMap::new()),
+            stopping: Mutex::new(HashSet::new()),
+        })
+    }
+
+    // Start the server in the background and supervise it
+    pub fn start(self: &Arc<Self>, server_name: &str) -> Result<ManagedProcess, ApplicationError> {
+        let server_store = ServerStore::load(self.paths.store_path.clone())?;
+        let runtime_state = RuntimeState::load(self.paths.runtime_state_path.clone())?;
+        let pids = server_store.start_servers_detached(
+            &[server_name],
+            &runtime_state,
+            Some(&self.paths.logs_dir),
+        )?;
+        let process = ManagedProcess {
+            server: server_name.to_string(),
+            pid: pids[0],
+            restarts: 0,
+        };
+        self.processes
+            .lock()
+            .unwrap()
+            .insert(server_name.to_string(), process.clone());
+
+        let daemon = self.clone();
+        let server_name = server_name.to_string();
+        let pid = process.pid;
+        thread::spawn(move || daemon.supervise(server_name, pid));
+        Ok(process)
+    }
+
+    // Stop the server's background process without restarting it, returning how it was stopped if
+    // it was running
+    pub fn stop(&self, server_name: &str) -> Result<Option<StopPhase>, ApplicationError> {
+        let server_store = ServerStore::load(self.paths.store_path.clone())?;
+        let runtime_state = RuntimeState::load(self.paths.runtime_state_path.clone())?;
+        self.stopping
+            .lock()
+            .unwrap()
+            .insert(server_name.to_string());
+        match server_store.stop_server(server_name, &runtime_state) {
+            // A supervised server that is waiting to be restarted has already exited, so it only
+            // needs to be prevented from restarting
+            Err(ApplicationError::ServerNotRunning(_))
+                if self.processes.lock().unwrap().contains_key(server_name) =>
+            {
+                Ok(None)
+            }
+            Err(err) => {
+                self.stopping.lock().unwrap().remove(server_name);
+                Err(err)
+            }
+            Ok(phase) => Ok(Some(phase)),
+        }
+    }
+
+    // Return the processes that the daemon is supervising, sorted by server name
+    pub fn status(&self) -> Vec<ManagedProcess> {
+        let mut processes = self
+            .processes
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        processes.sort_by(|process1, process2| process1.server.cmp(&process2.server));
+        processes
+    }
+
+    // Return every server in the store, sorted by name, along with its supervised process
+    pub fn servers(&self) -> Result<Vec<ServerStatus>, ApplicationError> {
+        let server_store = ServerStore::load(self.paths.store_path.clone())?;
+        let processes = self.processes.lock().unwrap();
+        let mut servers = server_store
+            .get_all()
+            .into_iter()
+            .map(|server| ServerStatus {
+                name: server.name.clone(),
+                port: server.port,
+                domain: server.get_domain(&self.domain_suffix),
+                url: server.get_url(),
+                process: processes.get(&server.name).cloned(),
+            })
+            .collect::<Vec<_>>();
+        servers.sort_by(|server1, server2| server1.name.cmp(&server2.name));
+        Ok(servers)
+    }
+
+    // Describe every server's state and resource usage in the Prometheus text format
+    pub fn metrics(&self) -> Result<String, ApplicationError> {
+        let server_store = ServerStore::load(self.paths.store_path.clone())?;
+        let runtime_state = RuntimeState::load(self.paths.runtime_state_path.clone())?;
+        let restarts = self
+            .processes
+            .lock()
+            .unwrap()
+            .values()
+            .map(|process| (process.server.clone(), process.restarts))
+            .collect();
+        Ok(metrics::render(
+            &server_store.get_enabled_with_tag(None),
+            &runtime_state,
+            &restarts,
+        ))
+    }
+
+    // Return the last lines of the server's log file, or all of them if no limit is provided
+    pub fn logs(
+        &self,
+        server_name: &str,
+        lines: Option<usize>,
+    ) -> Result<Vec<String>, ApplicationError> {
+        // Only servers in the store have logs, which keeps names like "../x" out of the log path
+        ServerStore::load(self.paths.store_path.clone())?.get_one(server_name)?;
+        let log_path = logging::get_log_path(&self.paths.logs_dir, server_name);
+        if !log_path.is_file() {
+            return Err(ApplicationError::NoLogs(server_name.to_string()));
+        }
+        logging::read_tail(&log_path, lines.unwrap_or(usize::MAX))
+    }
+
+    // Wait for the server to exit and restart it according to its restart policy
+    fn supervise(self: Arc<Self>, server_name: String, mut pid: u32) {
+        let mut backoff = INITIAL_BACKOFF;
+        loop {
+            let started_at = Instant::now();
+            let exited = self.watch_readiness(&server_name);
+            let (status, record) = process::wait_for_exit_then(pid, |status| {
+                let record = RuntimeState::load(self.paths.runtime_state_path.clone()).and_then(
+                    |runtime_state| runtime_state.record_exit(&server_name, pid, status.as_ref()),
+                );
+                (status, record)
+            });
+            exited.store(true, Ordering::Relaxed);
+            let stopping = self.stopping.lock().unwrap().remove(&server_name);
+            // Nothing is recorded if stopping the server already recorded how it exited
+            match record {
+                Ok(Some(record)) if record.is_crash() && !stopping => {
+                    notification::crashed(&record)
+                }
+                Ok(_) => {}
+                Err(err) => warn!("Couldn't record how {} exited: {}", server_name, err),
+            }
+
+            // Reload the store so that edits to the restart policy take effect
+            let server_store = ServerStore::load(self.paths.store_path.clone());
+            let server = server_store
+                .as_ref()
+                .ok()
+                .and_then(|server_store| server_store.get_one(&server_name).ok());
+            let should_restart = !stopping
+                && match (server, status) {
+                    (Some(server), Some(status)) => server.restart.should_restart(&status),
+                    _ => false,
+                };
+            if !should_restart {
+                self.processes.lock().unwrap().remove(&server_name);
+                return;
+            }
+
+            if started_at.elapsed() >= HEALTHY_UPTIME {
+                backoff = INITIAL_BACKOFF;
+            }
+            let restarts = self
+                .processes
+                .lock()
+                .unwrap()
+                .get(&server_name)
+                .map_or(0, |process| process.restarts);
+            info!(
+                "Restarting {} in {}s (restart #{})",
+                server_name,
+                backoff.as_secs(),
+                restarts + 1
+            );
+            notification::restarting(&server_name, restarts + 1, backoff);
+            thread::sleep(backoff);
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+            if self.stopping.lock().unwrap().remove(&server_name) {
+                self.processes.lock().unwrap().remove(&server_name);
+                return;
+            }
+
+            let log_path = logging::get_log_path(&self.paths.logs_dir, &server_name);
+            let restarted = server
+                .ok_or_else(|| ApplicationError::NonExistentServer(server_name.clone()))
+                .and_then(|server| server.start_detached(Some(&log_path)))
+                .and_then(|new_pid| {
+                    RuntimeState::load(self.paths.runtime_state_path.clone())?
+                        .record(&[(server_name.as_str(), new_pid)])?;
+                    Ok(new_pid)
+                });
+            match restarted {
+                Ok(new_pid) => {
+                    pid = new_pid;
+                    if let Some(process) = self.processes.lock().unwrap().get_mut(&server_name) {
+                        process.pid = new_pid;
+                        process.restarts += 1;
+                    }
+                }
+                Err(err) => {
+                    warn!("Couldn't restart {}: {}", server_name, err);
+                    self.processes.lock().unwrap().remove(&server_name);
+                    return;
+                }
+            }
+        }
+    }
+
+    // Notify the user when the server becomes ready, giving up once the returned flag is set
+    // because the server exited
+    fn watch_readiness(&self, server_name: &str) -> Arc<AtomicBool> {
+        let exited = Arc::new(AtomicBool::new(false));
+        let server_store = ServerStore::load(self.paths.store_path.clone());
+        let server = server_store.as_ref().ok().and_then(|server_store| {
+            server_store
+                .get_one(server_name)
+                .ok()
+                .map(|server| (server_store.clone(), server.clone()))
+        });
+        if let Some((server_store, server)) = server {
+            let exited = exited.clone();
+            thread::spawn(move || {
+                if let Some(elapsed) = health::wait_until_ready(&server, None, &exited) {
+                    notification::ready(&server.name, elapsed);
+                    let _ = server_store.record_startup_time(&server.name, elapsed);
+                }
+            });
+        }
+        exited
+    }
+
+    // Perform the method requested over the control socket
+    fn dispatch(self: &Arc<Self>, method: &str, params: &Value) -> Option<Result<Value, String>> {
+        let server_name = params["server"].as_str().unwrap_or_default();
+        let result = match method {
+            "start" => self
+                .start(server_name)
+                .map(|process| json!(process))
+                .map_err(|err| err.to_string()),
+            "stop" => self
+                .stop(server_name)
+                .map(|phase| json!(phase))
+                .map_err(|err| err.to_string()),
+            "status" => Ok(json!(self.status())),
+            "servers" => self
+                .servers()
+                .map(|servers| json!(servers))
+                .map_err(|err| err.to_string()),
+            "logs" => {
+                let lines = params["lines"].as_u64().map(|lines| lines as usize);
+                self.logs(server_name, lines)
+                    .map(|lines| json!(lines))
+                    .map_err(|err| err.to_string())
+            }
+            _ => return None,
+        };
+        Some(result)
+    }
+
+    // Respond to each request sent over the connection until it is closed
+    fn handle_connection(self: Arc<Self>, stream: UnixStream) {
+        let mut writer = match stream.try_clone() {
+            Ok(writer) => writer,
+            Err(_) => return,
+        };
+        for line in BufReader::new(stream).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => return,
+            };
+            let response = match serde_json::from_str::<Request>(&line) {
+                Ok(request) => match self.dispatch(&request.method, &request.params) {
+                    Some(Ok(result)) => Response {
+                        jsonrpc: "2.0".to_string(),
+                        id: request.id,
+                        result: Some(result),
+                        error: None,
+                    },
+                    Some(Err(message)) => error_response(request.id, SERVER_ERROR, message),
+                    None => error_response(
+                        request.id,
+                        METHOD_NOT_FOUND,
+                        format!("Unknown method \"{}\"", request.method),
+                    ),
+                },
+                Err(_) => error_response(0, PARSE_ERROR, "Invalid request".to_string()),
+            };
+            let stringified = serde_json::to_string(&response).unwrap_or_default();
+            if writeln!(writer, "{}", stringified).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+// Create a JSON-RPC error response
+fn error_response(id: u64, code: i32, message: String) -> Response {
+    Response {
+        jsonrpc: "2.0".to_string(),
+        id,
+        result: None,
+        error: Some(ResponseError { code, message }),
+    }
+}
+
+// Listen on the control socket and handle requests until the daemon is killed
+pub fn serve(daemon: Arc<Daemon>) -> Result<(), ApplicationError> {
+    let socket_path = daemon.paths.socket_path.clone();
+    if DaemonClient::connect(&socket_path).is_some() {
+        return Err(ApplicationError::DaemonAlreadyRunning(socket_path));
+    }
+
+    // Nothing is listening on the socket, so it was left behind by a daemon that didn't exit cleanly
+    let _ = fs::remove_file(&socket_path);
+    if let Some(parent_dir) = socket_path.parent() {
+        fs::create_dir_all(parent_dir)
+            .map_err(|_| ApplicationError::BindSocket(socket_path.clone()))?;
+    }
+    let listener = UnixListener::bind(&socket_path)
+        .map_err(|_| ApplicationError::BindSocket(socket_path.clone()))?;
+    for stream in listener.incoming().flatten() {
+        let daemon = daemon.clone();
+        thread::spawn(move || daemon.handle_connection(stream));
+    }
+    Ok(())
+}
+
+// This struct represents a connection to the daemon's control socket
+pub struct DaemonClient {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+    next_id: u64,
+}
+
+impl DaemonClient {
+    // Connect to the daemon if it is running
+    pub fn connect(socket_path: &Path) -> Option<Self> {
+        let writer = UnixStream::connect(socket_path).ok()?;
+        let reader = BufReader::new(writer.try_clone().ok()?);
+        Some(DaemonClient {
+            reader,
+            writer,
+            next_id: 1,
+        })
+    }
+
+    // Send a request to the daemon and wait for its result
+    fn call(&mut self, method: &str, params: Value) -> Result<Value, ApplicationError> {
+        let request = Request {
+            jsonrpc: "2.0".to_string(),
+            id: self.next_id,
+            method: method.to_string(),
+            params,
+        };
+        self.next_id += 1;
+        let stringified =
+            serde_json::to_string(&request).map_err(|_| ApplicationError::DaemonConnection)?;
+        writeln!(self.writer, "{}", stringified).map_err(|_| ApplicationError::DaemonConnection)?;
+
+        let mut line = String::new();
+        self.reader
+            .read_line(&mut line)
+            .map_err(|_| ApplicationError::DaemonConnection)?;
+        let response: Response =
+            serde_json::from_str(&line).map_err(|_| ApplicationError::DaemonConnection)?;
+        match (response.result, response.error) {
+            (_, Some(error)) => Err(ApplicationError::DaemonRequest(error.message)),
+            (Some(result), None) => Ok(result),
+            (None, None) => Ok(Value::Null),
+        }
+    }
+
+    // Ask the daemon to start and supervise the server
+    pub fn start(&mut self, server_name: &str) -> Result<ManagedProcess, ApplicationError> {
+        let result = self.call("start", json!({ "server": server_name }))?;
+        serde_json::from_value(result).map_err(|_| ApplicationError::DaemonConnection)
+    }
+
+    // Ask the daemon to stop the server, returning how it was stopped if it was running
+    pub fn stop(&mut self, server_name: &str) -> Result<Option<StopPhase>, ApplicationError> {
+        let result = self.call("stop", json!({ "server": server_name }))?;
+        serde_json::from_value(result).map_err(|_| ApplicationError::DaemonConnection)
+    }
+
+    // Ask the daemon which servers it is supervising
+    pub fn status(&mut self) -> Result<Vec<ManagedProcess>, ApplicationError> {
+        let result = self.call("status", Value::Null)?;
+        serde_json::from_value(result).map_err(|_| ApplicationError::DaemonConnection)
+    }
+
+    // Ask the daemon for the last lines of the server's log file
+    pub fn logs(
+        &mut self,
+        server_name: &str,
+        lines: Option<usize>,
+    ) -> Result<Vec<String>, ApplicationError> {
+        let result = self.call("logs", json!({ "server": server_name, "lines": lines }))?;
+        serde_json::from_value(result).map_err(|_| ApplicationError::DaemonConnection)
+    }
+}
@@ -0,0 +1,74 @@
+use super::error::ApplicationError;
+use super::server::Server;
+use super::store::RawServerStore;
+use colored::*;
+
+// Render a colored line diff of the servers' records in the store before and after a change
+// Servers that are being removed are only in the old list
+pub fn render_servers(old: &[&Server], new: &[&Server]) -> Result<String, ApplicationError> {
+    let old_str = stringify(old)?;
+    let new_str = stringify(new)?;
+    let old_lines = old_str.lines().collect::<Vec<_>>();
+    let new_lines = new_str.lines().collect::<Vec<_>>();
+    Ok(diff_lines(&old_lines, &new_lines)
+        .into_iter()
+        .map(|line| match line {
+            DiffLine::Unchanged(line) => format!("  {}", line),
+            DiffLine::Removed(line) => format!("- {}", line).red().to_string(),
+            DiffLine::Added(line) => format!("+ {}", line).green().to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+// Serialize the servers the same way that they are written to the store file
+fn stringify(servers: &[&Server]) -> Result<String, ApplicationError> {
+    if servers.is_empty() {
+        return Ok(String::new());
+    }
+    toml::to_string_pretty(&RawServerStore {
+        servers: servers.iter().map(|server| (*server).clone()).collect(),
+    })
+    .map_err(|_| ApplicationError::StringifyStore)
+}
+
+enum DiffLine<'a> {
+    Unchanged(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+// Compare the lines using their longest common subsequence, which is plenty fast for the handful
+// of lines in a server's record
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<DiffLine<'a>> {
+    // common[i][j] is the length of the longest common subsequence of old[i..] and new[j..]
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            lines.push(DiffLine::Unchanged(old[i]));
+            i += 1;
+            j += 1;
+        } else if common[i + 1][j] >= common[i][j + 1] {
+            lines.push(DiffLine::Removed(old[i]));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Added(new[j]));
+            j += 1;
+        }
+    }
+    lines.extend(old[i..].iter().map(|line| DiffLine::Removed(line)));
+    lines.extend(new[j..].iter().map(|line| DiffLine::Added(line)));
+    lines
+}
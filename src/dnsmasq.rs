@@ -0,0 +1,26 @@
+use super::error::ApplicationError;
+use super::server::Server;
+use std::fs;
+use std::path::Path;
+
+// Generate dnsmasq config that resolves the domain suffix and each server's domain to localhost
+pub fn generate_config(servers: &[&Server], domain_suffix: &str) -> String {
+    let mut servers = servers.to_vec();
+    servers.sort_by_key(|server| server.name.as_str());
+    let wildcard = format!("address=/{}/127.0.0.1\n", domain_suffix);
+    let entries = servers
+        .iter()
+        .map(|server| format!("address=/{}/127.0.0.1\n", server.get_domain(domain_suffix)))
+        .collect::<String>();
+    format!("{}\n{}", wildcard, entries)
+}
+
+// Write the generated dnsmasq config to the specified path
+pub fn write_config(
+    servers: &[&Server],
+    domain_suffix: &str,
+    output_path: &Path,
+) -> Result<(), ApplicationError> {
+    fs::write(output_path, generate_config(servers, domain_suffix))
+        .map_err(|_| ApplicationError::WriteDnsmasqConfig(output_path.to_path_buf()))
+}
@@ -0,0 +1,109 @@
+use super::package_manager::PackageManager;
+use super::port;
+use super::process::RuntimeState;
+use super::project::Project;
+use super::server::Server;
+use super::server_store::ServerStore;
+
+// This struct represents a problem with a server and how to fix it
+pub struct Problem {
+    pub message: String,
+    pub fix: String,
+}
+
+// Check every server for problems, returning each server along with its problems
+pub fn diagnose<'s>(
+    server_store: &'s ServerStore,
+    runtime_state: &RuntimeState,
+) -> Vec<(&'s Server, Vec<Problem>)> {
+    let mut servers = server_store.get_all();
+    servers.sort_by_key(|server| server.name.as_str());
+    servers
+        .iter()
+        .map(|server| (*server, diagnose_server(server, &servers, runtime_state)))
+        .collect()
+}
+
+// Check a single server for problems
+fn diagnose_server(
+    server: &Server,
+    all_servers: &[&Server],
+    runtime_state: &RuntimeState,
+) -> Vec<Problem> {
+    let mut problems = vec![];
+
+    let dir = server.get_project_dir();
+    if !dir.is_dir() {
+        problems.push(Problem {
+            message: format!("Directory {:?} doesn't exist", dir),
+            fix: format!("server-room edit path {}", server.name),
+        });
+    } else if let Ok(project) = Project::from_path(dir, PackageManager::default()) {
+        problems.extend(diagnose_project(server, &project));
+    }
+
+    let duplicates = all_servers
+        .iter()
+        .filter(|other| other.name != server.name && other.port == server.port)
+        .map(|other| other.name.as_str())
+        .collect::<Vec<_>>();
+    if !duplicates.is_empty() {
+        problems.push(Problem {
+            message: format!(
+                "Port {} is also used by {}",
+                server.port,
+                duplicates.join(", ")
+            ),
+            fix: format!("server-room edit port {}", server.name),
+        });
+    }
+
+    // The port is expected to be in use if server-room started the server in the background
+    if runtime_state.get(&server.name).is_none() && !port::is_free(server.port) {
+        problems.push(Problem {
+            message: format!("Port {} is in use by another process", server.port),
+            fix: format!("server-room kill-port {}", server.port),
+        });
+    }
+
+    problems
+}
+
+// Check whether the server's project can still be started with its start command
+fn diagnose_project(server: &Server, project: &Project) -> Vec<Problem> {
+    if !project.has_manifest() {
+        return vec![];
+    }
+
+    let scripts = match project.get_start_scripts() {
+        Ok(scripts) => scripts,
+        Err(err) => {
+            return vec![Problem {
+                message: err.to_string(),
+                fix: format!("Fix the project's manifest in {:?}", project.dir),
+            }]
+        }
+    };
+
+    // Only start commands that run one of the project's scripts can be checked
+    let runs_script = server.package_manager.is_some_and(|package_manager| {
+        server
+            .start_command
+            .starts_with(&format!("{} run ", package_manager.get_executable()))
+    });
+    if runs_script
+        && !scripts
+            .iter()
+            .any(|script| script.start_command == server.start_command)
+    {
+        return vec![Problem {
+            message: format!(
+                "Start command \"{}\" runs a script that no longer exists",
+                server.start_command
+            ),
+            fix: format!("server-room edit start-script {}", server.name),
+        }];
+    }
+
+    vec![]
+}
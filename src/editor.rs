@@ -0,0 +1,25 @@
+use super::error::ApplicationError;
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+// Open the file in the user's editor and wait for it to be closed
+// The editor comes from $VISUAL or $EDITOR and may include arguments, like "code --wait"
+pub fn edit_file(path: &Path) -> Result<(), ApplicationError> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    // Run the editor through the shell so that its arguments are split like they would be in a terminal
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor))
+        .arg("sh")
+        .arg(path)
+        .status()
+        .map_err(|_| ApplicationError::RunEditor(editor.clone()))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(ApplicationError::RunEditor(editor))
+    }
+}
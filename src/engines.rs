@@ -0,0 +1,231 @@
+use super::project::Project;
+use std::fmt;
+use std::path::Path;
+use std::process::Command;
+
+type Version = (u64, u64, u64);
+
+// This struct represents a project whose package.json requires a Node version that isn't the one
+// installed
+pub struct NodeMismatch {
+    pub required: String,
+    pub installed: String,
+}
+
+impl fmt::Display for NodeMismatch {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "package.json requires Node {}, but {} is installed",
+            self.required, self.installed
+        )
+    }
+}
+
+// Compare the project's "engines.node" range to the installed Node version
+// Projects without a range and machines without Node are never considered mismatched
+pub fn check_node(project: &Project) -> Option<NodeMismatch> {
+    let required = project.get_node_engine()?;
+    let installed = get_installed_node_version(&project.dir)?;
+    if satisfies(&parse_version(&installed)?, &required) {
+        None
+    } else {
+        Some(NodeMismatch {
+            required,
+            installed,
+        })
+    }
+}
+
+// Return the version of Node installed on this machine, like "20.11.1"
+// Version managers like nvm and fnm can pick the version per directory, so Node runs in the project
+fn get_installed_node_version(dir: &Path) -> Option<String> {
+    let output = Command::new("node")
+        .arg("--version")
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let version = String::from_utf8(output.stdout).ok()?;
+    Some(version.trim().trim_start_matches('v').to_string())
+}
+
+// Parse a full version like "20.11.1", ignoring any prerelease or build suffix
+fn parse_version(version: &str) -> Option<Version> {
+    match parse_partial(version)? {
+        (Some(major), Some(minor), Some(patch)) => Some((major, minor, patch)),
+        _ => None,
+    }
+}
+
+// Parse a possibly partial version like "20", "20.x", or "*", where missing parts are None
+fn parse_partial(version: &str) -> Option<(Option<u64>, Option<u64>, Option<u64>)> {
+    let version = version.trim().trim_start_matches(['v', '=']);
+    let version = version.split(['-', '+']).next().unwrap_or(version);
+    let mut parts = version.split('.').map(|part| match part {
+        "" | "x" | "X" | "*" => Ok(None),
+        part => part.parse::<u64>().map(Some),
+    });
+    let mut next_part = || parts.next().unwrap_or(Ok(None)).ok();
+    let (major, minor, patch) = (next_part()?, next_part()?, next_part()?);
+    // Wildcards can't be followed by specific parts like in "20.x.1"
+    if (major.is_none() && minor.is_some()) || (minor.is_none() && patch.is_some()) {
+        return None;
+    }
+    Some((major, minor, patch))
+}
+
+// Determine whether the version satisfies an npm-style range like "^18.0.0 || >=20 <22"
+// Unparseable ranges are treated as satisfied so that they don't block running the server
+fn satisfies(version: &Version, range: &str) -> bool {
+    range
+        .split("||")
+        .map(|set| satisfies_set(version, set))
+        .any(|satisfied| satisfied.unwrap_or(true))
+}
+
+// Determine whether the version satisfies every comparator in a set like ">=20 <22" or "18 - 20"
+fn satisfies_set(version: &Version, set: &str) -> Option<bool> {
+    // Join operators separated from their versions, like ">= 20"
+    let mut comparators: Vec<String> = vec![];
+    for token in set.split_whitespace() {
+        match comparators.last_mut() {
+            Some(last) if last.chars().all(|char| "<>=~^".contains(char)) => last.push_str(token),
+            _ => comparators.push(token.to_string()),
+        }
+    }
+
+    if let [low, hyphen, high] = comparators.as_slice() {
+        if hyphen == "-" {
+            let (low, high) = (parse_partial(low)?, parse_partial(high)?);
+            let upper = match get_next(high) {
+                Some(next) => version < &next,
+                None if high.0.is_none() => true,
+                None => version <= &get_floor(high),
+            };
+            return Some(version >= &get_floor(low) && upper);
+        }
+    }
+    for comparator in comparators {
+        if !satisfies_comparator(version, &comparator)? {
+            return Some(false);
+        }
+    }
+    Some(true)
+}
+
+// Determine whether the version satisfies a single comparator like "^18.2" or ">=20"
+fn satisfies_comparator(version: &Version, comparator: &str) -> Option<bool> {
+    let operator_len = comparator
+        .find(|char: char| !"<>=~^".contains(char))
+        .unwrap_or(comparator.len());
+    let (operator, partial) = comparator.split_at(operator_len);
+    let partial = parse_partial(partial)?;
+    let floor = get_floor(partial);
+    let next = get_next(partial);
+    Some(match operator {
+        "" | "=" => match next {
+            Some(next) => version >= &floor && version < &next,
+            // Wildcards like "*" match every version, but complete versions only match themselves
+            None if partial.0.is_none() => true,
+            None => version == &floor,
+        },
+        ">=" => version >= &floor,
+        ">" => match next {
+            Some(next) => version >= &next,
+            None => version > &floor,
+        },
+        "<" => version < &floor,
+        "<=" => match next {
+            Some(next) => version < &next,
+            None => version <= &floor,
+        },
+        "~" => {
+            let upper = match partial {
+                (None, _, _) => None,
+                (Some(major), Some(minor), _) => Some((major, minor + 1, 0)),
+                (Some(major), None, _) => Some((major + 1, 0, 0)),
+            };
+            version >= &floor && upper.is_none_or(|upper| version < &upper)
+        }
+        "^" => {
+            // Bump the first part that isn't zero, or the last part provided
+            let upper = match partial {
+                (None, _, _) => None,
+                (Some(major), minor, _) if major > 0 || minor.is_none() => Some((major + 1, 0, 0)),
+                (_, Some(minor), patch) if minor > 0 || patch.is_none() => Some((0, minor + 1, 0)),
+                (_, _, patch) => Some((0, 0, patch.unwrap_or(0) + 1)),
+            };
+            version >= &floor && upper.is_none_or(|upper| version < &upper)
+        }
+        _ => return None,
+    })
+}
+
+// Return the lowest version matching a partial version
+fn get_floor(partial: (Option<u64>, Option<u64>, Option<u64>)) -> Version {
+    let (major, minor, patch) = partial;
+    (major.unwrap_or(0), minor.unwrap_or(0), patch.unwrap_or(0))
+}
+
+// Return the first version after every version matching a partial version, or None if the
+// partial version is complete or matches every version
+fn get_next(partial: (Option<u64>, Option<u64>, Option<u64>)) -> Option<Version> {
+    match partial {
+        (Some(major), None, _) => Some((major + 1, 0, 0)),
+        (Some(major), Some(minor), None) => Some((major, minor + 1, 0)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_satisfies_exact() {
+        assert!(satisfies(&(16, 14, 0), "16.14.0"));
+        assert!(satisfies(&(16, 14, 0), "=16.14.0"));
+        assert!(!satisfies(&(16, 14, 1), "16.14.0"));
+        assert!(!satisfies(&(20, 0, 0), "=16.14.0"));
+        assert!(satisfies(&(16, 20, 3), "16"));
+        assert!(!satisfies(&(17, 0, 0), "16.x"));
+        assert!(satisfies(&(22, 1, 0), "*"));
+    }
+
+    #[test]
+    fn test_satisfies_caret() {
+        assert!(satisfies(&(18, 5, 0), "^18.2"));
+        assert!(!satisfies(&(18, 1, 0), "^18.2"));
+        assert!(!satisfies(&(19, 0, 0), "^18.2"));
+        assert!(satisfies(&(0, 2, 5), "^0.2.3"));
+        assert!(!satisfies(&(0, 3, 0), "^0.2.3"));
+    }
+
+    #[test]
+    fn test_satisfies_tilde() {
+        assert!(satisfies(&(18, 2, 9), "~18.2.0"));
+        assert!(!satisfies(&(18, 3, 0), "~18.2.0"));
+        assert!(satisfies(&(18, 9, 0), "~18"));
+        assert!(!satisfies(&(19, 0, 0), "~18"));
+    }
+
+    #[test]
+    fn test_satisfies_range() {
+        assert!(satisfies(&(20, 5, 0), ">=20 <22"));
+        assert!(!satisfies(&(22, 0, 0), ">=20 <22"));
+        assert!(satisfies(&(20, 0, 0), ">= 20"));
+        assert!(satisfies(&(20, 9, 9), "18 - 20"));
+        assert!(!satisfies(&(21, 0, 0), "18 - 20"));
+        assert!(!satisfies(&(17, 9, 9), "18 - 20"));
+    }
+
+    #[test]
+    fn test_satisfies_alternatives() {
+        assert!(satisfies(&(18, 4, 0), "^18.0.0 || >=20"));
+        assert!(satisfies(&(21, 0, 0), "^18.0.0 || >=20"));
+        assert!(!satisfies(&(19, 0, 0), "^18.0.0 || >=20"));
+    }
+}
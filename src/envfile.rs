@@ -0,0 +1,178 @@
+use super::error::ApplicationError;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Find the dotenv files like .env and .env.local in the directory
+pub fn find_env_files(dir: &Path) -> Vec<PathBuf> {
+    let mut env_files = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.is_file()
+                        && path
+                            .file_name()
+                            .and_then(|name| name.to_str())
+                            .is_some_and(|name| name == ".env" || name.starts_with(".env."))
+                })
+                .filter_map(|path| path.strip_prefix(dir).ok().map(Path::to_path_buf))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    env_files.sort();
+    env_files
+}
+
+// Load the variables from a dotenv file
+pub fn load(path: &Path) -> Result<Vec<(String, String)>, ApplicationError> {
+    let content =
+        fs::read_to_string(path).map_err(|_| ApplicationError::ReadEnvFile(path.to_path_buf()))?;
+    let mut variables = vec![];
+    for (index, line) in content.lines().enumerate() {
+        if let Some(variable) = parse_line(line).map_err(|_| ApplicationError::ParseEnvFile {
+            path: path.to_path_buf(),
+            line: index + 1,
+        })? {
+            variables.push(variable);
+        }
+    }
+    Ok(variables)
+}
+
+// Parse a dotenv line like `export KEY="value"`, returning None for blank lines and comments
+fn parse_line(line: &str) -> Result<Option<(String, String)>, ()> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    let line = line.strip_prefix("export ").unwrap_or(line);
+    let (key, value) = line.split_once('=').ok_or(())?;
+    let key = key.trim();
+    if key.is_empty()
+        || !key
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || char == '_' || char == '.')
+    {
+        return Err(());
+    }
+
+    let value = value.trim();
+    let value = if let Some(quoted) = value.strip_prefix('"') {
+        // Double-quoted values support escape sequences
+        let (value, rest) = parse_double_quoted(quoted)?;
+        check_after_quote(rest)?;
+        value
+    } else if let Some(quoted) = value.strip_prefix('\'') {
+        // Single-quoted values are taken literally
+        let (value, rest) = quoted.split_once('\'').ok_or(())?;
+        check_after_quote(rest)?;
+        value.to_string()
+    } else {
+        // Unquoted values can have trailing comments
+        match value.find(" #") {
+            Some(comment_start) => value[..comment_start].trim_end().to_string(),
+            None => value.to_string(),
+        }
+    };
+    Ok(Some((key.to_string(), value)))
+}
+
+// Decode a double-quoted value up to its closing quote in a single pass so that escaped backslashes
+// aren't decoded twice, returning the value and the text after the closing quote
+fn parse_double_quoted(quoted: &str) -> Result<(String, &str), ()> {
+    let mut value = String::new();
+    let mut chars = quoted.char_indices();
+    while let Some((index, char)) = chars.next() {
+        match char {
+            '"' => return Ok((value, &quoted[index + 1..])),
+            '\\' => match chars.next().ok_or(())?.1 {
+                'n' => value.push('\n'),
+                'r' => value.push('\r'),
+                't' => value.push('\t'),
+                escaped @ ('"' | '\\') => value.push(escaped),
+                // Unknown escape sequences are kept as they are
+                other => {
+                    value.push('\\');
+                    value.push(other);
+                }
+            },
+            _ => value.push(char),
+        }
+    }
+    // The closing quote is missing
+    Err(())
+}
+
+// Only a comment can follow a quoted value
+fn check_after_quote(rest: &str) -> Result<(), ()> {
+    let rest = rest.trim_start();
+    if rest.is_empty() || rest.starts_with('#') {
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_value(line: &str) -> Result<Option<String>, ()> {
+        parse_line(line).map(|variable| variable.map(|(_, value)| value))
+    }
+
+    #[test]
+    fn test_parse_line_skips_blank_lines_and_comments() {
+        assert_eq!(parse_line(""), Ok(None));
+        assert_eq!(parse_line("   "), Ok(None));
+        assert_eq!(parse_line("# KEY=value"), Ok(None));
+    }
+
+    #[test]
+    fn test_parse_line_unquoted() {
+        assert_eq!(
+            parse_line("export KEY = value # comment"),
+            Ok(Some(("KEY".to_string(), "value".to_string())))
+        );
+        assert_eq!(parse_value("KEY=a#b"), Ok(Some("a#b".to_string())));
+        assert_eq!(parse_value("KEY="), Ok(Some(String::new())));
+    }
+
+    #[test]
+    fn test_parse_line_double_quoted() {
+        assert_eq!(
+            parse_value(r#"KEY="line 1\nline 2""#),
+            Ok(Some("line 1\nline 2".to_string()))
+        );
+        // An escaped backslash followed by n isn't a newline
+        assert_eq!(
+            parse_value(r#"KEY="C:\\new""#),
+            Ok(Some(r"C:\new".to_string()))
+        );
+        assert_eq!(
+            parse_value(r#"KEY="say \"hi\"" # comment"#),
+            Ok(Some(r#"say "hi""#.to_string()))
+        );
+        assert_eq!(parse_value(r#"KEY="a\qb""#), Ok(Some(r"a\qb".to_string())));
+        assert_eq!(parse_value(r#"KEY="a" "b""#), Err(()));
+        assert_eq!(parse_value(r#"KEY="unterminated"#), Err(()));
+        assert_eq!(parse_value(r#"KEY="ends with \""#), Err(()));
+    }
+
+    #[test]
+    fn test_parse_line_single_quoted() {
+        assert_eq!(parse_value(r"KEY='a\nb'"), Ok(Some(r"a\nb".to_string())));
+        assert_eq!(parse_value("KEY='a' # comment"), Ok(Some("a".to_string())));
+        assert_eq!(parse_value("KEY='a' 'b'"), Err(()));
+        assert_eq!(parse_value("KEY='unterminated"), Err(()));
+    }
+
+    #[test]
+    fn test_parse_line_invalid_keys() {
+        assert_eq!(parse_line("no equals sign"), Err(()));
+        assert_eq!(parse_line("=value"), Err(()));
+        assert_eq!(parse_line("MY-KEY=value"), Err(()));
+    }
+}
@@ -1,13 +1,35 @@
+use super::package_manager::PackageManager;
 use super::project::Project;
 use super::server::Server;
 use std::path::PathBuf;
 use thiserror::Error;
 
+/// An error from loading or changing servers, projects, or server-room's other files
+///
+/// Errors are grouped by cause with [`ApplicationError::get_category`].
 #[derive(Error, Debug)]
 pub enum ApplicationError {
     #[error("Couldn't determine application directories")]
     ProjectDirs,
 
+    #[error("Couldn't parse config file \"{0}\"")]
+    ParseConfig(PathBuf),
+
+    #[cfg(not(feature = "sqlite"))]
+    #[error(
+        "Can't open SQLite store \"{0}\" because server-room was built without SQLite support"
+    )]
+    SqliteUnsupported(PathBuf),
+
+    #[error("Couldn't write config file \"{0}\"")]
+    WriteConfig(PathBuf),
+
+    #[error("\"{value}\" isn't a valid value for {key}")]
+    InvalidConfigValue { key: String, value: String },
+
+    #[error("No servers directory is configured")]
+    NoServersDir,
+
     #[error("Couldn't write server store file \"{0}\"")]
     WriteStore(PathBuf),
 
@@ -17,39 +39,421 @@ pub enum ApplicationError {
     #[error("Couldn't stringify server store")]
     StringifyStore,
 
+    #[error("Couldn't edit server store \"{0}\" because only TOML stores can be edited")]
+    RawEditUnsupported(PathBuf),
+
+    #[error("Couldn't run the editor \"{0}\"")]
+    RunEditor(String),
+
+    #[error("The edited server store was invalid, so it was restored:\n    {}", .0.join("\n    "))]
+    InvalidStoreEdit(Vec<String>),
+
     #[error("Could not read file {:?}", .0.get_package_json())]
     ReadPackageJson(Project),
 
     #[error("Malformed package.json file \"{path}\": {cause}")]
     MalformedPackageJson { path: PathBuf, cause: String },
 
+    #[error("Couldn't write runtime state file \"{0}\"")]
+    WriteRuntimeState(PathBuf),
+
+    #[error("Couldn't parse runtime state file \"{0}\"")]
+    ParseRuntimeState(PathBuf),
+
+    #[error("Couldn't write log file \"{0}\"")]
+    WriteLog(PathBuf),
+
+    #[error("Couldn't read log file \"{0}\"")]
+    ReadLog(PathBuf),
+
+    #[error("Server \"{0}\" doesn't have any logs yet")]
+    NoLogs(String),
+
+    #[error("Malformed Cargo.toml file \"{path}\": {cause}")]
+    MalformedCargoToml { path: PathBuf, cause: String },
+
+    #[error("Couldn't read Makefile \"{0}\"")]
+    ReadMakefile(PathBuf),
+
+    #[error("Couldn't read Procfile \"{0}\"")]
+    ReadProcfile(PathBuf),
+
+    #[error("Couldn't read env file \"{0}\"")]
+    ReadEnvFile(PathBuf),
+
+    #[error("Couldn't parse line {line} of env file \"{path}\"")]
+    ParseEnvFile { path: PathBuf, line: usize },
+
+    #[error("No env files found in \"{0}\"")]
+    NoEnvFiles(PathBuf),
+
     #[error("Couldn't parse path \"{0}\"")]
     ParsePath(PathBuf),
 
-    #[error("Script \"{script}\" doesn't exist in {:?}", .project.get_package_json())]
+    #[error("Script \"{script}\" doesn't exist in project {:?}", .project.dir)]
     NonExistentScript { project: Project, script: String },
 
+    #[error("Project {0:?} doesn't have any start scripts")]
+    NoStartScripts(PathBuf),
+
     #[error("Couldn't execute command \"{0}\"")]
     RunScript(String),
 
+    #[error("Couldn't run \"server-room {0}\"")]
+    RunSubcommand(String),
+
+    #[error("Couldn't parse project config \"{0}\"")]
+    ParseProjectConfig(PathBuf),
+
+    #[error("Couldn't install the dependencies of server \"{0}\"")]
+    InstallDependencies(String),
+
+    #[error("Couldn't install the dependencies of {} server(s)", .0.len())]
+    InstallFailures(Vec<String>),
+
+    #[error("Couldn't check the outdated dependencies of server \"{0}\"")]
+    CheckOutdated(String),
+
+    #[error("{0} can't report outdated dependencies")]
+    OutdatedUnsupported(PackageManager),
+
+    #[error("Project \"{project}\" requires Node {required}, but {installed} is installed")]
+    UnsupportedNodeVersion {
+        project: String,
+        required: String,
+        installed: String,
+    },
+
     #[error("Server \"{0}\" don't exist")]
     NonExistentServer(String),
 
+    #[error("Server \"{0}\" is already running")]
+    ServerAlreadyRunning(String),
+
+    #[error("Server \"{0}\" isn't running")]
+    ServerNotRunning(String),
+
+    #[error("Couldn't stop process {0}")]
+    StopProcess(u32),
+
+    #[error("{0} isn't a valid process ID")]
+    InvalidPid(u32),
+
+    #[error("No process is listening on port {0}")]
+    PortNotInUse(u16),
+
+    #[error("Server dependencies form a cycle: {}", .0.join(" -> "))]
+    DependencyCycle(Vec<String>),
+
+    #[error("Invalid health check URL \"{0}\"")]
+    InvalidHealthCheckUrl(String),
+
+    #[error("Server \"{server}\" wasn't ready after {timeout}s")]
+    NotReady { server: String, timeout: u64 },
+
+    #[error("Couldn't open \"{0}\" in the browser")]
+    OpenBrowser(String),
+
+    #[error("Couldn't find a free port")]
+    NoFreePort,
+
     #[error("Server name cannot be empty")]
     EmptyServerName,
 
     #[error("Server with name \"{0}\" already exists")]
     DuplicateServerName(String),
 
+    #[error("Port {port} is already used by server \"{existing}\"")]
+    DuplicateServerPort { port: u16, existing: String },
+
+    #[error("Server with domain \"{0}\" already exists")]
+    DuplicateServerDomain(String),
+
+    #[error("\"{0}\" isn't a valid domain")]
+    InvalidDomain(String),
+
+    #[error("Server doesn't have a start command")]
+    MissingStartCommand,
+
     #[error("Server at \"{dir}\" already exists")]
-    DuplicateServerDir { dir: PathBuf, existing: Server },
+    DuplicateServerDir { dir: PathBuf, existing: Box<Server> },
+
+    #[error("Couldn't write Caddyfile \"{0}\"")]
+    WriteCaddyfile(PathBuf),
+
+    #[error("Couldn't write dnsmasq config \"{0}\"")]
+    WriteDnsmasqConfig(PathBuf),
+
+    #[error("Couldn't parse VS Code launch config \"{0}\"")]
+    ParseLaunchConfig(PathBuf),
+
+    #[error("Couldn't write VS Code launch config \"{0}\"")]
+    WriteLaunchConfig(PathBuf),
+
+    #[error("No Caddyfile path is configured")]
+    NoCaddyfile,
+
+    #[error("Couldn't reload Caddy with \"{0}\"")]
+    ReloadCaddy(String),
+
+    #[error("Couldn't load the config into Caddy at \"{0}\"")]
+    PushCaddyConfig(String),
+
+    #[error("Couldn't find cloudflared or ngrok")]
+    NoTunnelProvider,
+
+    #[error("Couldn't open a tunnel with {0}")]
+    RunTunnel(String),
+
+    #[error("Couldn't detect this machine's local network IP address")]
+    NoLanIp,
+
+    #[error("Couldn't generate a QR code")]
+    GenerateQrCode,
+
+    #[error("Couldn't mint a certificate for \"{0}\" with mkcert")]
+    RunMkcert(String),
+
+    #[error("Couldn't load certificate \"{0}\"")]
+    LoadCert(PathBuf),
+
+    #[error("Couldn't configure TLS")]
+    ConfigureTls,
+
+    #[error("Couldn't read hosts file \"{0}\"")]
+    ReadHosts(PathBuf),
+
+    #[error("Couldn't write hosts file \"{0}\"")]
+    WriteHosts(PathBuf),
+
+    #[error("Hosts file \"{0}\" has a server-room block without an end marker")]
+    UnterminatedHostsBlock(PathBuf),
+
+    #[error("Couldn't read pm2 ecosystem file \"{0}\"")]
+    ReadPm2Config(PathBuf),
+
+    #[error("Couldn't parse pm2 ecosystem file \"{0}\"")]
+    ParsePm2Config(PathBuf),
+
+    #[error("Couldn't read compose file \"{0}\"")]
+    ReadComposeFile(PathBuf),
+
+    #[error("Couldn't parse compose file \"{0}\"")]
+    ParseComposeFile(PathBuf),
+
+    #[error("Couldn't read Caddyfile \"{0}\"")]
+    ReadCaddyfile(PathBuf),
+
+    #[error("Couldn't read exported servers \"{0}\"")]
+    ReadExport(PathBuf),
+
+    #[error("Couldn't parse exported servers \"{0}\"")]
+    ParseExport(PathBuf),
+
+    #[error("Couldn't back up the server store to \"{0}\"")]
+    WriteBackup(PathBuf),
+
+    #[error("Couldn't restore backup \"{0}\"")]
+    RestoreBackup(PathBuf),
+
+    #[error("Backup \"{0}\" doesn't exist")]
+    NonExistentBackup(String),
+
+    #[error("No backups of the server store exist yet")]
+    NoBackups,
+
+    #[error("Couldn't parse journal \"{0}\"")]
+    ParseJournal(PathBuf),
+
+    #[error("Couldn't write journal \"{0}\"")]
+    WriteJournal(PathBuf),
+
+    #[error("There is nothing to undo")]
+    NothingToUndo,
+
+    #[error("Couldn't parse run history \"{0}\"")]
+    ParseRunHistory(PathBuf),
+
+    #[error("Couldn't write run history \"{0}\"")]
+    WriteRunHistory(PathBuf),
+
+    #[error("Couldn't run `git {0}`")]
+    RunGit(String),
+
+    #[error("Servers were changed both locally and remotely: {}", .0.join(", "))]
+    SyncConflict(Vec<String>),
+
+    #[error("Couldn't sync server store \"{0}\" because only TOML stores can be synced")]
+    SyncUnsupported(PathBuf),
+
+    #[error("No servers have the tag \"{0}\"")]
+    NoTaggedServers(String),
 
     #[error("No servers have been added yet")]
     NoServers,
 
+    #[error("No servers have been archived")]
+    NoArchivedServers,
+
+    #[error("No server has been run yet")]
+    NoRunHistory,
+
+    #[error("Found problems with {0} server(s)")]
+    DoctorFailed(usize),
+
+    #[error("Input is disabled, so {0} must be provided")]
+    MissingInput(String),
+
     #[error(transparent)]
     InquireError(#[from] inquire::error::InquireError),
 
+    #[error("Couldn't listen on the control socket \"{0}\"")]
+    BindSocket(PathBuf),
+
+    #[error("Couldn't listen on port {0}")]
+    BindPort(u16),
+
+    #[error("Couldn't write the REST API token file \"{0}\"")]
+    WriteHttpToken(PathBuf),
+
+    #[error("The daemon is already listening on \"{0}\"")]
+    DaemonAlreadyRunning(PathBuf),
+
+    #[error("The daemon couldn't complete the request: {0}")]
+    DaemonRequest(String),
+
+    #[error("Couldn't communicate with the daemon")]
+    DaemonConnection,
+
+    #[error("Couldn't draw the dashboard")]
+    DrawDashboard,
+
     #[error("Invalid command \"{0}\"")]
     InvalidCommand(String),
 }
+
+// This enum represents the broad kinds of errors, which determine server-room's exit code so that
+// scripts can branch on the kind of failure
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorCategory {
+    Other = 1,
+    NotFound = 2,
+    Validation = 3,
+    StoreIo = 4,
+    ChildProcess = 5,
+}
+
+impl ErrorCategory {
+    // Return the exit code that represents this category of error
+    pub fn get_exit_code(self) -> i32 {
+        self as i32
+    }
+}
+
+impl ApplicationError {
+    // Return the category that this error belongs to
+    pub fn get_category(&self) -> ErrorCategory {
+        use ApplicationError::*;
+        match self {
+            NoServersDir
+            | NoLogs(_)
+            | NoEnvFiles(_)
+            | NonExistentScript { .. }
+            | NoStartScripts(_)
+            | NonExistentServer(_)
+            | ServerNotRunning(_)
+            | PortNotInUse(_)
+            | NoFreePort
+            | NoCaddyfile
+            | NoTunnelProvider
+            | NoLanIp
+            | NonExistentBackup(_)
+            | NoBackups
+            | NothingToUndo
+            | NoTaggedServers(_)
+            | NoServers
+            | NoArchivedServers
+            | NoRunHistory => ErrorCategory::NotFound,
+
+            InvalidConfigValue { .. }
+            | RawEditUnsupported(_)
+            | InvalidStoreEdit(_)
+            | MalformedPackageJson { .. }
+            | MalformedCargoToml { .. }
+            | ParseEnvFile { .. }
+            | ParsePath(_)
+            | ParseProjectConfig(_)
+            | OutdatedUnsupported(_)
+            | UnsupportedNodeVersion { .. }
+            | ServerAlreadyRunning(_)
+            | DependencyCycle(_)
+            | InvalidHealthCheckUrl(_)
+            | EmptyServerName
+            | DuplicateServerName(_)
+            | DuplicateServerPort { .. }
+            | InvalidPid(_)
+            | DuplicateServerDomain(_)
+            | InvalidDomain(_)
+            | MissingStartCommand
+            | UnterminatedHostsBlock(_)
+            | DuplicateServerDir { .. }
+            | SyncConflict(_)
+            | SyncUnsupported(_)
+            | DoctorFailed(_)
+            | MissingInput(_)
+            | DaemonAlreadyRunning(_)
+            | InvalidCommand(_) => ErrorCategory::Validation,
+
+            ParseConfig(_) | WriteConfig(_) | WriteStore(_) | ParseStore(_) | StringifyStore
+            | WriteRuntimeState(_) | ParseRuntimeState(_) | WriteLog(_) | ReadLog(_)
+            | WriteBackup(_) | RestoreBackup(_) | ParseJournal(_) | WriteJournal(_)
+            | ParseRunHistory(_) | WriteRunHistory(_) => ErrorCategory::StoreIo,
+            #[cfg(not(feature = "sqlite"))]
+            SqliteUnsupported(_) => ErrorCategory::StoreIo,
+
+            RunEditor(_)
+            | RunScript(_)
+            | RunSubcommand(_)
+            | InstallDependencies(_)
+            | InstallFailures(_)
+            | CheckOutdated(_)
+            | StopProcess(_)
+            | NotReady { .. }
+            | OpenBrowser(_)
+            | ReloadCaddy(_)
+            | RunTunnel(_)
+            | RunMkcert(_)
+            | RunGit(_) => ErrorCategory::ChildProcess,
+
+            ProjectDirs
+            | ReadPackageJson(_)
+            | ReadMakefile(_)
+            | ReadProcfile(_)
+            | ReadEnvFile(_)
+            | WriteCaddyfile(_)
+            | WriteDnsmasqConfig(_)
+            | ParseLaunchConfig(_)
+            | WriteLaunchConfig(_)
+            | PushCaddyConfig(_)
+            | GenerateQrCode
+            | LoadCert(_)
+            | ConfigureTls
+            | ReadHosts(_)
+            | WriteHosts(_)
+            | ReadPm2Config(_)
+            | ParsePm2Config(_)
+            | ReadComposeFile(_)
+            | ParseComposeFile(_)
+            | ReadCaddyfile(_)
+            | ReadExport(_)
+            | ParseExport(_)
+            | InquireError(_)
+            | BindSocket(_)
+            | BindPort(_)
+            | WriteHttpToken(_)
+            | DaemonRequest(_)
+            | DaemonConnection
+            | DrawDashboard => ErrorCategory::Other,
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use std::fmt;
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+// Enable or disable displaying servers' git status, which is enabled by default
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+// This struct represents the state of a project's git repo
+pub struct GitStatus {
+    pub branch: String,
+    // Whether there are uncommitted changes or untracked files
+    pub dirty: bool,
+}
+
+impl fmt::Display for GitStatus {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "{}{}",
+            self.branch,
+            if self.dirty { "*" } else { "" }
+        )
+    }
+}
+
+// Return the git status of the repo containing the directory, or None if it isn't in a repo or
+// git status is disabled
+pub fn get_status(dir: &Path) -> Option<GitStatus> {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return None;
+    }
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["status", "--porcelain", "--branch"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    // The first line describes the branch, like "## main...origin/main [ahead 1]", and every other
+    // line is a changed file
+    let output = String::from_utf8_lossy(&output.stdout);
+    let mut lines = output.lines();
+    let header = lines.next()?.strip_prefix("## ")?;
+    let branch = if let Some(branch) = header.strip_prefix("No commits yet on ") {
+        branch
+    } else if header.starts_with("HEAD (no branch)") {
+        "detached"
+    } else {
+        header.split("...").next().unwrap_or(header)
+    };
+    Some(GitStatus {
+        branch: branch.split(' ').next().unwrap_or(branch).to_string(),
+        dirty: lines.next().is_some(),
+    })
+}
+
+// Return the git status of each directory, checking them concurrently because each check runs git
+pub fn get_statuses(dirs: &[&Path]) -> Vec<Option<GitStatus>> {
+    thread::scope(|scope| {
+        dirs.iter()
+            .map(|dir| scope.spawn(move || get_status(dir)))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|handle| handle.join().ok().flatten())
+            .collect()
+    })
+}
@@ -0,0 +1,162 @@
+use super::error::ApplicationError;
+use super::server::Server;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::trace;
+
+// How often to check whether a server is ready
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// How long an individual check may take before it is considered failed
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+// This enum represents how to determine whether a server is ready to accept requests
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum HealthCheck {
+    // The server is ready once the URL responds with a successful or redirect status
+    Http { url: String },
+    // The server is ready once the shell command exits successfully
+    Command { command: String },
+}
+
+impl HealthCheck {
+    // Make sure that the health check can be performed
+    pub fn validate(&self) -> Result<(), ApplicationError> {
+        match self {
+            HealthCheck::Http { url } => parse_url(url)
+                .map(|_| ())
+                .ok_or_else(|| ApplicationError::InvalidHealthCheckUrl(url.clone())),
+            HealthCheck::Command { .. } => Ok(()),
+        }
+    }
+}
+
+// Determine whether the server is ready using its health check
+// Servers without a health check are ready once their port accepts connections
+pub fn is_ready(server: &Server) -> bool {
+    match &server.health_check {
+        Some(HealthCheck::Http { url }) => check_http(url),
+        Some(HealthCheck::Command { command }) => check_command(server, command),
+        None => check_port(server.port),
+    }
+}
+
+// Poll the server until it is ready and return how long that took
+// Returns None if the timeout elapses or the stop flag is set first
+pub fn wait_until_ready(
+    server: &Server,
+    timeout: Option<Duration>,
+    stop: &AtomicBool,
+) -> Option<Duration> {
+    let started_at = Instant::now();
+    loop {
+        if stop.load(Ordering::Relaxed) {
+            return None;
+        }
+        if is_ready(server) {
+            return Some(started_at.elapsed());
+        }
+        trace!("{} isn't ready yet", server.name);
+        if timeout.is_some_and(|timeout| started_at.elapsed() >= timeout) {
+            return None;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+// Format the time that a server took to become ready, like "3.2s"
+pub fn format_elapsed(elapsed: Duration) -> String {
+    format!("{:.1}s", elapsed.as_secs_f64())
+}
+
+// Split an HTTP URL into its address and path
+pub fn parse_url(url: &str) -> Option<(String, String)> {
+    let rest = url.strip_prefix("http://")?;
+    let (authority, path) = match rest.find('/') {
+        Some(index) => (&rest[..index], &rest[index..]),
+        None => (rest, "/"),
+    };
+    if authority.is_empty() {
+        return None;
+    }
+    let address = if authority.contains(':') {
+        authority.to_string()
+    } else {
+        format!("{}:80", authority)
+    };
+    Some((address, path.to_string()))
+}
+
+// Determine whether the URL responds with a successful or redirect status
+fn check_http(url: &str) -> bool {
+    let (address, path) = match parse_url(url) {
+        Some(parsed) => parsed,
+        None => return false,
+    };
+    let socket_address = match address
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addresses| addresses.next())
+    {
+        Some(socket_address) => socket_address,
+        None => return false,
+    };
+    let mut stream = match TcpStream::connect_timeout(&socket_address, CHECK_TIMEOUT) {
+        Ok(stream) => stream,
+        Err(_) => return false,
+    };
+    let _ = stream.set_read_timeout(Some(CHECK_TIMEOUT));
+    let _ = stream.set_write_timeout(Some(CHECK_TIMEOUT));
+    let host = address.split(':').next().unwrap_or_default();
+    let request = format!(
+        "GET {} HTTP/1.0\r\nHost: {}\r\nConnection: close\r\n\r\n",
+        path, host
+    );
+    if stream.write_all(request.as_bytes()).is_err() {
+        return false;
+    }
+
+    // Only the status line is needed, like "HTTP/1.1 200 OK"
+    let mut response = [0; 32];
+    let length = match stream.read(&mut response) {
+        Ok(length) => length,
+        Err(_) => return false,
+    };
+    String::from_utf8_lossy(&response[..length])
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse::<u16>().ok())
+        .is_some_and(|status| (200..400).contains(&status))
+}
+
+// Determine whether the shell command exits successfully in the server's project directory
+fn check_command(server: &Server, command: &str) -> bool {
+    server
+        .get_shell()
+        .build_command(command)
+        .current_dir(server.get_project_dir())
+        .env("PORT", server.port.to_string())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+// Determine whether something is accepting connections on the port
+fn check_port(port: u16) -> bool {
+    ("localhost", port)
+        .to_socket_addrs()
+        .map(|addresses| {
+            addresses
+                .into_iter()
+                .any(|address| TcpStream::connect_timeout(&address, CHECK_TIMEOUT).is_ok())
+        })
+        .unwrap_or(false)
+}
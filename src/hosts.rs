@@ -0,0 +1,180 @@
+use super::error::ApplicationError;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+// The lines that surround the entries that server-room manages in the hosts file
+const BEGIN_MARKER: &str = "# BEGIN server-room managed block";
+const END_MARKER: &str = "# END server-room managed block";
+
+// Replace the managed block in the hosts file with an entry for each domain
+pub fn sync(hosts_path: &Path, domains: &[String]) -> Result<(), ApplicationError> {
+    let contents = read_hosts(hosts_path)?;
+    let new_contents = replace_block(&contents, domains)
+        .ok_or_else(|| ApplicationError::UnterminatedHostsBlock(hosts_path.to_path_buf()))?;
+    if new_contents != contents {
+        write_hosts(hosts_path, &new_contents)?;
+    }
+    Ok(())
+}
+
+// Remove the entries for the domains from the managed block in the hosts file, leaving the file
+// untouched if it doesn't contain any of them
+pub fn remove(hosts_path: &Path, domains: &[String]) -> Result<(), ApplicationError> {
+    let contents = read_hosts(hosts_path)?;
+    let managed_domains = get_managed_domains(&contents);
+    if !managed_domains
+        .iter()
+        .any(|domain| domains.contains(domain))
+    {
+        return Ok(());
+    }
+    let remaining_domains = managed_domains
+        .into_iter()
+        .filter(|domain| !domains.contains(domain))
+        .collect::<Vec<_>>();
+    let new_contents = replace_block(&contents, &remaining_domains)
+        .ok_or_else(|| ApplicationError::UnterminatedHostsBlock(hosts_path.to_path_buf()))?;
+    write_hosts(hosts_path, &new_contents)
+}
+
+// Read the contents of the hosts file
+fn read_hosts(hosts_path: &Path) -> Result<String, ApplicationError> {
+    fs::read_to_string(hosts_path)
+        .map_err(|_| ApplicationError::ReadHosts(hosts_path.to_path_buf()))
+}
+
+// Write the contents of the hosts file, using sudo if the current user can't write it
+fn write_hosts(hosts_path: &Path, contents: &str) -> Result<(), ApplicationError> {
+    let write_error = || ApplicationError::WriteHosts(hosts_path.to_path_buf());
+    match fs::write(hosts_path, contents) {
+        Ok(()) => return Ok(()),
+        Err(err) if err.kind() == ErrorKind::PermissionDenied => {}
+        Err(_) => return Err(write_error()),
+    }
+
+    println!("Writing {} requires sudo", hosts_path.display());
+    let mut child = Command::new("sudo")
+        .arg("tee")
+        .arg(hosts_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .map_err(|_| write_error())?;
+    child
+        .stdin
+        .take()
+        .ok_or_else(write_error)?
+        .write_all(contents.as_bytes())
+        .map_err(|_| write_error())?;
+    match child.wait() {
+        Ok(status) if status.success() => Ok(()),
+        _ => Err(write_error()),
+    }
+}
+
+// Return the domains in the managed block of the hosts file's contents
+fn get_managed_domains(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .skip_while(|line| *line != BEGIN_MARKER)
+        .take_while(|line| *line != END_MARKER)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .map(|domain| domain.to_string())
+        .collect()
+}
+
+// Replace the managed block in the hosts file's contents, removing it entirely if there are no
+// domains and appending it if it doesn't exist yet
+// Returns None if the block is never closed, because its end can't be known and every line after
+// the opening marker would otherwise be dropped
+fn replace_block(contents: &str, domains: &[String]) -> Option<String> {
+    let mut lines = Vec::new();
+    let mut in_block = false;
+    let mut block_index = None;
+    for line in contents.lines() {
+        if line == BEGIN_MARKER {
+            in_block = true;
+            block_index = Some(lines.len());
+        } else if line == END_MARKER {
+            in_block = false;
+        } else if !in_block {
+            lines.push(line.to_string());
+        }
+    }
+    if in_block {
+        return None;
+    }
+
+    if !domains.is_empty() {
+        let block = std::iter::once(BEGIN_MARKER.to_string())
+            .chain(domains.iter().map(|domain| format!("127.0.0.1 {}", domain)))
+            .chain(std::iter::once(END_MARKER.to_string()));
+        let index = block_index.unwrap_or(lines.len());
+        lines.splice(index..index, block);
+    }
+    Some(
+        lines
+            .into_iter()
+            .map(|line| format!("{}\n", line))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn domains(domains: &[&str]) -> Vec<String> {
+        domains.iter().map(|domain| domain.to_string()).collect()
+    }
+
+    #[test]
+    fn test_replace_block_appends() {
+        assert_eq!(
+            replace_block("127.0.0.1 localhost\n", &domains(&["app.test"])).unwrap(),
+            format!(
+                "127.0.0.1 localhost\n{}\n127.0.0.1 app.test\n{}\n",
+                BEGIN_MARKER, END_MARKER
+            )
+        );
+    }
+
+    #[test]
+    fn test_replace_block_replaces_in_place() {
+        let contents = format!(
+            "127.0.0.1 localhost\n{}\n127.0.0.1 old.test\n{}\n::1 localhost\n",
+            BEGIN_MARKER, END_MARKER
+        );
+        assert_eq!(
+            replace_block(&contents, &domains(&["new.test"])).unwrap(),
+            format!(
+                "127.0.0.1 localhost\n{}\n127.0.0.1 new.test\n{}\n::1 localhost\n",
+                BEGIN_MARKER, END_MARKER
+            )
+        );
+    }
+
+    #[test]
+    fn test_replace_block_removes_empty_block() {
+        let contents = format!(
+            "127.0.0.1 localhost\n{}\n127.0.0.1 old.test\n{}\n",
+            BEGIN_MARKER, END_MARKER
+        );
+        assert_eq!(
+            replace_block(&contents, &[]).unwrap(),
+            "127.0.0.1 localhost\n"
+        );
+    }
+
+    #[test]
+    fn test_replace_block_unterminated() {
+        let contents = format!(
+            "127.0.0.1 localhost\n{}\n127.0.0.1 old.test\n::1 localhost\n",
+            BEGIN_MARKER
+        );
+        assert_eq!(replace_block(&contents, &domains(&["new.test"])), None);
+    }
+}
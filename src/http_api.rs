@@ -0,0 +1,196 @@
+use super::daemon::Daemon;
+use super::error::ApplicationError;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+
+const JSON_CONTENT_TYPE: &str = "application/json";
+const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+// Serve the daemon's REST API on the port in the background
+// Requests that change servers must send the token written to the token file as a bearer token
+pub fn listen(daemon: Arc<Daemon>, port: u16, token_path: &Path) -> Result<(), ApplicationError> {
+    let token = create_token(token_path)?;
+    let listener =
+        TcpListener::bind(("127.0.0.1", port)).map_err(|_| ApplicationError::BindPort(port))?;
+    let token = Arc::new(token);
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let daemon = daemon.clone();
+            let token = token.clone();
+            thread::spawn(move || handle_connection(&daemon, port, &token, stream));
+        }
+    });
+    Ok(())
+}
+
+// Write a new random token to the file, which only the current user can read
+fn create_token(token_path: &Path) -> Result<String, ApplicationError> {
+    let write_error = || ApplicationError::WriteHttpToken(token_path.to_path_buf());
+    let mut bytes = [0u8; 16];
+    File::open("/dev/urandom")
+        .and_then(|mut random| random.read_exact(&mut bytes))
+        .map_err(|_| write_error())?;
+    let token = bytes
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    // Replace any previous token file so that its permissions are set from scratch
+    let _ = fs::remove_file(token_path);
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(token_path)
+        .and_then(|mut file| file.write_all(token.as_bytes()))
+        .map_err(|_| write_error())?;
+    Ok(token)
+}
+
+// Respond to a single HTTP request
+fn handle_connection(daemon: &Arc<Daemon>, port: u16, token: &str, stream: TcpStream) {
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    // None of the endpoints read a body, so reading stops after the headers
+    let mut headers = HashMap::new();
+    let mut header = String::new();
+    while reader.read_line(&mut header).is_ok_and(|length| length > 2) {
+        if let Some((name, value)) = header.split_once(':') {
+            headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        // Only accept requests addressed to this machine by name so that web pages can't reach the
+        // API through DNS rebinding or send cross-site requests to it
+        (Some(_), Some(_)) if !is_local_request(&headers, port) => (
+            403,
+            JSON_CONTENT_TYPE,
+            json!({ "error": "Forbidden" }).to_string(),
+        ),
+        (Some(method), Some(_)) if method != "GET" && !is_authorized(&headers, token) => (
+            401,
+            JSON_CONTENT_TYPE,
+            json!({ "error": "Missing or invalid token" }).to_string(),
+        ),
+        // Metrics are served in the Prometheus text format instead of JSON
+        (Some("GET"), Some(target)) if target.split('?').next() == Some("/metrics") => {
+            match daemon.metrics() {
+                Ok(metrics) => (200, METRICS_CONTENT_TYPE, metrics),
+                Err(err) => (
+                    get_error_status(&err),
+                    JSON_CONTENT_TYPE,
+                    json!({ "error": err.to_string() }).to_string(),
+                ),
+            }
+        }
+        (Some(method), Some(target)) => {
+            let (status, body) = route(daemon, method, target);
+            (status, JSON_CONTENT_TYPE, body.to_string())
+        }
+        _ => (
+            400,
+            JSON_CONTENT_TYPE,
+            json!({ "error": "Malformed request" }).to_string(),
+        ),
+    };
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        get_reason(status),
+        content_type,
+        body.len(),
+        body
+    );
+    let _ = (&stream).write_all(response.as_bytes());
+}
+
+// Determine whether the Host and Origin headers both refer to the API's own local address
+fn is_local_request(headers: &HashMap<String, String>, port: u16) -> bool {
+    let local_hosts = [format!("localhost:{}", port), format!("127.0.0.1:{}", port)];
+    let is_local_host = headers
+        .get("host")
+        .is_some_and(|host| local_hosts.contains(host));
+    // Browsers send an Origin with cross-site requests, but other clients usually don't
+    let is_local_origin = headers.get("origin").is_none_or(|origin| {
+        origin
+            .strip_prefix("http://")
+            .is_some_and(|host| local_hosts.iter().any(|local_host| local_host == host))
+    });
+    is_local_host && is_local_origin
+}
+
+// Determine whether the request carries the API's bearer token
+fn is_authorized(headers: &HashMap<String, String>, token: &str) -> bool {
+    headers
+        .get("authorization")
+        .and_then(|authorization| authorization.strip_prefix("Bearer "))
+        .is_some_and(|request_token| request_token.trim() == token)
+}
+
+// Perform the action requested by the method and path and return the status code and body
+fn route(daemon: &Arc<Daemon>, method: &str, target: &str) -> (u16, Value) {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let segments = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>();
+    let result = match (method, segments.as_slice()) {
+        ("GET", ["servers"]) => daemon.servers().map(|servers| json!(servers)),
+        ("POST", ["servers", server_name, "start"]) => {
+            daemon.start(server_name).map(|process| json!(process))
+        }
+        ("POST", ["servers", server_name, "stop"]) => daemon
+            .stop(server_name)
+            .map(|phase| json!({ "stopped": server_name, "phase": phase })),
+        ("GET", ["servers", server_name, "logs"]) => {
+            let lines = query
+                .split('&')
+                .filter_map(|param| param.strip_prefix("lines="))
+                .find_map(|lines| lines.parse::<usize>().ok());
+            daemon.logs(server_name, lines).map(|lines| json!(lines))
+        }
+        (_, ["servers"] | ["metrics"]) | (_, ["servers", _, "start" | "stop" | "logs"]) => {
+            return (405, json!({ "error": "Method not allowed" }))
+        }
+        _ => return (404, json!({ "error": "Not found" })),
+    };
+    match result {
+        Ok(body) => (200, body),
+        Err(err) => (get_error_status(&err), json!({ "error": err.to_string() })),
+    }
+}
+
+// Choose the status code that describes the error
+fn get_error_status(err: &ApplicationError) -> u16 {
+    match err {
+        ApplicationError::NonExistentServer(_) | ApplicationError::NoLogs(_) => 404,
+        ApplicationError::ServerAlreadyRunning(_) | ApplicationError::ServerNotRunning(_) => 409,
+        _ => 500,
+    }
+}
+
+// Return the reason phrase for the status code
+fn get_reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        _ => "Internal Server Error",
+    }
+}
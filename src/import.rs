@@ -0,0 +1,216 @@
+use super::error::ApplicationError;
+use super::server::Server;
+use super::server_store::ServerStore;
+use colored::*;
+use std::collections::{HashMap, HashSet};
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+
+// This struct represents a server definition read from another tool's config file
+pub struct ImportedServer {
+    pub name: String,
+    pub dir: PathBuf,
+    pub start_command: String,
+    // Servers without a port are assigned a free one when they are added
+    pub port: Option<u16>,
+    pub domain: Option<String>,
+}
+
+// Add the imported servers to the store, skipping and reporting the ones that conflict with
+// existing servers
+pub fn add_servers(
+    server_store: &ServerStore,
+    imported: Vec<ImportedServer>,
+    port_range: RangeInclusive<u16>,
+) -> Result<(), ApplicationError> {
+    let mut names = HashSet::new();
+    // The servers that claimed each explicit port
+    let mut claimed_ports = HashMap::new();
+    let imported = imported
+        .into_iter()
+        .filter(|imported| {
+            // Servers without a start command would only fail once they are run
+            let conflict = if imported.start_command.trim().is_empty() {
+                Err(ApplicationError::MissingStartCommand)
+            } else {
+                Ok(())
+            }
+            .and_then(|_| server_store.validate_new_server(&imported.name, &imported.dir))
+            .and_then(|_| match &imported.domain {
+                Some(domain) => Server::validate_domain(domain),
+                None => Ok(()),
+            })
+            .and_then(|_| match imported.port {
+                Some(port) => validate_port(server_store, &claimed_ports, port),
+                None => Ok(()),
+            })
+            .err()
+            .map(|err| err.to_string())
+            .or_else(|| {
+                (!names.insert(imported.name.clone()))
+                    .then(|| format!("Server \"{}\" is defined twice", imported.name))
+            });
+            match conflict {
+                None => {
+                    if let Some(port) = imported.port {
+                        claimed_ports.insert(port, imported.name.clone());
+                    }
+                    true
+                }
+                Some(conflict) => {
+                    eprintln!(
+                        "{}: skipping {}: {}",
+                        "Warning".bold().yellow(),
+                        imported.name,
+                        conflict
+                    );
+                    false
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+    if imported.is_empty() {
+        println!("No new servers to import");
+        return Ok(());
+    }
+
+    // Don't assign free ports that other imported servers already use
+    let mut free_ports = server_store
+        .get_free_ports(port_range, imported.len() + claimed_ports.len())
+        .into_iter()
+        .filter(|port| !claimed_ports.contains_key(port));
+    let servers = imported
+        .into_iter()
+        .map(|imported| {
+            let port = imported
+                .port
+                .or_else(|| free_ports.next())
+                .ok_or(ApplicationError::NoFreePort)?;
+            Ok(Server {
+                domain: imported.domain,
+                ..Server::new(imported.name, imported.dir, imported.start_command, port)
+            })
+        })
+        .collect::<Result<Vec<_>, ApplicationError>>()?;
+    for server in servers.iter() {
+        println!(
+            "Adding {} ({}) on port {}",
+            server.name.bold().green(),
+            server.start_command.cyan(),
+            server.port.to_string().yellow()
+        );
+    }
+    server_store.add_servers(servers)
+}
+
+// Make sure that the explicit port isn't used by an existing server or another imported server
+fn validate_port(
+    server_store: &ServerStore,
+    claimed_ports: &HashMap<u16, String>,
+    port: u16,
+) -> Result<(), ApplicationError> {
+    let existing = server_store
+        .get_all()
+        .into_iter()
+        .find(|server| server.port == port)
+        .map(|server| server.name.clone())
+        .or_else(|| claimed_ports.get(&port).cloned());
+    match existing {
+        Some(existing) => Err(ApplicationError::DuplicateServerPort { port, existing }),
+        None => Ok(()),
+    }
+}
+
+// Add the servers from an exported store to the existing servers
+// Servers with the same name and directory as an existing server update it, servers whose name is
+// used by a server in another directory are renamed, and servers whose directory belongs to an
+// existing server that isn't being imported are skipped
+pub fn merge_servers(
+    server_store: &ServerStore,
+    imported: Vec<Server>,
+) -> Result<(), ApplicationError> {
+    let imported_names = imported
+        .iter()
+        .map(|server| server.name.clone())
+        .collect::<HashSet<_>>();
+    let mut servers = server_store
+        .get_all()
+        .into_iter()
+        .map(|server| (server.name.clone(), server.clone()))
+        .collect::<HashMap<_, _>>();
+    for mut server in imported {
+        warn_missing_dir(&server);
+        match servers.get(&server.name) {
+            Some(existing) if existing.dir == server.dir => {
+                // Usage statistics are specific to this machine, so keep the local ones
+                server.frecency = existing.frecency;
+                server.last_run = existing.last_run;
+                server.run_count = existing.run_count;
+                println!("Updating {}", server.name.bold().green());
+            }
+            Some(_) => {
+                let new_name = (2..)
+                    .map(|suffix| format!("{}-{}", server.name, suffix))
+                    .find(|name| !servers.contains_key(name) && !imported_names.contains(name))
+                    .unwrap_or_default();
+                eprintln!(
+                    "{}: renaming {} to {} because a server in another directory has its name",
+                    "Warning".bold().yellow(),
+                    server.name,
+                    new_name
+                );
+                server.name = new_name;
+                println!("Adding {}", server.name.bold().green());
+            }
+            None => {
+                if let Some(existing) = servers.values().find(|existing| {
+                    existing.dir == server.dir && !imported_names.contains(&existing.name)
+                }) {
+                    eprintln!(
+                        "{}: skipping {}: Directory {:?} is already used by server \"{}\"",
+                        "Warning".bold().yellow(),
+                        server.name,
+                        server.dir,
+                        existing.name
+                    );
+                    continue;
+                }
+                println!("Adding {}", server.name.bold().green());
+            }
+        }
+        servers.insert(server.name.clone(), server);
+    }
+    server_store.set_servers(servers.into_values().collect())
+}
+
+// Replace every existing server with the servers from an exported store, returning the servers
+// that no longer exist
+pub fn replace_servers(
+    server_store: &ServerStore,
+    imported: Vec<Server>,
+) -> Result<Vec<Server>, ApplicationError> {
+    let removed = server_store
+        .get_all()
+        .into_iter()
+        .filter(|existing| !imported.iter().any(|server| server.name == existing.name))
+        .cloned()
+        .collect::<Vec<_>>();
+    for server in &imported {
+        warn_missing_dir(server);
+        println!("Adding {}", server.name.bold().green());
+    }
+    server_store.set_servers(imported)?;
+    Ok(removed)
+}
+
+// Warn that an imported server's directory doesn't exist on this machine
+fn warn_missing_dir(server: &Server) {
+    if !server.dir.is_dir() {
+        eprintln!(
+            "{}: {}'s directory {:?} doesn't exist",
+            "Warning".bold().yellow(),
+            server.name,
+            server.dir
+        );
+    }
+}
@@ -0,0 +1,39 @@
+use clap::ValueEnum;
+
+// This enum represents the shells that server-room can generate integration scripts for
+#[derive(Clone, Copy, ValueEnum)]
+pub enum InitShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+// The POSIX shell function works in both bash and zsh
+const POSIX_INIT_SCRIPT: &str = r#"sr() {
+  if [ "$1" = "cd" ]; then
+    shift
+    local dir
+    dir="$(command server-room path "$@")" && cd "$dir"
+  else
+    command server-room "$@"
+  fi
+}
+"#;
+
+const FISH_INIT_SCRIPT: &str = r#"function sr
+  if test "$argv[1]" = cd
+    set -l dir (command server-room path $argv[2..-1]); and cd $dir
+  else
+    command server-room $argv
+  end
+end
+"#;
+
+// Return the script that defines the `sr` shell function, which wraps server-room and adds an
+// `sr cd <server>` command that changes into the server's project directory
+pub fn get_init_script(shell: InitShell) -> &'static str {
+    match shell {
+        InitShell::Bash | InitShell::Zsh => POSIX_INIT_SCRIPT,
+        InitShell::Fish => FISH_INIT_SCRIPT,
+    }
+}
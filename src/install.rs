@@ -0,0 +1,68 @@
+use super::crash;
+use super::server::Server;
+use std::process::Stdio;
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// The number of installs that run at once by default
+pub const DEFAULT_JOBS: usize = 4;
+
+// This enum represents how installing a server's dependencies went
+pub enum InstallResult {
+    Installed(Duration),
+    // The last lines of the install command's output explain why it failed
+    Failed(Vec<String>),
+}
+
+// Install the dependencies of every server, running up to `jobs` installs at once
+// The callback is called on the current thread as each install finishes
+pub fn install_all(
+    servers: &[&Server],
+    jobs: usize,
+    mut on_finish: impl FnMut(&Server, InstallResult),
+) {
+    let queue = Mutex::new(servers.iter());
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        for _ in 0..jobs.max(1).min(servers.len()) {
+            let sender = sender.clone();
+            let queue = &queue;
+            scope.spawn(move || loop {
+                let server = match queue.lock().unwrap().next() {
+                    Some(server) => *server,
+                    None => break,
+                };
+                let _ = sender.send((server, install(server)));
+            });
+        }
+        // Drop the original sender so that the loop below ends once every install has finished
+        drop(sender);
+        for (server, result) in receiver {
+            on_finish(server, result);
+        }
+    });
+}
+
+// Install a server's dependencies, capturing the output so that concurrent installs don't
+// interleave
+fn install(server: &Server) -> InstallResult {
+    let started_at = Instant::now();
+    let output = server.build_install_command().stdin(Stdio::null()).output();
+    match output {
+        Ok(output) if output.status.success() => InstallResult::Installed(started_at.elapsed()),
+        Ok(output) => {
+            let output = String::from_utf8_lossy(&output.stdout).into_owned()
+                + &String::from_utf8_lossy(&output.stderr);
+            let lines = output.lines().collect::<Vec<_>>();
+            InstallResult::Failed(
+                lines[lines.len().saturating_sub(crash::TAIL_LINES)..]
+                    .iter()
+                    .map(|line| line.to_string())
+                    .collect(),
+            )
+        }
+        Err(err) => InstallResult::Failed(vec![err.to_string()]),
+    }
+}
@@ -0,0 +1,128 @@
+use super::error::ApplicationError;
+use super::server::Server;
+use super::server_store::ServerStore;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// The number of operations to remember before forgetting the oldest ones
+const MAX_ENTRIES: usize = 50;
+
+// This struct represents an operation that changed servers and the server records it replaced
+#[derive(Clone, Deserialize, Serialize)]
+pub struct JournalEntry {
+    pub operation: String,
+    pub timestamp: u64,
+    // The names of the servers that the operation created or changed, which undoing removes
+    pub after: Vec<String>,
+    // The servers as they were before the operation, which undoing restores
+    // TOML tables must come after plain values, so this table must be the last field
+    pub before: Vec<Server>,
+}
+
+// This struct represents the operations that can be undone, oldest first
+pub struct Journal {
+    entries: Vec<JournalEntry>,
+    journal_path: PathBuf,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct RawJournal {
+    entries: Vec<JournalEntry>,
+}
+
+impl Journal {
+    // Load the journal from disk
+    pub fn load(journal_path: PathBuf) -> Result<Journal, ApplicationError> {
+        let journal_str =
+            fs::read_to_string(&journal_path).unwrap_or_else(|_| "entries = []".to_string());
+        let raw_journal: RawJournal = toml::from_str(&journal_str)
+            .map_err(|_| ApplicationError::ParseJournal(journal_path.clone()))?;
+        Ok(Journal {
+            entries: raw_journal.entries,
+            journal_path,
+        })
+    }
+
+    // Write the journal to disk
+    fn flush(&self) -> Result<(), ApplicationError> {
+        let stringified = toml::to_string_pretty(&RawJournal {
+            entries: self.entries.clone(),
+        })
+        .map_err(|_| ApplicationError::WriteJournal(self.journal_path.clone()))?;
+        fs::write(&self.journal_path, stringified)
+            .map_err(|_| ApplicationError::WriteJournal(self.journal_path.clone()))
+    }
+
+    // Permanently record the servers that an operation changed by comparing the store from
+    // before the operation to the store after it
+    pub fn record(
+        &mut self,
+        operation: &str,
+        old_store: &ServerStore,
+        new_store: &ServerStore,
+    ) -> Result<(), ApplicationError> {
+        let before = get_changed(old_store, new_store)
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        let after = get_changed(new_store, old_store)
+            .into_iter()
+            .map(|server| server.name.clone())
+            .collect::<Vec<_>>();
+        if before.is_empty() && after.is_empty() {
+            return Ok(());
+        }
+
+        self.entries.push(JournalEntry {
+            operation: operation.to_string(),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0),
+            after,
+            before,
+        });
+        let excess = self.entries.len().saturating_sub(MAX_ENTRIES);
+        self.entries.drain(..excess);
+        self.flush()
+    }
+
+    // Permanently reverse the most recent operation, returning it
+    pub fn undo(&mut self, server_store: &ServerStore) -> Result<JournalEntry, ApplicationError> {
+        let entry = self.entries.pop().ok_or(ApplicationError::NothingToUndo)?;
+
+        // Refuse to overwrite servers that were created after the operation
+        let removed = entry.after.iter().collect::<HashSet<_>>();
+        if let Some(server) = entry.before.iter().find(|server| {
+            !removed.contains(&server.name) && server_store.get_one(&server.name).is_ok()
+        }) {
+            return Err(ApplicationError::DuplicateServerName(server.name.clone()));
+        }
+
+        let servers = server_store
+            .get_all()
+            .into_iter()
+            .filter(|server| !removed.contains(&server.name))
+            .cloned()
+            .chain(entry.before.iter().cloned())
+            .collect();
+        server_store.set_servers(servers)?;
+        self.flush()?;
+        Ok(entry)
+    }
+}
+
+// Return the servers in the store that are missing from or different in the other store
+fn get_changed<'s>(store: &'s ServerStore, other_store: &ServerStore) -> Vec<&'s Server> {
+    store
+        .get_all()
+        .into_iter()
+        .filter(|server| match other_store.get_one(&server.name) {
+            Ok(other) => serde_json::to_value(server).ok() != serde_json::to_value(other).ok(),
+            Err(_) => true,
+        })
+        .collect()
+}
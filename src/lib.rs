@@ -0,0 +1,111 @@
+//! Programmatic access to server-room's servers
+//!
+//! The server store holds the servers that server-room manages and can be loaded from the default
+//! location or any other store file:
+//!
+//! ```no_run
+//! use server_room::ServerStore;
+//!
+//! let store = ServerStore::load(server_room::get_default_store_path()?)?;
+//! for server in store.get_all() {
+//!     println!("{} runs `{}` on port {}", server.name, server.start_command, server.port);
+//! }
+//! # Ok::<(), server_room::ApplicationError>(())
+//! ```
+//!
+//! Projects on disk can be discovered with `Project::from_path` and `scan::find_projects`, and
+//! other ecosystems can be detected by registering a `project_kind::ProjectKind`.
+
+// Servers are managed with Unix process groups, signals, and sockets, so Windows isn't supported
+// Start commands can still run with PowerShell where it is installed
+#[cfg(not(unix))]
+compile_error!("server-room only supports Unix-like systems such as Linux and macOS");
+
+pub mod config;
+pub mod error;
+pub mod package_manager;
+pub mod port;
+pub mod project;
+pub mod project_config;
+pub mod project_kind;
+pub mod scan;
+pub mod script;
+pub mod server;
+pub mod server_store;
+pub mod shell;
+pub mod store;
+
+// These modules implement the server-room binary's commands and aren't part of the public API
+mod app;
+mod backup;
+mod browser;
+mod caddy;
+mod cargo_project;
+mod cli;
+mod compose;
+mod crash;
+mod daemon;
+mod diff;
+mod dnsmasq;
+mod doctor;
+mod editor;
+mod engines;
+mod envfile;
+mod git;
+mod health;
+mod hosts;
+mod http_api;
+mod import;
+mod init;
+mod install;
+mod journal;
+mod logging;
+mod makefile;
+mod metrics;
+mod notification;
+mod outdated;
+mod pm2;
+mod process;
+mod procfile;
+mod prompt;
+mod proxy;
+mod qr;
+mod resources;
+mod run_history;
+mod search;
+mod share;
+#[cfg(feature = "sqlite")]
+mod sqlite_store;
+mod supervisor;
+mod sync;
+mod tls;
+mod top;
+mod tui;
+mod vscode;
+mod workspace;
+
+pub use error::ApplicationError;
+pub use project::Project;
+pub use project_kind::ProjectKind;
+pub use server::Server;
+pub use server_store::ServerStore;
+
+// The server-room binary's entry point
+#[doc(hidden)]
+pub use app::main;
+
+use config::Config;
+use directories::ProjectDirs;
+use std::path::PathBuf;
+
+// Return the path to the server store file in the user's data directory, which is where the
+// server-room binary keeps it unless it is told to use another file
+pub fn get_default_store_path() -> Result<PathBuf, ApplicationError> {
+    let project_dirs = ProjectDirs::from("com", "github.canac", "server-room")
+        .ok_or(ApplicationError::ProjectDirs)?;
+    // The config lives in the data directory because its path can't depend on the store path
+    let config = Config::load(project_dirs.data_dir().join("config.toml"))?;
+    Ok(project_dirs
+        .data_dir()
+        .join(PathBuf::from(config.get_store_file_name())))
+}
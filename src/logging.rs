@@ -0,0 +1,252 @@
+use super::error::ApplicationError;
+use super::process::ForegroundGroup;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, PipeReader, Read, Seek, SeekFrom, Write};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// Return the path to the specified server's log file
+pub fn get_log_path(logs_dir: &Path, server_name: &str) -> PathBuf {
+    logs_dir.join(format!("{}.log", server_name))
+}
+
+// Open a log file for appending, creating it and its parent directory if necessary
+pub fn open_log(log_path: &Path) -> Result<File, ApplicationError> {
+    if let Some(parent_dir) = log_path.parent() {
+        fs::create_dir_all(parent_dir)
+            .map_err(|_| ApplicationError::WriteLog(log_path.to_path_buf()))?;
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_path)
+        .map_err(|_| ApplicationError::WriteLog(log_path.to_path_buf()))
+}
+
+// Return the current local time formatted for log lines
+pub fn timestamp() -> String {
+    chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+// Write a line to the log file marking the start of a new run
+pub fn write_header(log: &mut File, command: &str) -> io::Result<()> {
+    writeln!(log, "[{}] --- Running \"{}\" ---", timestamp(), command)
+}
+
+// Write a line of output to the log file, prefixed with the current time
+pub fn write_line(log: &mut File, line: &[u8]) -> io::Result<()> {
+    write!(log, "[{}] ", timestamp())?;
+    log.write_all(line)?;
+    if !line.ends_with(b"\n") {
+        writeln!(log)?;
+    }
+    Ok(())
+}
+
+// Run a command, copying its stdout and stderr to the terminal and to the log file
+pub fn run_logged(
+    mut command: Command,
+    command_str: &str,
+    log_path: &Path,
+    grace_period: Duration,
+) -> Result<ExitStatus, ApplicationError> {
+    let mut log = open_log(log_path)?;
+    write_header(&mut log, command_str)
+        .map_err(|_| ApplicationError::WriteLog(log_path.to_path_buf()))?;
+    let log = Arc::new(Mutex::new(log));
+
+    let (mut child, group) = ForegroundGroup::spawn(
+        command.stdout(Stdio::piped()).stderr(Stdio::piped()),
+        true,
+        grace_period,
+    )
+    .map_err(|_| ApplicationError::RunScript(command_str.to_string()))?;
+    let readers = vec![
+        child
+            .stdout
+            .take()
+            .map(|stdout| tee(stdout, io::stdout(), log.clone())),
+        child
+            .stderr
+            .take()
+            .map(|stderr| tee(stderr, io::stderr(), log.clone())),
+    ];
+
+    let status = group
+        .wait(&mut child)
+        .map_err(|_| ApplicationError::RunScript(command_str.to_string()))?;
+    // Stop leftover processes in the group before waiting for the output pipes that they hold open
+    drop(group);
+    for reader in readers.into_iter().flatten() {
+        let _ = reader.join();
+    }
+    Ok(status)
+}
+
+// Start a process in the background server's process group that copies the server's output from
+// the pipe to the log file with timestamps
+// Background servers outlive server-room, so their output can't be copied by a thread like the
+// output of foreground servers
+pub fn spawn_writer(
+    output: PipeReader,
+    log_path: &Path,
+    pgid: u32,
+) -> Result<(), ApplicationError> {
+    let exe = env::current_exe().map_err(|_| ApplicationError::WriteLog(log_path.to_path_buf()))?;
+    let mut writer = Command::new(exe)
+        .arg("write-log")
+        .arg(log_path)
+        .stdin(output)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(pgid as i32)
+        .spawn()
+        .map_err(|_| ApplicationError::WriteLog(log_path.to_path_buf()))?;
+    // Reap the writer if this process is still running when it exits, like the daemon
+    thread::spawn(move || writer.wait());
+    Ok(())
+}
+
+// Copy lines from stdin to the log file with timestamps until stdin closes
+pub fn write_stdin(log_path: &Path) -> Result<(), ApplicationError> {
+    // Stopping the server signals its whole process group, but its last lines should still be
+    // written after it exits
+    unsafe {
+        libc::signal(libc::SIGINT, libc::SIG_IGN);
+        libc::signal(libc::SIGTERM, libc::SIG_IGN);
+    }
+    let mut log = open_log(log_path)?;
+    let mut stdin = BufReader::new(io::stdin().lock());
+    let mut line = vec![];
+    loop {
+        line.clear();
+        match stdin.read_until(b'\n', &mut line) {
+            Ok(0) | Err(_) => return Ok(()),
+            Ok(_) => write_line(&mut log, &line)
+                .map_err(|_| ApplicationError::WriteLog(log_path.to_path_buf()))?,
+        }
+    }
+}
+
+// Print the log file, limited to the last `lines` lines if provided
+// Returns the number of bytes in the log file so that following can pick up where printing stopped
+pub fn print_log(log_path: &Path, lines: Option<usize>) -> Result<u64, ApplicationError> {
+    let contents =
+        fs::read(log_path).map_err(|_| ApplicationError::ReadLog(log_path.to_path_buf()))?;
+    let start = get_tail_start(&contents, lines);
+    let mut stdout = io::stdout();
+    stdout
+        .write_all(&contents[start..])
+        .and_then(|_| stdout.flush())
+        .map_err(|_| ApplicationError::ReadLog(log_path.to_path_buf()))?;
+    Ok(contents.len() as u64)
+}
+
+// Read the last `lines` lines of the log file
+pub fn read_tail(log_path: &Path, lines: usize) -> Result<Vec<String>, ApplicationError> {
+    let contents =
+        fs::read(log_path).map_err(|_| ApplicationError::ReadLog(log_path.to_path_buf()))?;
+    let start = get_tail_start(&contents, Some(lines));
+    Ok(String::from_utf8_lossy(&contents[start..])
+        .lines()
+        .map(|line| line.to_string())
+        .collect())
+}
+
+// Read the output from the last run in the log file, limited to the last `lines` lines and without
+// timestamps
+pub fn read_last_run(log_path: &Path, lines: usize) -> Result<Vec<String>, ApplicationError> {
+    let tail = read_tail(log_path, lines + 1)?;
+    let run_start = tail
+        .iter()
+        .rposition(|line| line.contains("] --- Running \""))
+        .map_or(0, |index| index + 1);
+    Ok(tail[run_start..]
+        .iter()
+        .rev()
+        .take(lines)
+        .rev()
+        .map(|line| match line.split_once("] ") {
+            Some((timestamp, line)) if timestamp.starts_with('[') => line.to_string(),
+            _ => line.clone(),
+        })
+        .collect())
+}
+
+// Return the index in the contents where the last `lines` lines start, or the beginning of the
+// contents if no limit is provided
+fn get_tail_start(contents: &[u8], lines: Option<usize>) -> usize {
+    match lines {
+        Some(0) => contents.len(),
+        Some(lines) => {
+            // Ignore the trailing newline so that it doesn't count as an empty last line
+            let trimmed = contents.strip_suffix(b"\n").unwrap_or(contents);
+            trimmed
+                .iter()
+                .enumerate()
+                .rev()
+                .filter(|(_, byte)| **byte == b'\n')
+                .nth(lines - 1)
+                .map(|(index, _)| index + 1)
+                .unwrap_or(0)
+        }
+        None => 0,
+    }
+}
+
+// Continuously print new content appended to the log file, starting at the provided offset
+pub fn follow_log(log_path: &Path, offset: u64) -> Result<(), ApplicationError> {
+    let mut file =
+        File::open(log_path).map_err(|_| ApplicationError::ReadLog(log_path.to_path_buf()))?;
+    let mut position = offset;
+    let mut stdout = io::stdout();
+    let mut buffer = Vec::new();
+    loop {
+        let len = file
+            .metadata()
+            .map_err(|_| ApplicationError::ReadLog(log_path.to_path_buf()))?
+            .len();
+        // Start over from the beginning if the log file was truncated
+        if len < position {
+            position = 0;
+        }
+        if len > position {
+            file.seek(SeekFrom::Start(position))
+                .and_then(|_| file.read_to_end(&mut buffer))
+                .and_then(|_| stdout.write_all(&buffer))
+                .and_then(|_| stdout.flush())
+                .map_err(|_| ApplicationError::ReadLog(log_path.to_path_buf()))?;
+            position += buffer.len() as u64;
+            buffer.clear();
+        }
+        thread::sleep(Duration::from_millis(250));
+    }
+}
+
+// Copy each line from the reader to the terminal and to the log file with a timestamp
+fn tee<R: Read + Send + 'static, W: Write + Send + 'static>(
+    reader: R,
+    mut terminal: W,
+    log: Arc<Mutex<File>>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(reader);
+        let mut line = Vec::new();
+        while let Ok(bytes) = reader.read_until(b'\n', &mut line) {
+            if bytes == 0 {
+                break;
+            }
+            let _ = terminal.write_all(&line);
+            let _ = terminal.flush();
+            if let Ok(mut log) = log.lock() {
+                let _ = write_line(&mut log, &line);
+            }
+            line.clear();
+        }
+    })
+}
@@ -0,0 +1,87 @@
+use super::error::ApplicationError;
+use super::project::Project;
+use super::project_kind::ProjectKind;
+use super::script::Script;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// The file names that make looks for, in the order that make checks them
+const MAKEFILE_NAMES: [&str; 3] = ["GNUmakefile", "makefile", "Makefile"];
+
+// This struct represents projects with a Makefile, whose targets are run with make
+pub struct MakeProject;
+
+impl ProjectKind for MakeProject {
+    fn name(&self) -> &'static str {
+        "make"
+    }
+
+    fn detect(&self, dir: &Path) -> bool {
+        find_makefile(dir).is_some()
+    }
+
+    fn get_scripts(&self, project: &Project) -> Result<Vec<Script>, ApplicationError> {
+        get_scripts(&project.dir)
+    }
+}
+
+// Return the path to the project's Makefile, if it has one
+pub fn find_makefile(dir: &Path) -> Option<PathBuf> {
+    MAKEFILE_NAMES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+// Return the make commands for each of the top-level targets in the project's Makefile
+pub fn get_scripts(dir: &Path) -> Result<Vec<Script>, ApplicationError> {
+    let makefile_path =
+        find_makefile(dir).ok_or_else(|| ApplicationError::ReadMakefile(dir.join("Makefile")))?;
+    let content = fs::read_to_string(&makefile_path)
+        .map_err(|_| ApplicationError::ReadMakefile(makefile_path.clone()))?;
+
+    let mut targets = content
+        .lines()
+        .filter_map(parse_targets)
+        .flatten()
+        .collect::<Vec<_>>();
+    targets.sort();
+    targets.dedup();
+
+    Ok(targets
+        .into_iter()
+        .map(|target| {
+            let command = format!("make {}", target);
+            Script {
+                name: command.clone(),
+                command: command.clone(),
+                start_command: command,
+            }
+        })
+        .collect())
+}
+
+// Parse the targets from a rule line like "dev serve: build"
+// Recipes, variable assignments, special targets, and pattern rules are ignored
+fn parse_targets(line: &str) -> Option<Vec<String>> {
+    if line.starts_with('\t') || line.trim_start().starts_with('#') {
+        return None;
+    }
+
+    let (targets, rest) = line.split_once(':')?;
+    // ":=" and "::=" are variable assignments, not rules
+    if rest.starts_with('=') || rest.starts_with(":=") || targets.contains('=') {
+        return None;
+    }
+
+    let targets = targets
+        .split_whitespace()
+        .filter(|target| !target.starts_with('.') && !target.contains('%') && !target.contains('$'))
+        .map(|target| target.to_string())
+        .collect::<Vec<_>>();
+    if targets.is_empty() {
+        None
+    } else {
+        Some(targets)
+    }
+}
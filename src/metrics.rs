@@ -0,0 +1,103 @@
+use super::process::RuntimeState;
+use super::resources::ResourceSampler;
+use super::server::Server;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+// Describe the servers in the Prometheus text exposition format
+// Restart counts are only known for the servers that the daemon supervises
+pub fn render(
+    servers: &[&Server],
+    runtime_state: &RuntimeState,
+    restarts: &HashMap<String, u32>,
+) -> String {
+    let mut servers = servers.to_vec();
+    servers.sort_by_key(|server| server.name.as_str());
+    let mut sampler = ResourceSampler::default();
+    sampler.sample();
+
+    let mut up = vec![];
+    let mut restart_counts = vec![];
+    let mut start_times = vec![];
+    let mut cpu_seconds = vec![];
+    let mut memory_bytes = vec![];
+    for server in servers {
+        let labels = format!("server=\"{}\"", escape_label(&server.name));
+        let process = runtime_state.get(&server.name);
+        up.push((labels.clone(), if process.is_some() { 1.0 } else { 0.0 }));
+        restart_counts.push((
+            labels.clone(),
+            restarts.get(&server.name).copied().unwrap_or(0) as f64,
+        ));
+        let process = match process {
+            Some(process) => process,
+            None => continue,
+        };
+        start_times.push((labels.clone(), process.started_at as f64));
+        if let Some(usage) = sampler.get(process.pid) {
+            cpu_seconds.push((labels.clone(), usage.cpu_seconds));
+            memory_bytes.push((labels, usage.memory_bytes as f64));
+        }
+    }
+
+    let mut output = String::new();
+    write_metric(
+        &mut output,
+        "server_room_server_up",
+        "gauge",
+        "Whether the server is running",
+        &up,
+    );
+    write_metric(
+        &mut output,
+        "server_room_server_restarts_total",
+        "counter",
+        "The number of times that the daemon has restarted the server",
+        &restart_counts,
+    );
+    write_metric(
+        &mut output,
+        "server_room_server_start_time_seconds",
+        "gauge",
+        "When the running server was started, in seconds since the Unix epoch",
+        &start_times,
+    );
+    write_metric(
+        &mut output,
+        "server_room_server_cpu_seconds",
+        "gauge",
+        "The CPU time used by the server's running processes, which drops when a child process exits",
+        &cpu_seconds,
+    );
+    write_metric(
+        &mut output,
+        "server_room_server_memory_bytes",
+        "gauge",
+        "The resident memory used by the server's processes",
+        &memory_bytes,
+    );
+    output
+}
+
+// Append a metric's help text, type, and samples to the output
+fn write_metric(
+    output: &mut String,
+    name: &str,
+    metric_type: &str,
+    help: &str,
+    samples: &[(String, f64)],
+) {
+    let _ = writeln!(output, "# HELP {} {}", name, help);
+    let _ = writeln!(output, "# TYPE {} {}", name, metric_type);
+    for (labels, value) in samples {
+        let _ = writeln!(output, "{}{{{}}} {}", name, labels, value);
+    }
+}
+
+// Escape the characters that have special meaning inside label values
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
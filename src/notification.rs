@@ -0,0 +1,56 @@
+use super::health;
+use super::run_history::RunRecord;
+use notify_rust::Notification;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+// Enable or disable desktop notifications, which are enabled by default
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+// Notify the user that a server crashed
+pub fn crashed(record: &RunRecord) {
+    show(
+        format!("{} crashed", record.server),
+        format!(
+            "It exited {} after {}",
+            record.get_cause(),
+            record.get_uptime()
+        ),
+    );
+}
+
+// Notify the user that a server is about to be restarted
+pub fn restarting(server_name: &str, restarts: u32, delay: Duration) {
+    show(
+        format!("Restarting {}", server_name),
+        format!("Restart #{} in {}s", restarts, delay.as_secs()),
+    );
+}
+
+// Notify the user that a server is ready
+pub fn ready(server_name: &str, elapsed: Duration) {
+    show(
+        format!("{} is ready", server_name),
+        format!("It started in {}", health::format_elapsed(elapsed)),
+    );
+}
+
+// Show a desktop notification without waiting for it
+// Notifications are a convenience, so failures like a missing notification daemon are ignored
+fn show(summary: String, body: String) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    thread::spawn(move || {
+        let _ = Notification::new()
+            .appname("server-room")
+            .summary(&summary)
+            .body(&body)
+            .show();
+    });
+}
@@ -0,0 +1,116 @@
+use super::error::ApplicationError;
+use super::package_manager::PackageManager;
+use super::server::Server;
+use serde_json::Value;
+use std::process::{Command, Stdio};
+use std::thread;
+
+// This struct represents a dependency with a newer version available
+pub struct OutdatedPackage {
+    pub name: String,
+    // The installed version, or None if the dependency isn't installed
+    pub current: Option<String>,
+    // The newest version that satisfies the range in package.json
+    pub wanted: String,
+    pub latest: String,
+}
+
+// Find the outdated dependencies of every server, checking them concurrently because each check
+// queries the registry
+pub fn check_all(servers: &[&Server]) -> Vec<Result<Vec<OutdatedPackage>, ApplicationError>> {
+    thread::scope(|scope| {
+        servers
+            .iter()
+            .map(|server| (server, scope.spawn(move || check(server))))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|(server, handle)| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| Err(ApplicationError::CheckOutdated(server.name.clone())))
+            })
+            .collect()
+    })
+}
+
+// Find the server's outdated dependencies with its package manager
+pub fn check(server: &Server) -> Result<Vec<OutdatedPackage>, ApplicationError> {
+    let check_error = || ApplicationError::CheckOutdated(server.name.clone());
+    let package_manager = server.package_manager.unwrap_or_default();
+    let args: &[&str] = match package_manager {
+        PackageManager::Npm => &["outdated", "--json"],
+        PackageManager::Yarn => &["outdated", "--json"],
+        PackageManager::Pnpm => &["outdated", "--format", "json"],
+        PackageManager::Bun => {
+            return Err(ApplicationError::OutdatedUnsupported(package_manager));
+        }
+    };
+    let output = Command::new(package_manager.get_executable())
+        .args(args)
+        .current_dir(server.get_project_dir())
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|_| check_error())?;
+    // Package managers exit with a failure code when anything is outdated, so only the output
+    // determines whether the check worked
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    match package_manager {
+        PackageManager::Yarn => parse_yarn(&stdout),
+        _ => parse_npm(&stdout),
+    }
+    .ok_or_else(check_error)
+}
+
+// Parse the object that npm and pnpm print, which maps each package name to its versions
+fn parse_npm(output: &str) -> Option<Vec<OutdatedPackage>> {
+    // Nothing is printed when every dependency is up to date
+    if output.trim().is_empty() {
+        return Some(vec![]);
+    }
+    let packages = serde_json::from_str::<Value>(output).ok()?;
+    packages
+        .as_object()?
+        .iter()
+        .map(|(name, versions)| {
+            // Dependencies installed in several workspaces are listed once per workspace
+            let versions = versions
+                .as_array()
+                .and_then(|versions| versions.first())
+                .unwrap_or(versions);
+            Some(OutdatedPackage {
+                name: name.clone(),
+                current: versions["current"]
+                    .as_str()
+                    .map(|current| current.to_string()),
+                wanted: versions["wanted"].as_str()?.to_string(),
+                latest: versions["latest"].as_str()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+// Parse the JSON lines that yarn prints, where the table line contains a row for each package
+fn parse_yarn(output: &str) -> Option<Vec<OutdatedPackage>> {
+    let table = output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .find(|line| line["type"] == "table");
+    let table = match table {
+        Some(table) => table,
+        None => return Some(vec![]),
+    };
+    table["data"]["body"]
+        .as_array()?
+        .iter()
+        .map(|row| {
+            let column = |index: usize| row.get(index)?.as_str().map(|value| value.to_string());
+            Some(OutdatedPackage {
+                name: column(0)?,
+                current: column(1).filter(|current| current != "exotic"),
+                wanted: column(2)?,
+                latest: column(3)?,
+            })
+        })
+        .collect()
+}
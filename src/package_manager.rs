@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+use std::process::Command;
+
+// This enum represents the package manager that a project uses to run its scripts
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageManager {
+    #[default]
+    Npm,
+    Yarn,
+    Pnpm,
+    Bun,
+}
+
+impl fmt::Display for PackageManager {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}", self.get_executable())
+    }
+}
+
+impl PackageManager {
+    // Detect the package manager used by the project in the directory based on its lockfile,
+    // falling back to the default package manager
+    pub fn detect(dir: &Path, default: Self) -> Self {
+        [
+            ("pnpm-lock.yaml", PackageManager::Pnpm),
+            ("yarn.lock", PackageManager::Yarn),
+            ("bun.lockb", PackageManager::Bun),
+            ("bun.lock", PackageManager::Bun),
+            ("package-lock.json", PackageManager::Npm),
+        ]
+        .iter()
+        .find(|(lockfile, _)| dir.join(lockfile).is_file())
+        .map_or(default, |(_, package_manager)| *package_manager)
+    }
+
+    // Parse a package.json "packageManager" field like "pnpm@8.6.0+sha256.abc" into the package
+    // manager and its version
+    pub fn parse_field(field: &str) -> Option<(Self, Option<String>)> {
+        let (name, version) = match field.split_once('@') {
+            Some((name, version)) => {
+                // Ignore the integrity hash that corepack allows after the version
+                let version = version.split('+').next().unwrap_or(version);
+                (name, Some(version.to_string()))
+            }
+            None => (field, None),
+        };
+        let package_manager = match name {
+            "npm" => PackageManager::Npm,
+            "yarn" => PackageManager::Yarn,
+            "pnpm" => PackageManager::Pnpm,
+            "bun" => PackageManager::Bun,
+            _ => return None,
+        };
+        Some((package_manager, version))
+    }
+
+    // Return the version of the package manager installed on this machine, if any
+    pub fn get_installed_version(&self, dir: &Path) -> Option<String> {
+        let output = Command::new(self.get_executable())
+            .arg("--version")
+            .current_dir(dir)
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let version = String::from_utf8(output.stdout).ok()?;
+        Some(version.trim().trim_start_matches('v').to_string())
+    }
+
+    // Return the name of the package manager's executable
+    pub fn get_executable(&self) -> &'static str {
+        match self {
+            PackageManager::Npm => "npm",
+            PackageManager::Yarn => "yarn",
+            PackageManager::Pnpm => "pnpm",
+            PackageManager::Bun => "bun",
+        }
+    }
+
+    // Return the lockfiles that the package manager writes
+    pub fn get_lockfiles(&self) -> &'static [&'static str] {
+        match self {
+            PackageManager::Npm => &["package-lock.json"],
+            PackageManager::Yarn => &["yarn.lock"],
+            PackageManager::Pnpm => &["pnpm-lock.yaml"],
+            PackageManager::Bun => &["bun.lockb", "bun.lock"],
+        }
+    }
+
+    // Return the command that installs the project's dependencies
+    pub fn get_install_command(&self) -> String {
+        format!("{} install", self.get_executable())
+    }
+
+    // Return the command that runs the specified package.json script
+    pub fn get_run_command(&self, script_name: &str) -> String {
+        format!("{} run {}", self.get_executable(), script_name)
+    }
+}
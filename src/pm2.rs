@@ -0,0 +1,135 @@
+use super::error::ApplicationError;
+use super::import::ImportedServer;
+use serde_json::Value;
+use std::convert::TryInto;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+// Read the apps defined in a pm2 ecosystem file
+// JavaScript ecosystem files are evaluated with node, and JSON ecosystem files are read directly
+pub fn read_apps(path: &Path) -> Result<Vec<ImportedServer>, ApplicationError> {
+    let path =
+        fs::canonicalize(path).map_err(|_| ApplicationError::ReadPm2Config(path.to_path_buf()))?;
+    let parse_error = || ApplicationError::ParsePm2Config(path.clone());
+    let is_json = path
+        .extension()
+        .is_some_and(|extension| extension == "json");
+    let content = if is_json {
+        fs::read_to_string(&path).map_err(|_| ApplicationError::ReadPm2Config(path.clone()))?
+    } else {
+        evaluate_js(&path)?
+    };
+    let ecosystem = serde_json::from_str::<Value>(&content).map_err(|_| parse_error())?;
+    // Ecosystem files either export an object with an "apps" array or the array itself
+    let apps = ecosystem
+        .get("apps")
+        .unwrap_or(&ecosystem)
+        .as_array()
+        .ok_or_else(parse_error)?;
+    let base_dir = path.parent().ok_or_else(parse_error)?;
+    apps.iter()
+        .map(|app| parse_app(app, base_dir).ok_or_else(parse_error))
+        .collect()
+}
+
+// Print the object that a JavaScript ecosystem file exports as JSON
+fn evaluate_js(path: &Path) -> Result<String, ApplicationError> {
+    let output = Command::new("node")
+        .arg("-e")
+        .arg("process.stdout.write(JSON.stringify(require(process.argv[1])))")
+        .arg(path)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|_| ApplicationError::ParsePm2Config(path.to_path_buf()))?;
+    if !output.status.success() {
+        return Err(ApplicationError::ParsePm2Config(path.to_path_buf()));
+    }
+    String::from_utf8(output.stdout)
+        .map_err(|_| ApplicationError::ParsePm2Config(path.to_path_buf()))
+}
+
+// Convert a pm2 app definition into a server
+fn parse_app(app: &Value, base_dir: &Path) -> Option<ImportedServer> {
+    let script = app.get("script")?.as_str()?;
+    let name = match app.get("name").and_then(Value::as_str) {
+        Some(name) => name.to_string(),
+        None => Path::new(script).file_stem()?.to_str()?.to_string(),
+    };
+    // Relative working directories are relative to the ecosystem file
+    let dir = match app.get("cwd").and_then(Value::as_str) {
+        Some(cwd) => fs::canonicalize(base_dir.join(cwd)).unwrap_or_else(|_| base_dir.join(cwd)),
+        None => base_dir.to_path_buf(),
+    };
+    let env = app.get("env").and_then(Value::as_object);
+    let port = app
+        .get("port")
+        .or_else(|| env?.get("PORT"))
+        .and_then(parse_port);
+
+    let mut words = vec![];
+    match app.get("interpreter").and_then(Value::as_str) {
+        Some("none") => {}
+        Some(interpreter) => words.push(interpreter.to_string()),
+        // pm2 runs JavaScript files with node by default
+        None if [".js", ".cjs", ".mjs"]
+            .iter()
+            .any(|extension| script.ends_with(extension)) =>
+        {
+            words.push("node".to_string())
+        }
+        None => {}
+    }
+    words.push(quote(script));
+    match app.get("args") {
+        Some(Value::String(args)) => words.push(args.clone()),
+        Some(Value::Array(args)) => words.extend(args.iter().filter_map(Value::as_str).map(quote)),
+        _ => {}
+    }
+
+    // The port is injected when the server starts, so only the other variables are set here
+    let assignments = env
+        .into_iter()
+        .flatten()
+        .filter(|(key, _)| *key != "PORT")
+        .filter_map(|(key, value)| {
+            let value = match value {
+                Value::String(value) => value.clone(),
+                Value::Number(_) | Value::Bool(_) => value.to_string(),
+                _ => return None,
+            };
+            Some(format!("{}={}", key, quote(&value)))
+        });
+    let start_command = assignments.chain(words).collect::<Vec<_>>().join(" ");
+
+    Some(ImportedServer {
+        name,
+        dir,
+        start_command,
+        port,
+        domain: None,
+    })
+}
+
+// Parse a port that is either a number or a numeric string
+fn parse_port(value: &Value) -> Option<u16> {
+    match value {
+        Value::Number(port) => port.as_u64()?.try_into().ok(),
+        Value::String(port) => port.parse().ok(),
+        _ => None,
+    }
+}
+
+// Quote the word for the shell if it contains special characters
+fn quote(word: &str) -> String {
+    if !word.is_empty()
+        && word
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || "-_./:=@,+".contains(char))
+    {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', "'\\''"))
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, TcpListener};
+use std::ops::RangeInclusive;
+use std::process::Command;
+use std::str::FromStr;
+
+// This enum represents a port provided on the command line, which can be "auto" to pick a free port
+#[derive(Clone, Copy, Debug)]
+pub enum PortChoice {
+    Auto,
+    Fixed(u16),
+}
+
+impl FromStr for PortChoice {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value == "auto" {
+            return Ok(PortChoice::Auto);
+        }
+        value
+            .parse::<u16>()
+            .map(PortChoice::Fixed)
+            .map_err(|_| "must be a port number or \"auto\"".to_string())
+    }
+}
+
+// This struct represents a process listening on a port
+pub struct PortOwner {
+    pub pid: u32,
+    pub name: String,
+}
+
+impl fmt::Display for PortOwner {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{} (PID {})", self.name, self.pid)
+    }
+}
+
+// Determine whether nothing is currently listening on the local port
+// Servers may listen on any interface, and some platforms let a loopback bind succeed even when
+// another process is listening on all interfaces, so every address is probed
+pub fn is_free(port: u16) -> bool {
+    let addresses: [IpAddr; 3] = [
+        Ipv4Addr::LOCALHOST.into(),
+        Ipv4Addr::UNSPECIFIED.into(),
+        Ipv6Addr::UNSPECIFIED.into(),
+    ];
+    addresses
+        .iter()
+        .all(|address| match TcpListener::bind((*address, port)) {
+            Ok(_) => true,
+            // The address can't be bound at all when IPv6 is disabled, which doesn't mean that the
+            // port is taken
+            Err(err) => err.kind() == io::ErrorKind::AddrNotAvailable,
+        })
+}
+
+// Return the lowest ports in the range that aren't reserved and aren't currently bound
+pub fn find_free_ports(
+    range: RangeInclusive<u16>,
+    reserved: &HashSet<u16>,
+    count: usize,
+) -> Vec<u16> {
+    range
+        .filter(|port| !reserved.contains(port) && is_free(*port))
+        .take(count)
+        .collect()
+}
+
+// Return the processes listening on the local port
+// Uses lsof, so the owners can't be determined if it isn't installed
+pub fn find_owners(port: u16) -> Vec<PortOwner> {
+    let output = match Command::new("lsof")
+        .args(["-nP", &format!("-iTCP:{}", port), "-sTCP:LISTEN", "-Fpc"])
+        .output()
+    {
+        Ok(output) => output,
+        Err(_) => return vec![],
+    };
+
+    // lsof prints a "p<pid>" line for each process followed by a "c<command>" line
+    let mut owners: Vec<PortOwner> = vec![];
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if let Some(pid) = line.strip_prefix('p').and_then(|pid| pid.parse().ok()) {
+            owners.push(PortOwner {
+                pid,
+                name: String::new(),
+            });
+        } else if let (Some(name), Some(owner)) = (line.strip_prefix('c'), owners.last_mut()) {
+            owner.name = name.to_string();
+        }
+    }
+    owners
+}
@@ -0,0 +1,564 @@
+use super::error::ApplicationError;
+use super::logging;
+use super::run_history::{self, RunRecord};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::os::unix::io::AsRawFd;
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+
+// The process groups of the servers running in the foreground, which receive the SIGINT and
+// SIGTERM signals that server-room receives
+// Signal handlers can't take locks, so each group is stored in an atomic slot, where 0 is empty
+// and RESERVED_SLOT is claimed by a group that is still being spawned
+static FOREGROUND_GROUPS: [AtomicI32; 64] = [const { AtomicI32::new(0) }; 64];
+const RESERVED_SLOT: i32 = -1;
+
+// The number of foreground groups running and the SIGINT and SIGTERM handlers that were installed
+// before the first of them started, which are restored once the last of them stops
+static PREVIOUS_HANDLERS: Mutex<(usize, libc::sighandler_t, libc::sighandler_t)> =
+    Mutex::new((0, 0, 0));
+
+// Whether server-room received a signal telling its foreground servers to stop
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+// This struct represents a server process that was started in the background
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RunningProcess {
+    pub server: String,
+    pub pid: u32,
+    pub started_at: u64,
+}
+
+// This struct represents the processes that server-room has started in the background
+// It is stored as a vector in the runtime state file, but is deserialized into a hashmap of
+// processes, where the key is the server name
+#[derive(Clone)]
+pub struct RuntimeState {
+    processes: HashMap<String, RunningProcess>,
+    state_path: PathBuf,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct RawRuntimeState {
+    processes: Vec<RunningProcess>,
+}
+
+impl RuntimeState {
+    // Load the runtime state from disk, ignoring processes that have since exited
+    // Processes that exited without anyone noticing, like servers started by a `run --detach` that
+    // has since exited, are recorded in the run history and forgotten
+    pub fn load(state_path: PathBuf) -> Result<RuntimeState, ApplicationError> {
+        let state_str =
+            fs::read_to_string(&state_path).unwrap_or_else(|_| "processes = []".to_string());
+        let raw_state: RawRuntimeState = toml::from_str(&state_str)
+            .map_err(|_| ApplicationError::ParseRuntimeState(state_path.clone()))?;
+        let (processes, exited): (Vec<_>, Vec<_>) = raw_state
+            .processes
+            .into_iter()
+            .partition(|process| is_alive(process.pid));
+        let runtime_state = RuntimeState {
+            processes: processes
+                .into_iter()
+                .map(|process| (process.server.clone(), process))
+                .collect(),
+            state_path,
+        };
+        for process in exited {
+            if let Err(err) = runtime_state.record_exit(&process.server, process.pid, None) {
+                warn!("Couldn't record how {} exited: {}", process.server, err);
+            }
+        }
+        Ok(runtime_state)
+    }
+
+    // Write the runtime state to disk
+    pub fn flush(&self) -> Result<(), ApplicationError> {
+        let mut processes = self.processes.clone().into_values().collect::<Vec<_>>();
+        processes.sort_by(|process1, process2| process1.server.cmp(&process2.server));
+
+        let stringified = toml::to_string_pretty(&RawRuntimeState { processes })
+            .map_err(|_| ApplicationError::WriteRuntimeState(self.state_path.clone()))?;
+        let parent_dir = self
+            .state_path
+            .parent()
+            .ok_or_else(|| ApplicationError::WriteRuntimeState(self.state_path.clone()))?;
+        fs::create_dir_all(parent_dir)
+            .map_err(|_| ApplicationError::WriteRuntimeState(self.state_path.clone()))?;
+        fs::write(&self.state_path, stringified)
+            .map_err(|_| ApplicationError::WriteRuntimeState(self.state_path.clone()))
+    }
+
+    // Permanently record that each of the servers is running in the background
+    pub fn record(&self, processes: &[(&str, u32)]) -> Result<(), ApplicationError> {
+        let mut new_state = self.clone();
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+        for (server_name, pid) in processes {
+            new_state.processes.insert(
+                server_name.to_string(),
+                RunningProcess {
+                    server: server_name.to_string(),
+                    pid: *pid,
+                    started_at,
+                },
+            );
+        }
+        new_state.flush()
+    }
+
+    // Permanently forget about a server's background process after it exits and record the run
+    // in the history, returning the record
+    // The exit status is missing when the process wasn't a child of server-room
+    // Only the first of the processes that notice the exit records it, so nothing is returned if
+    // the process was already forgotten
+    pub fn record_exit(
+        &self,
+        server_name: &str,
+        pid: u32,
+        status: Option<&ExitStatus>,
+    ) -> Result<Option<RunRecord>, ApplicationError> {
+        let write_error = || ApplicationError::WriteRuntimeState(self.state_path.clone());
+        if let Some(parent_dir) = self.state_path.parent() {
+            fs::create_dir_all(parent_dir).map_err(|_| write_error())?;
+        }
+        // Lock the state file so that other processes noticing the same exit wait until it is
+        // forgotten
+        // The lock is released when the file is closed
+        let mut file = fs::OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&self.state_path)
+            .map_err(|_| write_error())?;
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+            return Err(write_error());
+        }
+        let mut state_str = String::new();
+        file.read_to_string(&mut state_str)
+            .map_err(|_| ApplicationError::ParseRuntimeState(self.state_path.clone()))?;
+        if state_str.trim().is_empty() {
+            state_str = "processes = []".to_string();
+        }
+        let mut raw_state: RawRuntimeState = toml::from_str(&state_str)
+            .map_err(|_| ApplicationError::ParseRuntimeState(self.state_path.clone()))?;
+        let index = match raw_state
+            .processes
+            .iter()
+            .position(|process| process.server == server_name && process.pid == pid)
+        {
+            Some(index) => index,
+            None => return Ok(None),
+        };
+        let process = raw_state.processes.remove(index);
+
+        let record = match status {
+            Some(status) => RunRecord::new(server_name, process.started_at, status),
+            None => RunRecord::new_unknown(server_name, process.started_at),
+        };
+        run_history::record(&self.history_path(), record.clone())?;
+        let stringified = toml::to_string_pretty(&raw_state).map_err(|_| write_error())?;
+        fs::write(&self.state_path, stringified).map_err(|_| write_error())?;
+        Ok(Some(record))
+    }
+
+    // Return the path to the history of how servers exited, which lives next to the runtime state
+    // file
+    fn history_path(&self) -> PathBuf {
+        self.state_path.with_file_name("history.toml")
+    }
+
+    // Return the background process of the specified server, if it is running
+    pub fn get(&self, server_name: &str) -> Option<&RunningProcess> {
+        self.processes.get(server_name)
+    }
+
+    // Return all of the running background processes, sorted by server name
+    pub fn get_all(&self) -> Vec<&RunningProcess> {
+        let mut processes = self.processes.values().collect::<Vec<_>>();
+        processes.sort_by(|process1, process2| process1.server.cmp(&process2.server));
+        processes
+    }
+}
+
+// This enum represents the signal that stopped a process
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum StopPhase {
+    // The process exited gracefully after SIGTERM
+    Terminated,
+    // The process was still running after the grace period and was killed with SIGKILL
+    Killed,
+}
+
+impl fmt::Display for StopPhase {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> fmt::Result {
+        let phase = match self {
+            StopPhase::Terminated => "exited after SIGTERM",
+            StopPhase::Killed => "killed with SIGKILL after the grace period",
+        };
+        write!(formatter, "{}", phase)
+    }
+}
+
+// Spawn a command in its own process group without waiting for it to exit
+// If a log path is provided, the command's output is appended to it
+pub fn spawn_detached(
+    mut command: Command,
+    command_str: &str,
+    log_path: Option<&Path>,
+) -> Result<u32, ApplicationError> {
+    // The output is piped to a separate process that timestamps it
+    let (stdout, stderr, output) = match log_path {
+        Some(log_path) => {
+            let mut log = logging::open_log(log_path)?;
+            logging::write_header(&mut log, command_str)
+                .map_err(|_| ApplicationError::WriteLog(log_path.to_path_buf()))?;
+            let (reader, writer) =
+                io::pipe().map_err(|_| ApplicationError::WriteLog(log_path.to_path_buf()))?;
+            let writer_clone = writer
+                .try_clone()
+                .map_err(|_| ApplicationError::WriteLog(log_path.to_path_buf()))?;
+            (
+                Stdio::from(writer),
+                Stdio::from(writer_clone),
+                Some((log_path, reader)),
+            )
+        }
+        None => (Stdio::null(), Stdio::null(), None),
+    };
+    let pid = command
+        .stdin(Stdio::null())
+        .stdout(stdout)
+        .stderr(stderr)
+        .process_group(0)
+        .spawn()
+        .map(|child| child.id())
+        .map_err(|_| ApplicationError::RunScript(command_str.to_string()))?;
+    info!(
+        "Spawned \"{}\" in the background (PID {})",
+        command_str, pid
+    );
+    // Close this process's end of the pipe so that the log writer exits along with the server
+    drop(command);
+
+    if let Some((log_path, reader)) = output {
+        if let Err(err) = logging::spawn_writer(reader, log_path, pid) {
+            // The server would be killed by SIGPIPE as soon as it wrote anything
+            let _ = terminate(pid, Duration::ZERO);
+            return Err(err);
+        }
+    }
+    Ok(pid)
+}
+
+// Wait for a child process of server-room to exit and return its exit status
+fn wait_for_exit(pid: u32) -> Option<ExitStatus> {
+    let mut status = 0;
+    let result = unsafe { libc::waitpid(pid as libc::pid_t, &mut status, 0) };
+    if result == pid as libc::pid_t {
+        Some(ExitStatus::from_raw(status))
+    } else {
+        None
+    }
+}
+
+// Wait for a child process of server-room to exit, then call the callback with its exit status
+// before reaping it
+// Other processes see the child as alive until it is reaped, so they can't record its exit
+// without a status while the callback records it
+pub fn wait_for_exit_then<T>(pid: u32, on_exit: impl FnOnce(Option<ExitStatus>) -> T) -> T {
+    let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+    let result = unsafe {
+        libc::waitid(
+            libc::P_PID,
+            pid as libc::id_t,
+            &mut info,
+            libc::WEXITED | libc::WNOWAIT,
+        )
+    };
+    // Rebuild the raw wait status from the fields that waitid reports
+    let status = (result == 0).then(|| {
+        let value = unsafe { info.si_status() };
+        ExitStatus::from_raw(match info.si_code {
+            libc::CLD_EXITED => value << 8,
+            libc::CLD_DUMPED => value | 0x80,
+            _ => value,
+        })
+    });
+    let output = on_exit(status);
+    wait_for_exit(pid);
+    output
+}
+
+// Wait for detached children on background threads so that they don't linger as zombies once
+// they exit, which would keep them looking alive, then record how they exited
+// Long-running processes that start servers in the background, like the proxy, must call this
+pub fn reap_in_background(runtime_state: &RuntimeState, server_names: &[&str], pids: &[u32]) {
+    for (server_name, pid) in server_names.iter().zip(pids.iter().copied()) {
+        let runtime_state = runtime_state.clone();
+        let server_name = server_name.to_string();
+        thread::spawn(move || {
+            wait_for_exit_then(pid, |status| {
+                if let Err(err) = runtime_state.record_exit(&server_name, pid, status.as_ref()) {
+                    warn!("Couldn't record how {} exited: {}", server_name, err);
+                }
+            })
+        });
+    }
+}
+
+// Convert the PID to the type that libc expects, rejecting PIDs that kill would treat as more than
+// one process, like 0 for server-room's own process group or PIDs that overflow into negative
+// process group IDs
+fn to_pid_t(pid: u32) -> Option<libc::pid_t> {
+    libc::pid_t::try_from(pid).ok().filter(|pid| *pid > 0)
+}
+
+// Determine whether a process with the specified PID is still running
+pub fn is_alive(pid: u32) -> bool {
+    // Signal 0 performs error checking without actually sending a signal
+    to_pid_t(pid).is_some_and(|pid| unsafe { libc::kill(pid, 0) == 0 })
+}
+
+// Terminate a detached process group, escalating from SIGTERM to SIGKILL if it doesn't exit
+// within the grace period
+pub fn terminate(pid: u32, grace_period: Duration) -> Result<StopPhase, ApplicationError> {
+    let pid_t = to_pid_t(pid).ok_or(ApplicationError::InvalidPid(pid))?;
+    // Detached processes are process group leaders, so signal the whole group to also stop
+    // any children that the start command spawned
+    signal_with_escalation(-pid_t, pid, grace_period)
+}
+
+// Terminate a single process, escalating from SIGTERM to SIGKILL if it doesn't exit within the
+// grace period
+pub fn kill(pid: u32, grace_period: Duration) -> Result<StopPhase, ApplicationError> {
+    let pid_t = to_pid_t(pid).ok_or(ApplicationError::InvalidPid(pid))?;
+    signal_with_escalation(pid_t, pid, grace_period)
+}
+
+// Send SIGTERM to the target, then SIGKILL if any of its processes are still running after the
+// grace period
+fn signal_with_escalation(
+    target: libc::pid_t,
+    pid: u32,
+    grace_period: Duration,
+) -> Result<StopPhase, ApplicationError> {
+    if unsafe { libc::kill(target, libc::SIGTERM) } != 0 {
+        return Err(ApplicationError::StopProcess(pid));
+    }
+
+    // Signal 0 checks whether any process in the target still exists
+    let deadline = Instant::now() + grace_period;
+    while Instant::now() < deadline {
+        if unsafe { libc::kill(target, 0) } != 0 {
+            return Ok(StopPhase::Terminated);
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    debug!(
+        "PID {} didn't exit within {}s of SIGTERM, so sending SIGKILL",
+        pid,
+        grace_period.as_secs()
+    );
+    if unsafe { libc::kill(target, libc::SIGKILL) } != 0 && is_alive(pid) {
+        return Err(ApplicationError::StopProcess(pid));
+    }
+    Ok(StopPhase::Killed)
+}
+
+// This struct represents a server's start command running in the foreground in its own process
+// group, so that every process that the start command spawns can be signaled together
+// Dropping it stops anything still running in the group
+pub struct ForegroundGroup {
+    pgid: libc::pid_t,
+    // Whether the group was given the terminal and must give it back
+    has_terminal: bool,
+    // The time that processes left in the group get to exit after SIGTERM before being killed
+    grace_period: Duration,
+    // The slot in FOREGROUND_GROUPS that holds the group
+    slot: &'static AtomicI32,
+}
+
+impl ForegroundGroup {
+    // Spawn the command in a new process group, forwarding server-room's signals to the group
+    // If the group takes the terminal, it reads input and receives Ctrl-C directly
+    pub fn spawn(
+        command: &mut Command,
+        take_terminal: bool,
+        grace_period: Duration,
+    ) -> io::Result<(Child, Self)> {
+        // Claim a slot before spawning so that the group never runs without receiving signals
+        let slot = FOREGROUND_GROUPS
+            .iter()
+            .find(|slot| {
+                slot.compare_exchange(0, RESERVED_SLOT, Ordering::SeqCst, Ordering::SeqCst)
+                    .is_ok()
+            })
+            .ok_or_else(|| io::Error::other("Too many servers are running in the foreground"))?;
+        install_signal_handlers();
+        let child = match command.process_group(0).spawn() {
+            Ok(child) => child,
+            Err(err) => {
+                slot.store(0, Ordering::SeqCst);
+                restore_signal_handlers();
+                return Err(err);
+            }
+        };
+        debug!(
+            "Spawned {:?} in the foreground (PID {})",
+            command,
+            child.id()
+        );
+        let pgid = child.id() as libc::pid_t;
+        let group = ForegroundGroup {
+            pgid,
+            has_terminal: take_terminal
+                && unsafe { libc::isatty(libc::STDIN_FILENO) } == 1
+                && set_terminal_group(pgid),
+            grace_period,
+            slot,
+        };
+        if group.has_terminal {
+            // Resume the group in case it tried to read input before it had the terminal
+            unsafe { libc::kill(-pgid, libc::SIGCONT) };
+        }
+        slot.store(pgid, Ordering::SeqCst);
+        // A signal that arrived while spawning wasn't forwarded to the new group
+        if SHUTTING_DOWN.load(Ordering::SeqCst) {
+            unsafe { libc::kill(-pgid, libc::SIGTERM) };
+        }
+        Ok((child, group))
+    }
+
+    // Wait for the group's leader to exit
+    // When the user suspends a group that has the terminal with Ctrl-Z, server-room takes the
+    // terminal back and suspends itself too, then hands the terminal back once the shell resumes it
+    pub fn wait(&self, child: &mut Child) -> io::Result<ExitStatus> {
+        let pid = child.id() as libc::pid_t;
+        loop {
+            let mut status = 0;
+            if unsafe { libc::waitpid(pid, &mut status, libc::WUNTRACED) } == -1 {
+                let err = io::Error::last_os_error();
+                if err.kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(err);
+            }
+            if !libc::WIFSTOPPED(status) {
+                return Ok(ExitStatus::from_raw(status));
+            }
+            if self.has_terminal {
+                debug!("PID {} was suspended, so suspending server-room", pid);
+                set_terminal_group(unsafe { libc::getpgrp() });
+                unsafe { libc::raise(libc::SIGTSTP) };
+                set_terminal_group(self.pgid);
+                unsafe { libc::kill(-self.pgid, libc::SIGCONT) };
+            }
+        }
+    }
+
+    // Stop every foreground group and keep them from restarting, like when server-room receives
+    // SIGTERM
+    pub fn stop_all() {
+        forward_signal(libc::SIGTERM);
+    }
+
+    // Determine whether server-room received a signal telling its foreground servers to stop
+    pub fn is_shutting_down() -> bool {
+        SHUTTING_DOWN.load(Ordering::SeqCst)
+    }
+
+    // Stop children like vite or nodemon that outlived the start command and would otherwise keep
+    // their ports bound
+    fn stop_leftovers(&self) {
+        if unsafe { libc::kill(-self.pgid, libc::SIGTERM) } != 0 {
+            return;
+        }
+        let deadline = Instant::now() + self.grace_period;
+        while Instant::now() < deadline {
+            if unsafe { libc::kill(-self.pgid, 0) } != 0 {
+                return;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        unsafe { libc::kill(-self.pgid, libc::SIGKILL) };
+    }
+}
+
+impl Drop for ForegroundGroup {
+    fn drop(&mut self) {
+        self.slot.store(0, Ordering::SeqCst);
+        if self.has_terminal {
+            set_terminal_group(unsafe { libc::getpgrp() });
+        }
+        self.stop_leftovers();
+        restore_signal_handlers();
+    }
+}
+
+// Forward SIGINT and SIGTERM to the foreground groups, remembering the previous handlers if no
+// other group is running
+fn install_signal_handlers() {
+    let mut handlers = PREVIOUS_HANDLERS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if handlers.0 == 0 {
+        let handler = forward_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        unsafe {
+            handlers.1 = libc::signal(libc::SIGINT, handler);
+            handlers.2 = libc::signal(libc::SIGTERM, handler);
+        }
+    }
+    handlers.0 += 1;
+}
+
+// Restore the previous SIGINT and SIGTERM handlers once no foreground groups are running
+fn restore_signal_handlers() {
+    let mut handlers = PREVIOUS_HANDLERS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    handlers.0 = handlers.0.saturating_sub(1);
+    if handlers.0 == 0 {
+        unsafe {
+            libc::signal(libc::SIGINT, handlers.1);
+            libc::signal(libc::SIGTERM, handlers.2);
+        }
+    }
+}
+
+// Forward the signal to every foreground process group
+extern "C" fn forward_signal(signal: libc::c_int) {
+    SHUTTING_DOWN.store(true, Ordering::SeqCst);
+    for slot in FOREGROUND_GROUPS.iter() {
+        let pgid = slot.load(Ordering::SeqCst);
+        if pgid > 0 {
+            unsafe { libc::kill(-pgid, signal) };
+        }
+    }
+}
+
+// Make the process group the terminal's foreground group, returning whether it succeeded
+fn set_terminal_group(pgid: libc::pid_t) -> bool {
+    unsafe {
+        // Changing the foreground group from a background group raises SIGTTOU, which would
+        // otherwise stop server-room when it takes the terminal back
+        let previous_handler = libc::signal(libc::SIGTTOU, libc::SIG_IGN);
+        let result = libc::tcsetpgrp(libc::STDIN_FILENO, pgid);
+        libc::signal(libc::SIGTTOU, previous_handler);
+        result == 0
+    }
+}
@@ -0,0 +1,61 @@
+use super::error::ApplicationError;
+use super::project::Project;
+use super::project_kind::ProjectKind;
+use super::script::Script;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// This struct represents projects with a Procfile, whose processes are run directly
+pub struct ProcfileProject;
+
+impl ProjectKind for ProcfileProject {
+    fn name(&self) -> &'static str {
+        "procfile"
+    }
+
+    fn detect(&self, dir: &Path) -> bool {
+        get_procfile(dir).is_file()
+    }
+
+    fn get_scripts(&self, project: &Project) -> Result<Vec<Script>, ApplicationError> {
+        get_scripts(&project.dir)
+    }
+}
+
+// Return the path to the project's Procfile
+pub fn get_procfile(dir: &Path) -> PathBuf {
+    dir.join("Procfile")
+}
+
+// Return a script for each of the process types declared in the project's Procfile
+pub fn get_scripts(dir: &Path) -> Result<Vec<Script>, ApplicationError> {
+    let procfile_path = get_procfile(dir);
+    let content = fs::read_to_string(&procfile_path)
+        .map_err(|_| ApplicationError::ReadProcfile(procfile_path.clone()))?;
+    Ok(content.lines().filter_map(parse_process).collect())
+}
+
+// Parse a Procfile line like "web: node server.js"
+fn parse_process(line: &str) -> Option<Script> {
+    let line = line.trim();
+    if line.starts_with('#') {
+        return None;
+    }
+
+    let (name, command) = line.split_once(':')?;
+    let (name, command) = (name.trim(), command.trim());
+    if name.is_empty()
+        || command.is_empty()
+        || !name
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || char == '_' || char == '-')
+    {
+        return None;
+    }
+
+    Some(Script {
+        name: name.to_string(),
+        command: command.to_string(),
+        start_command: command.to_string(),
+    })
+}
@@ -1,15 +1,27 @@
 use super::error::ApplicationError;
+use super::package_manager::PackageManager;
+use super::procfile;
+use super::project_kind::{self, ProjectKind};
 use super::script::Script;
 use serde_json::Value;
 use std::fmt;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-// This struct represents a project on the filesystem
+// The names of scripts that usually start a server, in order of preference
+pub const PRIORITY_SCRIPTS: [&str; 3] = ["dev", "start", "run"];
+
+/// A project on the filesystem whose start scripts can be run as servers
+///
+/// Load one with [`Project::from_path`].
 #[derive(Clone, Debug)]
 pub struct Project {
     pub name: String,
     pub dir: PathBuf,
+    // The package manager is only known for projects with a package.json file
+    pub package_manager: Option<PackageManager>,
+    // The package manager version requested by package.json's "packageManager" field
+    pub package_manager_version: Option<String>,
 }
 
 impl fmt::Display for Project {
@@ -20,38 +32,95 @@ impl fmt::Display for Project {
 
 impl Project {
     // Try to create a project based on a path
-    pub fn from_path(project_path: PathBuf) -> Result<Self, ApplicationError> {
+    // Projects don't need a package.json file, but it is required to use package.json scripts
+    // The default package manager is used for projects without a lockfile
+    pub fn from_path(
+        project_path: PathBuf,
+        default_package_manager: PackageManager,
+    ) -> Result<Self, ApplicationError> {
         let name = project_path
             .file_name()
             .ok_or_else(|| ApplicationError::ParsePath(project_path.clone()))?
             .to_str()
             .ok_or_else(|| ApplicationError::ParsePath(project_path.clone()))?
             .to_string();
-        let project = Project {
+        let mut project = Project {
             name,
+            package_manager: None,
+            package_manager_version: None,
             dir: project_path,
         };
-        let metadata = fs::metadata(project.get_package_json())
-            .map_err(|_| ApplicationError::ReadPackageJson(project.clone()))?;
+        if !project.has_package_json() {
+            return Ok(project);
+        }
+        project.package_manager = Some(PackageManager::detect(
+            &project.dir,
+            default_package_manager,
+        ));
 
-        if !metadata.is_file() {
-            return Err(ApplicationError::ReadPackageJson(project));
+        // Prefer the package manager that package.json explicitly requests over the lockfile
+        // Malformed package.json files are reported later when the scripts are read
+        if let Some((package_manager, version)) = project
+            .read_package_json()
+            .ok()
+            .and_then(|package_json| {
+                package_json["packageManager"]
+                    .as_str()
+                    .and_then(PackageManager::parse_field)
+            })
+        {
+            project.package_manager = Some(package_manager);
+            project.package_manager_version = version;
         }
 
         Ok(project)
     }
 
-    // Return a vector of the project's start scripts
-    pub fn get_start_scripts(&self) -> Result<Vec<Script>, ApplicationError> {
+    // Read and parse the project's package.json file
+    fn read_package_json(&self) -> Result<Value, ApplicationError> {
         let package_json_path = self.get_package_json();
         let package_json_content = fs::read_to_string(&package_json_path)
             .map_err(|_| ApplicationError::ReadPackageJson(self.clone()))?;
-        let package_json: Value = serde_json::from_str(&package_json_content).map_err(|_| {
+        serde_json::from_str(&package_json_content).map_err(|_| {
             ApplicationError::MalformedPackageJson {
-                path: package_json_path.clone(),
+                path: package_json_path,
                 cause: "contains invalid JSON".to_string(),
             }
-        })?;
+        })
+    }
+
+    // Return the installed version of the project's package manager if it differs from the
+    // version requested by package.json
+    pub fn get_package_manager_mismatch(&self) -> Option<String> {
+        let requested_version = self.package_manager_version.as_ref()?;
+        let installed_version = self
+            .package_manager?
+            .get_installed_version(&self.dir)?;
+        if &installed_version == requested_version {
+            None
+        } else {
+            Some(installed_version)
+        }
+    }
+
+    // Return a vector of the project's start scripts from each of its project kinds
+    pub fn get_start_scripts(&self) -> Result<Vec<Script>, ApplicationError> {
+        let mut scripts = vec![];
+        for kind in project_kind::detect(&self.dir) {
+            scripts.extend(kind.get_scripts(self)?);
+        }
+        // Projects without any other start scripts need a package.json file
+        if scripts.is_empty() {
+            scripts.extend(self.get_npm_scripts()?);
+        }
+        Ok(scripts)
+    }
+
+    // Return a vector of the scripts in the project's package.json file
+    fn get_npm_scripts(&self) -> Result<Vec<Script>, ApplicationError> {
+        let package_manager = self.package_manager.unwrap_or_default();
+        let package_json_path = self.get_package_json();
+        let package_json = self.read_package_json()?;
         let scripts = package_json["scripts"].as_object().ok_or_else(|| {
             ApplicationError::MalformedPackageJson {
                 path: package_json_path.clone(),
@@ -69,6 +138,7 @@ impl Project {
             .map(|(name, command)| Script {
                 name: name.to_string(),
                 command: command.to_string(),
+                start_command: package_manager.get_run_command(name),
             })
             .collect::<Vec<_>>())
     }
@@ -85,8 +155,96 @@ impl Project {
             })
     }
 
+    // Return the script most likely to start the project's server
+    pub fn get_default_start_script(&self) -> Result<Script, ApplicationError> {
+        let mut scripts = self.get_start_scripts()?;
+        if scripts.is_empty() {
+            return Err(ApplicationError::NoStartScripts(self.dir.clone()));
+        }
+        scripts.sort_by(|script1, script2| script1.name.cmp(&script2.name));
+        // Scripts like "make dev" and "cargo run" are prioritized based on their last word
+        let priority_index = PRIORITY_SCRIPTS.iter().find_map(|priority_script| {
+            scripts
+                .iter()
+                .position(|script| script.name.split_whitespace().last() == Some(*priority_script))
+        });
+        Ok(scripts.swap_remove(priority_index.unwrap_or(0)))
+    }
+
+    // Return the command that runs the specified script
+    pub fn get_start_command(&self, script: &Script) -> String {
+        script.start_command.clone()
+    }
+
+    // Determine whether the project has a manifest that server-room knows how to read scripts from
+    pub fn has_manifest(&self) -> bool {
+        !project_kind::detect(&self.dir).is_empty()
+    }
+
+    // Return the Node version range in package.json's "engines" field, if any
+    pub fn get_node_engine(&self) -> Option<String> {
+        let package_json = self.read_package_json().ok()?;
+        package_json["engines"]["node"]
+            .as_str()
+            .map(|range| range.to_string())
+    }
+
+    // Return the package name in package.json's "name" field, if any
+    pub fn get_package_name(&self) -> Option<String> {
+        let package_json = self.read_package_json().ok()?;
+        package_json["name"].as_str().map(|name| name.to_string())
+    }
+
+    // Return the patterns in package.json's "workspaces" field, which is either a list of patterns
+    // or an object with a "packages" list
+    pub fn get_workspace_patterns(&self) -> Vec<String> {
+        let package_json = match self.read_package_json() {
+            Ok(package_json) => package_json,
+            Err(_) => return vec![],
+        };
+        let workspaces = &package_json["workspaces"];
+        workspaces
+            .as_array()
+            .or_else(|| workspaces["packages"].as_array())
+            .map(|patterns| {
+                patterns
+                    .iter()
+                    .filter_map(|pattern| pattern.as_str().map(|pattern| pattern.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    // Determine whether the project has a package.json file
+    pub fn has_package_json(&self) -> bool {
+        self.get_package_json().is_file()
+    }
+
+    // Return the processes declared in the project's Procfile
+    pub fn get_processes(&self) -> Result<Vec<Script>, ApplicationError> {
+        procfile::get_scripts(&self.dir)
+    }
+
     // Return the path to the project's package.json file
     pub fn get_package_json(&self) -> PathBuf {
         self.dir.join("package.json")
     }
 }
+
+// This struct represents projects with a package.json file, whose scripts are run with their
+// package manager
+pub struct NpmProject;
+
+impl ProjectKind for NpmProject {
+    fn name(&self) -> &'static str {
+        "npm"
+    }
+
+    fn detect(&self, dir: &Path) -> bool {
+        dir.join("package.json").is_file()
+    }
+
+    fn get_scripts(&self, project: &Project) -> Result<Vec<Script>, ApplicationError> {
+        project.get_npm_scripts()
+    }
+}
@@ -0,0 +1,51 @@
+use super::error::ApplicationError;
+use super::health::HealthCheck;
+use super::server::Server;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// The name of the file in a project's directory that describes its server
+pub const FILE_NAME: &str = ".server-room.toml";
+
+// This struct represents the server settings that a project declares for itself
+// Every setting is optional, and missing settings are detected or prompted for like usual
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectConfig {
+    pub name: Option<String>,
+    pub start_command: Option<String>,
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    #[serde(default)]
+    pub env_files: Vec<PathBuf>,
+    pub health_check: Option<HealthCheck>,
+}
+
+impl ProjectConfig {
+    // Load the project's config file, falling back to an empty config if it doesn't exist
+    pub fn load(dir: &Path) -> Result<ProjectConfig, ApplicationError> {
+        let config_path = dir.join(FILE_NAME);
+        let config = match fs::read_to_string(&config_path) {
+            Ok(config_str) => toml::from_str::<ProjectConfig>(&config_str)
+                .map_err(|_| ApplicationError::ParseProjectConfig(config_path.clone()))?,
+            Err(_) => return Ok(ProjectConfig::default()),
+        };
+        if let Some(health_check) = &config.health_check {
+            health_check.validate()?;
+        }
+        Ok(config)
+    }
+
+    // Copy the settings that can't be provided on the command line to the new server
+    pub fn apply(&self, server: Server) -> Server {
+        Server {
+            env: self.env.clone(),
+            env_files: self.env_files.clone(),
+            health_check: self.health_check.clone(),
+            ..server
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use super::cargo_project::CargoProject;
+use super::compose::ComposeProject;
+use super::error::ApplicationError;
+use super::makefile::MakeProject;
+use super::procfile::ProcfileProject;
+use super::project::{NpmProject, Project};
+use super::script::Script;
+use std::path::Path;
+use std::sync::{Arc, OnceLock, RwLock};
+
+/// An ecosystem that server-room can read a project's start scripts from
+///
+/// Projects can be several kinds at once, like an npm project with a Makefile. Other ecosystems
+/// can be supported by implementing this trait and passing the kind to
+/// [`register`].
+pub trait ProjectKind: Send + Sync {
+    // Return a short name for the kind of project, like "npm"
+    fn name(&self) -> &'static str;
+
+    // Determine whether the project in the directory is this kind of project
+    fn detect(&self, dir: &Path) -> bool;
+
+    // Return the scripts that could start the project's server
+    fn get_scripts(&self, project: &Project) -> Result<Vec<Script>, ApplicationError>;
+}
+
+// The project kinds to check, in the order that their scripts are listed
+static REGISTRY: OnceLock<RwLock<Vec<Arc<dyn ProjectKind>>>> = OnceLock::new();
+
+fn get_registry() -> &'static RwLock<Vec<Arc<dyn ProjectKind>>> {
+    REGISTRY.get_or_init(|| {
+        RwLock::new(vec![
+            Arc::new(CargoProject),
+            Arc::new(MakeProject),
+            Arc::new(ProcfileProject),
+            Arc::new(ComposeProject),
+            Arc::new(NpmProject),
+        ])
+    })
+}
+
+// Add a project kind after the built-in ones
+pub fn register(kind: Arc<dyn ProjectKind>) {
+    get_registry().write().unwrap().push(kind);
+}
+
+// Return the kinds of the project in the directory
+pub fn detect(dir: &Path) -> Vec<Arc<dyn ProjectKind>> {
+    get_registry()
+        .read()
+        .unwrap()
+        .iter()
+        .filter(|kind| kind.detect(dir))
+        .cloned()
+        .collect()
+}
@@ -1,11 +1,48 @@
+use super::backup::Backup;
+use super::envfile;
 use super::error::ApplicationError;
-use super::project::Project;
-use super::server::Server;
+use super::git::{self, GitStatus};
+use super::health::HealthCheck;
+use super::port::PortChoice;
+use super::project::{Project, PRIORITY_SCRIPTS};
+use super::script::Script;
+use super::server::{RestartPolicy, Server, DEFAULT_GRACE_PERIOD};
 use super::server_store::ServerStore;
+use super::shell::Shell;
 
-use inquire::{Confirm, CustomType, Select, Text};
+use clap::ValueEnum;
+use colored::*;
+use inquire::{Confirm, CustomType, MultiSelect, Select, Text};
 use std::collections::HashSet;
+use std::fmt;
 use std::iter::FromIterator;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Whether prompts are allowed to read from stdin
+static INPUT_ENABLED: AtomicBool = AtomicBool::new(true);
+
+// Whether confirmation prompts are answered with yes without asking the user
+static ASSUME_YES: AtomicBool = AtomicBool::new(false);
+
+// Make every prompt fail instead of waiting for the user to answer it
+pub fn disable_input() {
+    INPUT_ENABLED.store(false, Ordering::Relaxed);
+}
+
+// Answer every confirmation prompt with yes
+pub fn assume_yes() {
+    ASSUME_YES.store(true, Ordering::Relaxed);
+}
+
+// Fail with the command line flag that provides the value when prompts are disabled
+fn require_input(flag: &str) -> Result<(), ApplicationError> {
+    if INPUT_ENABLED.load(Ordering::Relaxed) {
+        Ok(())
+    } else {
+        Err(ApplicationError::MissingInput(flag.to_string()))
+    }
+}
 
 // Get an existing server from the command line argument, falling back to letting the user interactively pick one
 pub fn choose_server<'s>(
@@ -16,29 +53,123 @@ pub fn choose_server<'s>(
     match cli_server_name {
         Some(server_name) => server_store.get_one(server_name.as_str()),
         None => {
-            // If no server was provided, let the user pick one
-            let mut servers = server_store.get_all();
-            // Put the servers with the highest weight first
+            require_input("a server")?;
+            // If no server was provided, let the user pick one of the servers that aren't archived
+            let mut servers = server_store.get_enabled_with_tag(None);
+            // Put the pinned servers first, then the servers with the highest weight
             servers.sort_by(|server1, server2| {
-                server1
-                    .get_weight()
-                    .partial_cmp(&server2.get_weight())
-                    .unwrap_or(std::cmp::Ordering::Equal)
-                    .reverse()
-                    .then_with(|| server1.name.cmp(&server2.name))
+                server2.pinned.cmp(&server1.pinned).then_with(|| {
+                    server1
+                        .get_weight()
+                        .partial_cmp(&server2.get_weight())
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                        .reverse()
+                        .then_with(|| server1.name.cmp(&server2.name))
+                })
             });
 
             if servers.is_empty() {
                 return Err(ApplicationError::NoServers);
             }
 
-            Select::new(prompt, servers)
+            // Pad the names so that the secondary text lines up
+            let name_width = servers
+                .iter()
+                .map(|server| server.name.len())
+                .max()
+                .unwrap_or(0);
+            let git_statuses = git::get_statuses(
+                &servers
+                    .iter()
+                    .map(|server| server.dir.as_path())
+                    .collect::<Vec<_>>(),
+            );
+            let options = servers
+                .into_iter()
+                .zip(git_statuses)
+                .map(|(server, git_status)| ServerOption {
+                    server,
+                    name_width,
+                    git_status,
+                })
+                .collect();
+            Select::new(prompt, options)
+                .with_filter(&|filter, option: &ServerOption, _, _| {
+                    fuzzy_match(filter, option.server.name.as_str())
+                        || fuzzy_match(filter, &option.server.dir.to_string_lossy())
+                })
+                .with_formatter(&|option| option.value.server.name.clone())
                 .prompt()
+                .map(|option| option.server)
                 .map_err(ApplicationError::from)
         }
     }
 }
 
+// Get an archived server from the command line argument, falling back to letting the user pick one
+pub fn choose_archived_server<'s>(
+    server_store: &'s ServerStore,
+    cli_server_name: Option<String>,
+    prompt: &str,
+) -> Result<&'s Server, ApplicationError> {
+    if let Some(server_name) = cli_server_name {
+        return server_store.get_one(server_name.as_str());
+    }
+    require_input("a server")?;
+    let mut servers = server_store
+        .get_all()
+        .into_iter()
+        .filter(|server| !server.enabled)
+        .collect::<Vec<_>>();
+    if servers.is_empty() {
+        return Err(ApplicationError::NoArchivedServers);
+    }
+    servers.sort_by_key(|server| server.name.as_str());
+    Select::new(prompt, servers)
+        .prompt()
+        .map_err(ApplicationError::InquireError)
+}
+
+// This struct labels a server in the selection prompt with its port, start command, usage, git
+// status, and description
+struct ServerOption<'s> {
+    server: &'s Server,
+    name_width: usize,
+    git_status: Option<GitStatus>,
+}
+
+impl fmt::Display for ServerOption<'_> {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "{:width$}  :{}  {} ({})",
+            self.server.name,
+            self.server.port,
+            self.server.start_command,
+            self.server.get_usage_summary(),
+            width = self.name_width
+        )?;
+        if let Some(git_status) = &self.git_status {
+            write!(formatter, " [{}]", git_status)?;
+        }
+        if let Some(description) = &self.server.description {
+            write!(formatter, " - {}", description)?;
+        }
+        Ok(())
+    }
+}
+
+// Determine whether every character of the filter appears in the text in order, ignoring case
+fn fuzzy_match(filter: &str, text: &str) -> bool {
+    let text = text.to_lowercase();
+    let mut text_chars = text.chars();
+    filter
+        .to_lowercase()
+        .chars()
+        .filter(|char| !char.is_whitespace())
+        .all(|filter_char| text_chars.any(|text_char| text_char == filter_char))
+}
+
 // Get the start command from the script name command line argument, falling back to letting the user interactively pick one
 pub fn choose_start_command(
     project: &Project,
@@ -51,9 +182,10 @@ pub fn choose_start_command(
             project.get_start_script(start_script)?
         }
         None => {
+            require_input("--start-script")?;
             // If no start script was provided, let the user pick one
             let mut scripts = project.get_start_scripts()?;
-            let priority_scripts = HashSet::<&&str>::from_iter(["dev", "run", "start"].iter());
+            let priority_scripts = HashSet::<&&str>::from_iter(PRIORITY_SCRIPTS.iter());
             // Sort the scripts by name, but put priority scripts first
             scripts.sort_by(|script1, script2| {
                 priority_scripts
@@ -65,7 +197,117 @@ pub fn choose_start_command(
             Select::new(prompt, scripts).prompt()?
         }
     };
-    Ok(format!("npm run {}", start_script.name))
+
+    // Warn when the installed package manager isn't the version that package.json requests
+    if let (Some(package_manager), Some(requested_version), Some(installed_version)) = (
+        project.package_manager,
+        project.package_manager_version.as_ref(),
+        project.get_package_manager_mismatch(),
+    ) {
+        eprintln!(
+            "{}: package.json requests {}@{}, but {} {} is installed. Try running `{}`.",
+            "Warning".bold().yellow(),
+            package_manager,
+            requested_version,
+            package_manager,
+            installed_version,
+            "corepack enable".bold().cyan()
+        );
+    }
+
+    Ok(project.get_start_command(&start_script))
+}
+
+// Let the user pick which of the project's Procfile processes to use, defaulting to all of them
+// There is no flag for picking processes, so all of them are used when prompts are disabled
+pub fn choose_processes(project: &Project, prompt: &str) -> Result<Vec<Script>, ApplicationError> {
+    let processes = project.get_processes()?;
+    if !INPUT_ENABLED.load(Ordering::Relaxed) {
+        return Ok(processes);
+    }
+    let all_processes = (0..processes.len()).collect::<Vec<_>>();
+    MultiSelect::new(prompt, processes)
+        .with_default(&all_processes)
+        .prompt()
+        .map_err(ApplicationError::InquireError)
+}
+
+// Let the user pick which of the workspace packages to add, defaulting to all of them
+// Picking none of them adds the workspace root itself instead
+pub fn choose_packages(
+    packages: Vec<Project>,
+    prompt: &str,
+) -> Result<Vec<Project>, ApplicationError> {
+    if !INPUT_ENABLED.load(Ordering::Relaxed) {
+        return Ok(packages);
+    }
+    let all_packages = (0..packages.len()).collect::<Vec<_>>();
+    MultiSelect::new(prompt, packages)
+        .with_default(&all_packages)
+        .prompt()
+        .map_err(ApplicationError::InquireError)
+}
+
+// Let the user pick several servers
+pub fn choose_servers<'s>(
+    server_store: &'s ServerStore,
+    prompt: &str,
+) -> Result<Vec<&'s Server>, ApplicationError> {
+    require_input("a server")?;
+    let mut servers = server_store.get_enabled_with_tag(None);
+    if servers.is_empty() {
+        return Err(ApplicationError::NoServers);
+    }
+    servers.sort_by_key(|server| server.name.as_str());
+    MultiSelect::new(prompt, servers)
+        .prompt()
+        .map_err(ApplicationError::InquireError)
+}
+
+// Let the user pick which of the projects to use, defaulting to all of them
+pub fn choose_projects(
+    projects: Vec<Project>,
+    prompt: &str,
+) -> Result<Vec<Project>, ApplicationError> {
+    if ASSUME_YES.load(Ordering::Relaxed) {
+        return Ok(projects);
+    }
+    require_input("--yes")?;
+    let all_projects = (0..projects.len()).collect::<Vec<_>>();
+    MultiSelect::new(prompt, projects)
+        .with_default(&all_projects)
+        .prompt()
+        .map_err(ApplicationError::InquireError)
+}
+
+// Ask the user for the command that starts a server imported from a file that doesn't include one
+// Returns an empty command when the user leaves it blank or prompts are disabled, which skips the server
+pub fn choose_imported_start_command(server_name: &str) -> Result<String, ApplicationError> {
+    if !INPUT_ENABLED.load(Ordering::Relaxed) {
+        return Ok(String::new());
+    }
+    Text::new(format!("What command starts {}?", server_name).as_str())
+        .with_help_message("Leave it empty to skip importing this server")
+        .prompt()
+        .map_err(ApplicationError::InquireError)
+}
+
+// Get the new project directory for an existing server from the command line argument, falling back to asking the user
+pub fn choose_server_path(
+    server: &Server,
+    cli_path: Option<PathBuf>,
+    prompt: &str,
+) -> Result<PathBuf, ApplicationError> {
+    match cli_path {
+        Some(path) => Ok(path),
+        None => {
+            require_input("--path")?;
+            let path = Text::new(prompt)
+                .with_default(server.dir.to_string_lossy().as_ref())
+                .prompt()?;
+            Ok(PathBuf::from(path))
+        }
+    }
 }
 
 // Get the new name for an existing server from the command line argument, falling back to letting the user choose one
@@ -76,29 +318,496 @@ pub fn choose_server_new_name(
 ) -> Result<String, ApplicationError> {
     match cli_new_name {
         Some(new_name) => Ok(new_name),
-        None => Text::new(prompt)
-            .with_placeholder(server.name.as_str())
-            .prompt()
-            .map_err(ApplicationError::InquireError),
+        None => {
+            require_input("--name")?;
+            Text::new(prompt)
+                .with_placeholder(server.name.as_str())
+                .prompt()
+                .map_err(ApplicationError::InquireError)
+        }
+    }
+}
+
+// Get the new tags for an existing server from the command line argument, falling back to letting the user enter them
+pub fn choose_server_tags(
+    server: &Server,
+    cli_tags: Option<Vec<String>>,
+    prompt: &str,
+) -> Result<Vec<String>, ApplicationError> {
+    match cli_tags {
+        Some(tags) => Ok(tags),
+        None => {
+            require_input("--tags")?;
+            let current_tags = server.tags.join(", ");
+            let tags = Text::new(prompt)
+                .with_default(current_tags.as_str())
+                .with_help_message("Separate tags with commas")
+                .prompt()?;
+            Ok(tags.split(',').map(|tag| tag.to_string()).collect())
+        }
+    }
+}
+
+// Get the description for a server from the command line arguments, falling back to asking the user
+pub fn choose_server_description(
+    server: &Server,
+    cli_description: Option<Option<String>>,
+    prompt: &str,
+) -> Result<Option<String>, ApplicationError> {
+    let description = match cli_description {
+        Some(description) => description,
+        None => {
+            require_input("--description or --clear")?;
+            let description = Text::new(prompt)
+                .with_default(server.description.as_deref().unwrap_or_default())
+                .with_help_message("Leave empty to remove the description")
+                .prompt()?;
+            Some(description)
+        }
+    };
+    Ok(description
+        .map(|description| description.trim().to_string())
+        .filter(|description| !description.is_empty()))
+}
+
+// This enum represents the kinds of health checks that a server can have
+#[derive(Clone, Copy)]
+enum HealthCheckKind {
+    Port,
+    Http,
+    Command,
+}
+
+impl fmt::Display for HealthCheckKind {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> fmt::Result {
+        let kind = match self {
+            HealthCheckKind::Port => "Wait for the port to accept connections",
+            HealthCheckKind::Http => "Request an HTTP URL",
+            HealthCheckKind::Command => "Run a shell command",
+        };
+        write!(formatter, "{}", kind)
+    }
+}
+
+// Get the health check for a server from the command line arguments, falling back to letting the user choose one
+// None means that the server doesn't have a custom health check
+pub fn choose_health_check(
+    server: &Server,
+    cli_health_check: Option<Option<HealthCheck>>,
+    prompt: &str,
+) -> Result<Option<HealthCheck>, ApplicationError> {
+    if let Some(health_check) = cli_health_check {
+        return Ok(health_check);
+    }
+
+    require_input("--url, --command, or --clear")?;
+    let kinds = vec![
+        HealthCheckKind::Port,
+        HealthCheckKind::Http,
+        HealthCheckKind::Command,
+    ];
+    let starting_cursor = match server.health_check {
+        None => 0,
+        Some(HealthCheck::Http { .. }) => 1,
+        Some(HealthCheck::Command { .. }) => 2,
+    };
+    let kind = Select::new(prompt, kinds)
+        .with_starting_cursor(starting_cursor)
+        .prompt()?;
+    Ok(match kind {
+        HealthCheckKind::Port => None,
+        HealthCheckKind::Http => {
+            let default_url = match &server.health_check {
+                Some(HealthCheck::Http { url }) => url.clone(),
+                _ => format!("http://localhost:{}/", server.port),
+            };
+            let url = Text::new("What URL responds once the server is ready?")
+                .with_default(default_url.as_str())
+                .prompt()?;
+            Some(HealthCheck::Http { url })
+        }
+        HealthCheckKind::Command => {
+            let mut command_prompt = Text::new("What command succeeds once the server is ready?");
+            if let Some(HealthCheck::Command { command }) = &server.health_check {
+                command_prompt = command_prompt.with_default(command.as_str());
+            }
+            Some(HealthCheck::Command {
+                command: command_prompt.prompt()?,
+            })
+        }
+    })
+}
+
+// Get the custom domain for a server from the command line arguments, falling back to asking the user
+pub fn choose_server_domain(
+    server: &Server,
+    domain_suffix: &str,
+    cli_domain: Option<Option<String>>,
+    prompt: &str,
+) -> Result<Option<String>, ApplicationError> {
+    if let Some(domain) = cli_domain {
+        return Ok(domain.filter(|domain| !domain.is_empty()));
+    }
+
+    require_input("--domain or --clear")?;
+    let domain = Text::new(prompt)
+        .with_default(server.get_domain(domain_suffix).as_str())
+        .with_help_message("Leave empty to derive the domain from the server's name")
+        .prompt()?;
+    let default_domain = Server {
+        domain: None,
+        ..server.clone()
+    }
+    .get_domain(domain_suffix);
+    // Store the default domain as no custom domain so that it follows the server's name
+    Ok(Some(domain).filter(|domain| !domain.is_empty() && *domain != default_domain))
+}
+
+// Get the idle timeout for a server from the command line arguments, falling back to asking the user
+pub fn choose_idle_timeout(
+    server: &Server,
+    cli_idle_timeout: Option<Option<u64>>,
+    prompt: &str,
+) -> Result<Option<u64>, ApplicationError> {
+    if let Some(idle_timeout) = cli_idle_timeout {
+        return Ok(idle_timeout);
+    }
+
+    require_input("--minutes or --clear")?;
+    if Confirm::new(prompt)
+        .with_default(server.idle_timeout.is_none())
+        .prompt()?
+    {
+        return Ok(None);
+    }
+    let formatter = &|minutes: u64| minutes.to_string();
+    let mut minutes_prompt =
+        CustomType::<u64>::new("After how many idle minutes should the server be stopped?")
+            .with_help_message("Enter 0 to never stop the server")
+            .with_error_message("Please enter a whole number of minutes");
+    if let Some(idle_timeout) = server.idle_timeout {
+        minutes_prompt = minutes_prompt.with_default((idle_timeout, formatter));
+    }
+    Ok(Some(minutes_prompt.prompt()?))
+}
+
+// Get the server's grace period from the command line argument, falling back to prompting for it
+// None means that the server should use the default grace period
+pub fn choose_grace_period(
+    server: &Server,
+    cli_grace_period: Option<Option<u64>>,
+    prompt: &str,
+) -> Result<Option<u64>, ApplicationError> {
+    if let Some(grace_period) = cli_grace_period {
+        return Ok(grace_period);
+    }
+
+    require_input("--seconds or --clear")?;
+    if Confirm::new(prompt)
+        .with_help_message(&format!("The default is {} seconds", DEFAULT_GRACE_PERIOD))
+        .with_default(server.grace_period.is_none())
+        .prompt()?
+    {
+        return Ok(None);
+    }
+    let formatter = &|seconds: u64| seconds.to_string();
+    let mut seconds_prompt = CustomType::<u64>::new(
+        "How many seconds should the server get to exit before it is killed?",
+    )
+    .with_error_message("Please enter a whole number of seconds");
+    if let Some(grace_period) = server.grace_period {
+        seconds_prompt = seconds_prompt.with_default((grace_period, formatter));
+    }
+    Ok(Some(seconds_prompt.prompt()?))
+}
+
+// Get the servers that a server depends on from the command line argument, falling back to letting the user pick them
+pub fn choose_server_dependencies(
+    server_store: &ServerStore,
+    server: &Server,
+    cli_depends_on: Option<Vec<String>>,
+    prompt: &str,
+) -> Result<Vec<String>, ApplicationError> {
+    match cli_depends_on {
+        Some(depends_on) => Ok(depends_on),
+        None => {
+            require_input("--depends-on")?;
+            let mut options = server_store
+                .get_all()
+                .into_iter()
+                .filter(|other| other.name != server.name)
+                .map(|other| other.name.clone())
+                .collect::<Vec<_>>();
+            options.sort();
+            if options.is_empty() {
+                return Err(ApplicationError::NoServers);
+            }
+
+            let current_depends_on = options
+                .iter()
+                .enumerate()
+                .filter(|(_, name)| server.depends_on.contains(name))
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>();
+            MultiSelect::new(prompt, options)
+                .with_default(&current_depends_on)
+                .prompt()
+                .map_err(ApplicationError::InquireError)
+        }
     }
 }
 
 // Get the port for a server from the command line argument, falling back to letting the user choose one
-pub fn choose_port(cli_port: Option<u16>, prompt: &str) -> Result<u16, ApplicationError> {
+// The automatically chosen free port is used for "auto" and as the prompt's default
+pub fn choose_port(
+    cli_port: Option<PortChoice>,
+    auto_port: Option<u16>,
+    prompt: &str,
+) -> Result<u16, ApplicationError> {
     match cli_port {
-        Some(port) => Ok(port),
-        None => CustomType::<u16>::new(prompt)
-            .with_error_message("Please enter a valid port number")
+        Some(PortChoice::Fixed(port)) => Ok(port),
+        Some(PortChoice::Auto) => auto_port.ok_or(ApplicationError::NoFreePort),
+        None => {
+            require_input("--port")?;
+            let formatter = &|port: u16| port.to_string();
+            let mut port_prompt = CustomType::<u16>::new(prompt)
+                .with_error_message("Please enter a valid port number");
+            if let Some(auto_port) = auto_port {
+                port_prompt = port_prompt.with_default((auto_port, formatter));
+            }
+            port_prompt.prompt().map_err(ApplicationError::InquireError)
+        }
+    }
+}
+
+// Get whether to inject the PORT environment variable from the command line argument, falling back to asking the user
+pub fn choose_inject_port(
+    server: &Server,
+    cli_enabled: Option<bool>,
+    prompt: &str,
+) -> Result<bool, ApplicationError> {
+    match cli_enabled {
+        Some(enabled) => Ok(enabled),
+        None => {
+            require_input("--enabled")?;
+            Confirm::new(prompt)
+                .with_default(server.inject_port)
+                .prompt()
+                .map_err(ApplicationError::InquireError)
+        }
+    }
+}
+
+// Get the dotenv files for a server from the command line argument, falling back to letting the user pick them
+pub fn choose_env_files(
+    server: &Server,
+    cli_env_files: Option<Vec<PathBuf>>,
+    prompt: &str,
+) -> Result<Vec<PathBuf>, ApplicationError> {
+    match cli_env_files {
+        Some(env_files) => Ok(env_files),
+        None => {
+            require_input("--env-file")?;
+            let mut env_files = envfile::find_env_files(&server.get_project_dir());
+            for env_file in &server.env_files {
+                if !env_files.contains(env_file) {
+                    env_files.push(env_file.clone());
+                }
+            }
+            if env_files.is_empty() {
+                return Err(ApplicationError::NoEnvFiles(server.get_project_dir()));
+            }
+
+            let current_env_files = env_files
+                .iter()
+                .enumerate()
+                .filter(|(_, env_file)| server.env_files.contains(env_file))
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>();
+            let options = env_files
+                .iter()
+                .map(|env_file| env_file.display().to_string())
+                .collect::<Vec<_>>();
+            let selected = MultiSelect::new(prompt, options)
+                .with_default(&current_env_files)
+                .prompt()?;
+            Ok(selected.into_iter().map(PathBuf::from).collect())
+        }
+    }
+}
+
+// Get the restart policy for a server from the command line argument, falling back to letting the user pick one
+pub fn choose_restart_policy(
+    cli_restart: Option<RestartPolicy>,
+    prompt: &str,
+) -> Result<RestartPolicy, ApplicationError> {
+    match cli_restart {
+        Some(restart) => Ok(restart),
+        None => {
+            require_input("--restart")?;
+            Select::new(
+                prompt,
+                vec![
+                    RestartPolicy::Never,
+                    RestartPolicy::OnFailure,
+                    RestartPolicy::Always,
+                ],
+            )
             .prompt()
-            .map_err(ApplicationError::InquireError),
+            .map_err(ApplicationError::InquireError)
+        }
+    }
+}
+
+// Let the user pick a shell if one wasn't provided on the command line
+pub fn choose_shell(cli_shell: Option<Shell>, prompt: &str) -> Result<Shell, ApplicationError> {
+    match cli_shell {
+        Some(shell) => Ok(shell),
+        None => {
+            require_input("--shell")?;
+            Select::new(prompt, Shell::value_variants().to_vec())
+                .prompt()
+                .map_err(ApplicationError::InquireError)
+        }
+    }
+}
+
+// This enum represents what to do when a server's port is already in use
+#[derive(Clone, Copy)]
+pub enum PortConflictAction {
+    Kill,
+    RunAnyway,
+    Cancel,
+}
+
+impl fmt::Display for PortConflictAction {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> fmt::Result {
+        let action = match self {
+            PortConflictAction::Kill => "Stop the process using the port",
+            PortConflictAction::RunAnyway => "Run the server anyway",
+            PortConflictAction::Cancel => "Cancel",
+        };
+        write!(formatter, "{}", action)
+    }
+}
+
+// Let the user decide what to do about a process that is already using a server's port
+// When prompts are disabled, the server is run anyway like it was before conflicts were detected
+pub fn choose_port_conflict_action(
+    can_kill: bool,
+    prompt: &str,
+) -> Result<PortConflictAction, ApplicationError> {
+    if !INPUT_ENABLED.load(Ordering::Relaxed) {
+        return Ok(PortConflictAction::RunAnyway);
+    }
+    let mut actions = vec![PortConflictAction::RunAnyway, PortConflictAction::Cancel];
+    if can_kill {
+        actions.insert(0, PortConflictAction::Kill);
+    }
+    Select::new(prompt, actions)
+        .prompt()
+        .map_err(ApplicationError::InquireError)
+}
+
+// Ask the user whether to install a server's missing dependencies before running it
+// When prompts are disabled, the server is run without installing them
+pub fn confirm_install(prompt: &str) -> Result<bool, ApplicationError> {
+    if ASSUME_YES.load(Ordering::Relaxed) {
+        return Ok(true);
+    }
+    if !INPUT_ENABLED.load(Ordering::Relaxed) {
+        return Ok(false);
+    }
+    Ok(Confirm::new(prompt)
+        .with_default(true)
+        .prompt_skippable()?
+        .unwrap_or(false))
+}
+
+// Ask the user whether to move a server whose directory is missing to the project's new location
+// When prompts are disabled, the server is left alone
+pub fn confirm_relink(prompt: &str) -> Result<bool, ApplicationError> {
+    if ASSUME_YES.load(Ordering::Relaxed) {
+        return Ok(true);
+    }
+    if !INPUT_ENABLED.load(Ordering::Relaxed) {
+        return Ok(false);
+    }
+    Ok(Confirm::new(prompt)
+        .with_default(true)
+        .prompt_skippable()?
+        .unwrap_or(false))
+}
+
+// Let the user pick which of a server's fields to edit, returning the name of its edit subcommand
+// Returns None once the user is done editing and presses escape
+pub fn choose_edit_field(prompt: &str) -> Result<Option<&'static str>, ApplicationError> {
+    require_input("the field to edit")?;
+    let fields = vec![
+        "name",
+        "path",
+        "start-script",
+        "port",
+        "inject-port",
+        "env-file",
+        "restart",
+        "shell",
+        "health-check",
+        "domain",
+        "idle-timeout",
+        "grace-period",
+        "depends-on",
+        "description",
+        "tags",
+    ];
+    Select::new(prompt, fields)
+        .with_help_message("↑↓ to move, enter to select, esc to stop editing")
+        .prompt_skippable()
+        .map_err(ApplicationError::InquireError)
+}
+
+// Get a backup from the command line argument, falling back to letting the user choose one
+pub fn choose_backup(
+    backups: Vec<Backup>,
+    cli_backup: Option<String>,
+    prompt: &str,
+) -> Result<Backup, ApplicationError> {
+    if let Some(id) = cli_backup {
+        return backups
+            .into_iter()
+            .find(|backup| backup.id == id)
+            .ok_or(ApplicationError::NonExistentBackup(id));
+    }
+
+    require_input("a backup")?;
+    if backups.is_empty() {
+        return Err(ApplicationError::NoBackups);
+    }
+    Select::new(prompt, backups)
+        .prompt()
+        .map_err(ApplicationError::InquireError)
+}
+
+// Get confirmation to change the servers like confirm, showing the diff of the change first when
+// the user will be prompted
+pub fn confirm_change(
+    cli_confirm: bool,
+    diff: &str,
+    prompt: &str,
+) -> Result<bool, ApplicationError> {
+    if !cli_confirm && !ASSUME_YES.load(Ordering::Relaxed) && INPUT_ENABLED.load(Ordering::Relaxed)
+    {
+        println!("{}", diff);
     }
+    confirm(cli_confirm, prompt)
 }
 
 // Get confirmation to perform the operation from command line argument, falling back to prompting the user for confirmation
 pub fn confirm(cli_confirm: bool, prompt: &str) -> Result<bool, ApplicationError> {
-    if cli_confirm {
+    if cli_confirm || ASSUME_YES.load(Ordering::Relaxed) {
         Ok(true)
     } else {
+        require_input("--yes")?;
         Ok(Confirm::new(prompt)
             .with_default(true)
             .prompt_skippable()?
@@ -0,0 +1,640 @@
+use super::config::Config;
+use super::error::ApplicationError;
+use super::health;
+use super::logging;
+use super::process::{self, RuntimeState};
+use super::server_store::ServerStore;
+use super::supervisor;
+use super::tls::{self, TlsStream};
+use colored::*;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info};
+
+// How long to wait for a server that was started on demand to become ready
+const START_TIMEOUT: Duration = Duration::from_secs(60);
+
+// How often to look for idle servers to stop
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+// This struct contains the files that the proxy reads from and writes to
+pub struct ProxyPaths {
+    pub store_path: PathBuf,
+    pub runtime_state_path: PathBuf,
+    pub logs_dir: PathBuf,
+}
+
+// This trait represents a connection from a client that can be split between threads
+trait ClientStream: Read + Write + Send + Sized + 'static {
+    fn try_clone(&self) -> io::Result<Self>;
+
+    // Tell the other end of the connection that nothing more will be written
+    fn close_write(&mut self);
+}
+
+impl ClientStream for TcpStream {
+    fn try_clone(&self) -> io::Result<Self> {
+        TcpStream::try_clone(self)
+    }
+
+    fn close_write(&mut self) {
+        let _ = self.shutdown(Shutdown::Write);
+    }
+}
+
+impl ClientStream for TlsStream {
+    fn try_clone(&self) -> io::Result<Self> {
+        Ok(self.clone())
+    }
+
+    fn close_write(&mut self) {
+        TlsStream::close_write(self);
+    }
+}
+
+// This struct represents the proxy's state, which is shared between connections
+struct Proxy {
+    paths: ProxyPaths,
+    // Held while starting servers so that concurrent requests don't start the same server twice
+    starting: Mutex<()>,
+    // The time that each server last received a request through the proxy
+    last_requests: Mutex<HashMap<String, SystemTime>>,
+    // The number of connections that are currently open to each server through the proxy
+    open_connections: Mutex<HashMap<String, usize>>,
+    // The config's idle timeout in minutes, which servers without their own idle timeout use
+    idle_timeout: Option<u64>,
+    domain_suffix: String,
+}
+
+// This struct tracks a connection to a server through the proxy while it is open
+struct OpenConnection<'p> {
+    proxy: &'p Proxy,
+    server_name: String,
+}
+
+impl<'p> OpenConnection<'p> {
+    fn new(proxy: &'p Proxy, server_name: &str) -> Self {
+        proxy.record_request(server_name);
+        *proxy
+            .open_connections
+            .lock()
+            .unwrap()
+            .entry(server_name.to_string())
+            .or_default() += 1;
+        OpenConnection {
+            proxy,
+            server_name: server_name.to_string(),
+        }
+    }
+}
+
+impl Drop for OpenConnection<'_> {
+    // The idle timeout starts counting when the last connection closes
+    fn drop(&mut self) {
+        let mut open_connections = self.proxy.open_connections.lock().unwrap();
+        if let Some(count) = open_connections.get_mut(&self.server_name) {
+            *count = count.saturating_sub(1);
+        }
+        drop(open_connections);
+        self.proxy.record_request(&self.server_name);
+    }
+}
+
+// This struct represents a connection from the proxy to a server
+struct Upstream<'p> {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    connection: OpenConnection<'p>,
+}
+
+// This enum represents how the end of a message's body is found
+enum Body {
+    Empty,
+    Length(u64),
+    Chunked,
+    // The body continues until the connection closes, which only responses can do
+    UntilClose,
+}
+
+// This struct represents the start line and headers of an HTTP/1 request or response
+struct MessageHead {
+    // The start line and headers as they were received, so that they can be forwarded as-is
+    raw: String,
+    start_line: String,
+    headers: Vec<(String, String)>,
+}
+
+impl MessageHead {
+    // Read the next message head from the stream, returning None if the stream closes first
+    fn read(reader: &mut impl BufRead) -> Option<Self> {
+        let mut raw = String::new();
+        let mut start_line: Option<String> = None;
+        let mut headers = vec![];
+        loop {
+            let mut line = String::new();
+            match reader.read_line(&mut line) {
+                Ok(0) | Err(_) => return None,
+                Ok(_) => {}
+            }
+            let is_blank = line == "\r\n" || line == "\n";
+            // Blank lines before the start line are ignored
+            if is_blank && start_line.is_none() {
+                continue;
+            }
+            raw.push_str(&line);
+            if is_blank {
+                break;
+            }
+            match start_line {
+                None => start_line = Some(line.trim_end().to_string()),
+                Some(_) => {
+                    if let Some((name, value)) = line.split_once(':') {
+                        headers.push((name.trim().to_string(), value.trim().to_string()));
+                    }
+                }
+            }
+        }
+        Some(MessageHead {
+            raw,
+            start_line: start_line?,
+            headers,
+        })
+    }
+
+    // Return the value of the header if the message has it
+    fn get_header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    // Determine whether the comma-separated header contains the token
+    fn has_token(&self, name: &str, token: &str) -> bool {
+        self.headers
+            .iter()
+            .filter(|(header, _)| header.eq_ignore_ascii_case(name))
+            .flat_map(|(_, value)| value.split(','))
+            .any(|value| value.trim().eq_ignore_ascii_case(token))
+    }
+
+    // Return the response's status code, which is 0 if it is invalid
+    fn get_status(&self) -> u16 {
+        self.start_line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|status| status.parse().ok())
+            .unwrap_or_default()
+    }
+
+    // Determine how the message's body ends, using the default if the headers don't say, or return
+    // None if the Content-Length is invalid
+    fn get_body(&self, default: Body) -> Option<Body> {
+        if self.has_token("transfer-encoding", "chunked") {
+            return Some(Body::Chunked);
+        }
+        match self.get_header("content-length") {
+            Some(length) => length.parse().ok().map(Body::Length),
+            None => Some(default),
+        }
+    }
+
+    // Determine whether the sender of the message wants to keep the connection open, which
+    // HTTP/1.0 only does when it asks to
+    fn keeps_alive(&self, version: Option<&str>) -> bool {
+        if self.has_token("connection", "close") {
+            false
+        } else if version == Some("HTTP/1.0") {
+            self.has_token("connection", "keep-alive")
+        } else {
+            true
+        }
+    }
+
+    // Return the raw message head without the header
+    fn to_string_without(&self, name: &str) -> String {
+        self.raw
+            .split_inclusive('\n')
+            .filter(|line| {
+                line.split_once(':')
+                    .is_none_or(|(header, _)| !header.trim().eq_ignore_ascii_case(name))
+            })
+            .collect()
+    }
+}
+
+// Route requests for each server's domain on the port to the server's port until killed
+// If a TLS port is provided, HTTPS requests for servers with certificates are routed too
+// Servers that aren't running are started when they receive their first request, and servers that
+// are idle for longer than their idle timeout are stopped
+pub fn run(
+    paths: ProxyPaths,
+    port: u16,
+    tls_port: Option<u16>,
+    config: &Config,
+) -> Result<(), ApplicationError> {
+    let listener =
+        TcpListener::bind(("127.0.0.1", port)).map_err(|_| ApplicationError::BindPort(port))?;
+    let tls_listener = match tls_port {
+        Some(tls_port) => {
+            let server_store = ServerStore::load(paths.store_path.clone())?;
+            let tls_config =
+                tls::build_server_config(&server_store.get_all(), &config.get_domain_suffix())?;
+            let tls_listener = TcpListener::bind(("127.0.0.1", tls_port))
+                .map_err(|_| ApplicationError::BindPort(tls_port))?;
+            Some((tls_listener, tls_config))
+        }
+        None => None,
+    };
+    let proxy = Arc::new(Proxy {
+        paths,
+        starting: Mutex::new(()),
+        last_requests: Mutex::new(HashMap::new()),
+        open_connections: Mutex::new(HashMap::new()),
+        idle_timeout: config.idle_timeout,
+        domain_suffix: config.get_domain_suffix(),
+    });
+    let reaper = proxy.clone();
+    thread::spawn(move || loop {
+        thread::sleep(IDLE_CHECK_INTERVAL);
+        reaper.stop_idle_servers();
+    });
+    if let Some((tls_listener, tls_config)) = tls_listener {
+        let proxy = proxy.clone();
+        thread::spawn(move || {
+            for stream in tls_listener.incoming().flatten() {
+                let proxy = proxy.clone();
+                let tls_config = tls_config.clone();
+                thread::spawn(move || {
+                    if let Ok(stream) = TlsStream::accept(tls_config, stream) {
+                        proxy.handle_connection(stream);
+                    }
+                });
+            }
+        });
+    }
+    for stream in listener.incoming().flatten() {
+        let proxy = proxy.clone();
+        thread::spawn(move || proxy.handle_connection(stream));
+    }
+    Ok(())
+}
+
+impl Proxy {
+    // Forward each request on the connection to the server named by its Host header, so that a
+    // client that reuses the connection for several domains reaches the right server each time
+    fn handle_connection<S: ClientStream>(&self, mut client: S) {
+        let mut reader = BufReader::new(match client.try_clone() {
+            Ok(client) => client,
+            Err(_) => return,
+        });
+        let mut previous_upstream: Option<Upstream> = None;
+        loop {
+            let request = match MessageHead::read(&mut reader) {
+                Some(request) => request,
+                None => return,
+            };
+            let (server_name, port) = match self.route(request.get_header("host")) {
+                Ok(route) => route,
+                Err((status, message)) => return respond(&mut client, status, &message),
+            };
+            let request_body = match request.get_body(Body::Empty) {
+                Some(request_body) => request_body,
+                None => {
+                    return respond(
+                        &mut client,
+                        "400 Bad Request",
+                        "The request's Content-Length is invalid",
+                    )
+                }
+            };
+
+            // Reuse the connection to the server that the previous request went to
+            let mut upstream = match previous_upstream.take() {
+                Some(upstream) if upstream.connection.server_name == server_name => {
+                    self.record_request(&server_name);
+                    upstream
+                }
+                _ => match self.connect_upstream(&server_name, port) {
+                    Ok(upstream) => upstream,
+                    Err(message) => return respond(&mut client, "502 Bad Gateway", &message),
+                },
+            };
+
+            // The body is sent before the response is read, so the proxy tells the client to
+            // continue instead of the server
+            let head = if request.has_token("expect", "100-continue") {
+                if client.write_all(b"HTTP/1.1 100 Continue\r\n\r\n").is_err() {
+                    return;
+                }
+                request.to_string_without("expect")
+            } else {
+                request.raw.clone()
+            };
+            if upstream.writer.write_all(head.as_bytes()).is_err()
+                || copy_body(&mut reader, &mut upstream.writer, request_body).is_err()
+            {
+                return respond(
+                    &mut client,
+                    "502 Bad Gateway",
+                    &format!("Couldn't send the request to server \"{}\"", server_name),
+                );
+            }
+
+            // Forward informational responses until the final response arrives
+            let (response, status) = loop {
+                let response = match MessageHead::read(&mut upstream.reader) {
+                    Some(response) => response,
+                    None => {
+                        return respond(
+                            &mut client,
+                            "502 Bad Gateway",
+                            &format!("Server \"{}\" closed the connection", server_name),
+                        )
+                    }
+                };
+                let status = response.get_status();
+                if client.write_all(response.raw.as_bytes()).is_err() {
+                    return;
+                }
+                if !(100..200).contains(&status) || status == 101 {
+                    break (response, status);
+                }
+            };
+
+            // Once the server switches protocols, like for a WebSocket, the rest of the connection
+            // belongs to it
+            if status == 101 {
+                let Upstream {
+                    reader: upstream_reader,
+                    writer: upstream_writer,
+                    connection: _connection,
+                } = upstream;
+                let request_pipe = thread::spawn(move || pipe(reader, upstream_writer));
+                pipe(upstream_reader, client);
+                let _ = request_pipe.join();
+                return;
+            }
+
+            let method = request.start_line.split_whitespace().next();
+            let response_body = if method == Some("HEAD") || status == 204 || status == 304 {
+                Some(Body::Empty)
+            } else {
+                response.get_body(Body::UntilClose)
+            };
+            let response_body = match response_body {
+                Some(response_body) => response_body,
+                None => return client.close_write(),
+            };
+            let keep_alive = !matches!(response_body, Body::UntilClose)
+                && request.keeps_alive(request.start_line.split_whitespace().last())
+                && response.keeps_alive(response.start_line.split_whitespace().next());
+            if copy_body(&mut upstream.reader, &mut client, response_body)
+                .and_then(|_| client.flush())
+                .is_err()
+                || !keep_alive
+            {
+                return client.close_write();
+            }
+            previous_upstream = Some(upstream);
+        }
+    }
+
+    // Find the enabled server whose domain is the Host header's, returning its name and port or the
+    // status and message to respond with
+    fn route(&self, host: Option<&str>) -> Result<(String, u16), (&'static str, String)> {
+        let server_store = ServerStore::load(self.paths.store_path.clone())
+            .map_err(|err| ("500 Internal Server Error", err.to_string()))?;
+        // Ignore the port in Host headers like "api.localhost:8080"
+        let hostname = host
+            .and_then(|host| host.split(':').next())
+            .unwrap_or_default();
+        let server = server_store
+            .get_enabled_with_tag(None)
+            .into_iter()
+            .find(|server| server.get_domain(&self.domain_suffix) == hostname)
+            .ok_or_else(|| {
+                (
+                    "404 Not Found",
+                    format!("No server has the domain \"{}\"", hostname),
+                )
+            })?;
+        debug!(
+            "Routing {} to {} on port {}",
+            hostname, server.name, server.port
+        );
+        Ok((server.name.clone(), server.port))
+    }
+
+    // Connect to the server, starting it first if it isn't accepting connections, returning the
+    // message to respond with if that fails
+    fn connect_upstream(&self, server_name: &str, port: u16) -> Result<Upstream<'_>, String> {
+        // Servers aren't idle while a connection to them is open, like a WebSocket for HMR
+        let connection = OpenConnection::new(self, server_name);
+        let stream = match connect(port) {
+            Some(stream) => stream,
+            None => match self.start_server(server_name).map(|_| connect(port)) {
+                Ok(Some(stream)) => stream,
+                Ok(None) => {
+                    return Err(format!(
+                        "Server \"{}\" isn't accepting connections",
+                        server_name
+                    ))
+                }
+                Err(err) => return Err(err.to_string()),
+            },
+        };
+        let writer = stream.try_clone().map_err(|err| err.to_string())?;
+        Ok(Upstream {
+            reader: BufReader::new(stream),
+            writer,
+            connection,
+        })
+    }
+
+    // Record that the server just received a request
+    fn record_request(&self, server_name: &str) {
+        self.last_requests
+            .lock()
+            .unwrap()
+            .insert(server_name.to_string(), SystemTime::now());
+    }
+
+    // Stop the running servers that haven't received a request or written any output for longer
+    // than their idle timeout
+    fn stop_idle_servers(&self) {
+        let (server_store, runtime_state) = match (
+            ServerStore::load(self.paths.store_path.clone()),
+            RuntimeState::load(self.paths.runtime_state_path.clone()),
+        ) {
+            (Ok(server_store), Ok(runtime_state)) => (server_store, runtime_state),
+            _ => return,
+        };
+        for process in runtime_state.get_all() {
+            let server = match server_store.get_one(&process.server) {
+                Ok(server) => server,
+                Err(_) => continue,
+            };
+            let idle_timeout = match server.get_idle_timeout(self.idle_timeout) {
+                Some(idle_timeout) => idle_timeout,
+                None => continue,
+            };
+            if self
+                .open_connections
+                .lock()
+                .unwrap()
+                .get(&server.name)
+                .is_some_and(|count| *count > 0)
+            {
+                continue;
+            }
+
+            let started_at = UNIX_EPOCH + Duration::from_secs(process.started_at);
+            let last_output =
+                fs::metadata(logging::get_log_path(&self.paths.logs_dir, &server.name))
+                    .and_then(|metadata| metadata.modified())
+                    .ok();
+            let last_request = self
+                .last_requests
+                .lock()
+                .unwrap()
+                .get(&server.name)
+                .copied();
+            let last_activity = [last_output, last_request]
+                .iter()
+                .flatten()
+                .fold(started_at, |latest, time| latest.max(*time));
+            let idle = last_activity.elapsed().unwrap_or_default();
+            if idle >= idle_timeout
+                && server_store
+                    .stop_server(&server.name, &runtime_state)
+                    .is_ok()
+            {
+                println!(
+                    "Stopped {} after {} idle minutes",
+                    server.name.bold().green(),
+                    idle.as_secs() / 60
+                );
+            }
+        }
+    }
+
+    // Start the server and its dependencies in the background if it isn't already running, then
+    // wait for it to become ready
+    fn start_server(&self, server_name: &str) -> Result<(), ApplicationError> {
+        info!("Starting {} on demand", server_name);
+        let server_store = ServerStore::load(self.paths.store_path.clone())?;
+        let started = {
+            let _starting = self.starting.lock().unwrap();
+            let runtime_state = RuntimeState::load(self.paths.runtime_state_path.clone())?;
+            let server_names = supervisor::get_start_order(&server_store, &[server_name])?
+                .into_iter()
+                .filter(|name| runtime_state.get(name).is_none())
+                .collect::<Vec<_>>();
+            if !server_names.is_empty() {
+                let server_names = server_names
+                    .iter()
+                    .map(|name| name.as_str())
+                    .collect::<Vec<_>>();
+                let pids = server_store.start_servers_detached(
+                    &server_names,
+                    &runtime_state,
+                    Some(&self.paths.logs_dir),
+                )?;
+                process::reap_in_background(&runtime_state, &server_names, &pids);
+            }
+            server_names.iter().any(|name| name == server_name)
+        };
+
+        let server = server_store.get_one(server_name)?;
+        let elapsed =
+            health::wait_until_ready(server, Some(START_TIMEOUT), &AtomicBool::new(false))
+                .ok_or_else(|| ApplicationError::NotReady {
+                    server: server_name.to_string(),
+                    timeout: START_TIMEOUT.as_secs(),
+                })?;
+        // Servers that were already running weren't just started
+        if started {
+            server_store.record_startup_time(server_name, elapsed)?;
+        }
+        Ok(())
+    }
+}
+
+// Connect to the server listening on the port
+fn connect(port: u16) -> Option<TcpStream> {
+    ("localhost", port)
+        .to_socket_addrs()
+        .ok()?
+        .find_map(|address| TcpStream::connect(address).ok())
+}
+
+// Copy exactly the number of bytes from one stream to the other
+fn copy_exact(reader: &mut impl Read, writer: &mut impl Write, length: u64) -> io::Result<()> {
+    if io::copy(&mut reader.take(length), writer)? < length {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(())
+}
+
+// Copy a message's body from one stream to the other, leaving the stream after it untouched so
+// that the next message can be read
+fn copy_body(reader: &mut impl BufRead, writer: &mut impl Write, body: Body) -> io::Result<()> {
+    match body {
+        Body::Empty => Ok(()),
+        Body::Length(length) => copy_exact(reader, writer, length),
+        Body::Chunked => loop {
+            let mut size_line = String::new();
+            if reader.read_line(&mut size_line)? == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            writer.write_all(size_line.as_bytes())?;
+            let size = size_line.split(';').next().unwrap_or_default().trim();
+            let size = u64::from_str_radix(size, 16)
+                .map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+            if size == 0 {
+                // Copy the trailers and the blank line that ends them
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line)? == 0 {
+                        return Err(io::ErrorKind::UnexpectedEof.into());
+                    }
+                    writer.write_all(line.as_bytes())?;
+                    if line == "\r\n" || line == "\n" {
+                        return Ok(());
+                    }
+                }
+            }
+            // Copy the chunk and the line break after it
+            copy_exact(reader, writer, size)?;
+            let mut line_break = String::new();
+            reader.read_line(&mut line_break)?;
+            writer.write_all(line_break.as_bytes())?;
+        },
+        Body::UntilClose => io::copy(reader, writer).map(|_| ()),
+    }
+}
+
+// Copy everything read from one stream to the other until the reading side closes
+fn pipe(mut from: impl Read, mut to: impl ClientStream) {
+    let _ = io::copy(&mut from, &mut to);
+    to.close_write();
+}
+
+// Send a plain text response to the client
+fn respond(client: &mut impl Write, status: &str, body: &str) {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}\n",
+        status,
+        body.len() + 1,
+        body
+    );
+    let _ = client.write_all(response.as_bytes());
+}
@@ -0,0 +1,33 @@
+use super::error::ApplicationError;
+use qrcode::render::unicode::Dense1x2;
+use qrcode::QrCode;
+use std::net::{IpAddr, UdpSocket};
+
+// Detect the IP address that other devices on the local network can reach this machine at
+pub fn get_lan_ip() -> Result<IpAddr, ApplicationError> {
+    // Connecting a UDP socket doesn't send any packets, but it makes the OS pick the outbound
+    // interface, whose address is the LAN address
+    let socket = UdpSocket::bind("0.0.0.0:0").map_err(|_| ApplicationError::NoLanIp)?;
+    socket
+        .connect("8.8.8.8:80")
+        .map_err(|_| ApplicationError::NoLanIp)?;
+    let ip = socket
+        .local_addr()
+        .map_err(|_| ApplicationError::NoLanIp)?
+        .ip();
+    if ip.is_loopback() || ip.is_unspecified() {
+        return Err(ApplicationError::NoLanIp);
+    }
+    Ok(ip)
+}
+
+// Render the text as a QR code made of Unicode half blocks
+pub fn render(text: &str) -> Result<String, ApplicationError> {
+    let code = QrCode::new(text).map_err(|_| ApplicationError::GenerateQrCode)?;
+    Ok(code
+        .render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .quiet_zone(true)
+        .build())
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+// This struct represents the resources used by a server's process tree
+#[derive(Clone, Copy)]
+pub struct ResourceUsage {
+    // The percentage of one CPU core used since the previous sample, which is missing for the
+    // first sample because usage can only be measured over time
+    pub cpu_percent: Option<f64>,
+    // The CPU time that the processes that are still running have used since they started
+    pub cpu_seconds: f64,
+    pub memory_bytes: u64,
+}
+
+// This struct measures the CPU and memory usage of servers and the children that they spawn, like
+// the Node process behind `npm run dev`
+pub struct ResourceSampler {
+    system: System,
+    // Whether a previous sample exists that CPU usage can be measured against
+    has_previous: bool,
+}
+
+impl Default for ResourceSampler {
+    fn default() -> Self {
+        ResourceSampler {
+            system: System::new(),
+            has_previous: false,
+        }
+    }
+}
+
+impl ResourceSampler {
+    // Measure the current usage of every process
+    pub fn sample(&mut self) {
+        self.has_previous = !self.system.processes().is_empty();
+        self.system.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::nothing()
+                .with_cpu()
+                .with_memory()
+                .without_tasks(),
+        );
+    }
+
+    // Return the combined usage of the process and its descendants at the last sample
+    pub fn get(&self, pid: u32) -> Option<ResourceUsage> {
+        let root = Pid::from_u32(pid);
+        let processes = self.system.processes();
+        processes.get(&root)?;
+
+        // Map each process to its parent once instead of walking up from every process repeatedly
+        let parents = processes
+            .iter()
+            .filter_map(|(pid, process)| Some((*pid, process.parent()?)))
+            .collect::<HashMap<_, _>>();
+        let is_descendant = |mut pid: Pid| loop {
+            if pid == root {
+                return true;
+            }
+            match parents.get(&pid) {
+                // Guard against cycles in case a pid was reused between reads
+                Some(parent) if *parent != pid => pid = *parent,
+                _ => return false,
+            }
+        };
+
+        let mut usage = ResourceUsage {
+            cpu_percent: self.has_previous.then_some(0.0),
+            cpu_seconds: 0.0,
+            memory_bytes: 0,
+        };
+        for (pid, process) in processes {
+            if !is_descendant(*pid) {
+                continue;
+            }
+            usage.cpu_percent = usage
+                .cpu_percent
+                .map(|cpu_percent| cpu_percent + process.cpu_usage() as f64);
+            usage.cpu_seconds += process.accumulated_cpu_time() as f64 / 1000.0;
+            usage.memory_bytes += process.memory();
+        }
+        Some(usage)
+    }
+}
+
+// Format a number of bytes in the largest whole unit, like "12.3 MB"
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = "B";
+    for next_unit in UNITS {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = next_unit;
+    }
+    if unit == "B" {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, unit)
+    }
+}
@@ -0,0 +1,235 @@
+use super::error::ApplicationError;
+use super::server::{format_duration, format_time_ago, get_now_secs};
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::os::unix::io::AsRawFd;
+use std::os::unix::process::ExitStatusExt;
+use std::path::Path;
+use std::process::ExitStatus;
+use std::str::FromStr;
+
+// Exit codes that shells use for processes that were stopped by SIGINT or SIGTERM
+const STOPPED_EXIT_CODES: [i32; 2] = [128 + libc::SIGINT, 128 + libc::SIGTERM];
+
+// This struct represents a run of a server that has exited
+#[derive(Clone, Deserialize, Serialize)]
+pub struct RunRecord {
+    pub server: String,
+    // When the server was started and when it exited, in seconds since the Unix epoch
+    pub started_at: u64,
+    pub exited_at: u64,
+    // The exit code is missing if the server was killed by a signal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signal: Option<i32>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct RawRunHistory {
+    runs: Vec<RunRecord>,
+}
+
+impl RunRecord {
+    // Create a record of a server that just exited
+    pub fn new(server_name: &str, started_at: u64, status: &ExitStatus) -> Self {
+        RunRecord {
+            server: server_name.to_string(),
+            started_at,
+            exited_at: get_now_secs(),
+            exit_code: status.code(),
+            signal: status.signal(),
+        }
+    }
+
+    // Create a record of a server that just exited without its exit status being known, which
+    // happens when server-room didn't start the process that saw it exit
+    pub fn new_unknown(server_name: &str, started_at: u64) -> Self {
+        RunRecord {
+            server: server_name.to_string(),
+            started_at,
+            exited_at: get_now_secs(),
+            exit_code: None,
+            signal: None,
+        }
+    }
+
+    // Determine whether the server exited unexpectedly instead of finishing or being stopped
+    pub fn is_crash(&self) -> bool {
+        match (self.exit_code, self.signal) {
+            (Some(code), _) => code != 0 && !STOPPED_EXIT_CODES.contains(&code),
+            (None, Some(signal)) => signal != libc::SIGINT && signal != libc::SIGTERM,
+            (None, None) => false,
+        }
+    }
+
+    // Describe how the server exited, like "with exit 137"
+    pub fn get_cause(&self) -> String {
+        match (self.exit_code, self.signal) {
+            (Some(code), _) => format!("with exit {}", code),
+            (None, Some(signal)) => format!("from signal {}", signal),
+            (None, None) => "with an unknown status".to_string(),
+        }
+    }
+
+    // Format when the server started in the local time zone, like "2024-01-31 14:05"
+    pub fn get_start_time(&self) -> String {
+        format_time(self.started_at)
+    }
+
+    // Return how long the server ran before exiting
+    pub fn get_uptime(&self) -> String {
+        format_duration(self.exited_at.saturating_sub(self.started_at))
+    }
+
+    // Describe the exit, like "crashed 10 minutes ago with exit 137 after 2 hours"
+    pub fn get_summary(&self) -> String {
+        format!(
+            "{} {} {} after {}",
+            if self.is_crash() { "crashed" } else { "exited" },
+            format_time_ago(get_now_secs().saturating_sub(self.exited_at)),
+            self.get_cause(),
+            self.get_uptime()
+        )
+    }
+}
+
+// Format a time in seconds since the Unix epoch in the local time zone, like "2024-01-31 14:05"
+pub fn format_time(secs: u64) -> String {
+    Local
+        .timestamp_opt(secs as i64, 0)
+        .single()
+        .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+// This struct represents a length of time written like "30m", "12h", "7d", or "2w"
+#[derive(Clone, Copy, Debug)]
+pub struct Period {
+    pub seconds: u64,
+}
+
+impl FromStr for Period {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let error = || "must be a number followed by s, m, h, d, or w, like \"7d\"".to_string();
+        // Split before the last character rather than the last byte so that non-ASCII input is
+        // rejected instead of splitting a character in half
+        let (unit_index, _) = value.char_indices().last().ok_or_else(error)?;
+        let (count, unit) = value.split_at(unit_index);
+        let unit_seconds = match unit {
+            "s" => 1,
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => 24 * 60 * 60,
+            "w" => 7 * 24 * 60 * 60,
+            _ => return Err(error()),
+        };
+        let count = count.parse::<u64>().map_err(|_| error())?;
+        Ok(Period {
+            seconds: count.saturating_mul(unit_seconds),
+        })
+    }
+}
+
+// Load every recorded exit, oldest first
+pub fn load(history_path: &Path) -> Result<Vec<RunRecord>, ApplicationError> {
+    let history_str = fs::read_to_string(history_path).unwrap_or_else(|_| "runs = []".to_string());
+    let raw_history: RawRunHistory = toml::from_str(&history_str)
+        .map_err(|_| ApplicationError::ParseRunHistory(history_path.to_path_buf()))?;
+    Ok(raw_history.runs)
+}
+
+// Return the most recent exit of each server
+pub fn get_last_exits(history_path: &Path) -> Result<HashMap<String, RunRecord>, ApplicationError> {
+    Ok(load(history_path)?
+        .into_iter()
+        .map(|record| (record.server.clone(), record))
+        .collect())
+}
+
+// Permanently record a server's exit
+// The history is append-only so that it is a complete record of every run
+pub fn record(history_path: &Path, record: RunRecord) -> Result<(), ApplicationError> {
+    let write_error = || ApplicationError::WriteRunHistory(history_path.to_path_buf());
+    // Each record is its own [[runs]] table, so appending it to the file adds it to the array
+    let stringified =
+        toml::to_string_pretty(&RawRunHistory { runs: vec![record] }).map_err(|_| write_error())?;
+    if let Some(parent_dir) = history_path.parent() {
+        fs::create_dir_all(parent_dir).map_err(|_| write_error())?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(history_path)
+        .map_err(|_| write_error())?;
+    // Servers in the foreground and the daemon can exit at the same time, so lock the file to keep
+    // other processes from interleaving their entries with this one
+    // The lock is released when the file is closed
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+        return Err(write_error());
+    }
+    file.write_all(format!("{}\n", stringified).as_bytes())
+        .map_err(|_| write_error())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_period() {
+        assert_eq!("7d".parse::<Period>().unwrap().seconds, 7 * 24 * 60 * 60);
+        assert_eq!("30m".parse::<Period>().unwrap().seconds, 30 * 60);
+    }
+
+    #[test]
+    fn test_parse_period_without_count() {
+        assert!("d".parse::<Period>().is_err());
+    }
+
+    #[test]
+    fn test_parse_empty_period() {
+        assert!("".parse::<Period>().is_err());
+    }
+
+    #[test]
+    fn test_record_concurrently() {
+        let history_path = std::env::temp_dir().join(format!(
+            "server-room-history-test-{}.toml",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&history_path);
+        let threads = (0..20)
+            .map(|index| {
+                let history_path = history_path.clone();
+                std::thread::spawn(move || {
+                    let record = RunRecord {
+                        server: format!("server-{}", index),
+                        started_at: index,
+                        exited_at: index + 1,
+                        exit_code: Some(1),
+                        signal: None,
+                    };
+                    super::record(&history_path, record).unwrap();
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(load(&history_path).unwrap().len(), 20);
+        fs::remove_file(&history_path).unwrap();
+    }
+
+    #[test]
+    fn test_parse_non_ascii_period() {
+        assert!("7é".parse::<Period>().is_err());
+        assert!("é".parse::<Period>().is_err());
+        assert!("éd".parse::<Period>().is_err());
+    }
+}
@@ -0,0 +1,85 @@
+use super::package_manager::PackageManager;
+use super::project::Project;
+use super::server::Server;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// How many directories deep to look for projects
+const MAX_DEPTH: usize = 2;
+
+// Find all projects within the specified directory
+// Directories that contain a project are not searched any deeper
+pub fn find_projects(dir: &Path, default_package_manager: PackageManager) -> Vec<Project> {
+    let mut projects = vec![];
+    find_projects_recursive(dir, 0, default_package_manager, &mut projects);
+    projects.sort_by(|project1, project2| project1.dir.cmp(&project2.dir));
+    projects
+}
+
+// Find the projects within the directory that a server's missing project directory might have been
+// moved to, with projects whose package.json name matches before projects whose directory name matches
+// Projects match if either name is the server's name or the name of its old directory
+pub fn find_moved_project(
+    dir: &Path,
+    server: &Server,
+    default_package_manager: PackageManager,
+) -> Vec<PathBuf> {
+    let old_dir_name = server.dir.file_name().and_then(|name| name.to_str());
+    let matches = |name: &str| name == server.name || Some(name) == old_dir_name;
+    let projects = find_projects(dir, default_package_manager);
+    let package_matches = projects.iter().filter(|project| {
+        project
+            .get_package_name()
+            .is_some_and(|package_name| matches(&package_name))
+    });
+    let dir_matches = projects.iter().filter(|project| {
+        project
+            .dir
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(matches)
+    });
+    let mut moved_dirs = vec![];
+    for project in package_matches.chain(dir_matches) {
+        if !moved_dirs.contains(&project.dir) {
+            moved_dirs.push(project.dir.clone());
+        }
+    }
+    moved_dirs
+}
+
+fn find_projects_recursive(
+    dir: &Path,
+    depth: usize,
+    default_package_manager: PackageManager,
+    projects: &mut Vec<Project>,
+) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    let mut subdirs = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir() && !is_ignored(path))
+        .collect::<Vec<PathBuf>>();
+    subdirs.sort();
+
+    for subdir in subdirs {
+        match Project::from_path(subdir.clone(), default_package_manager) {
+            Ok(project) if project.has_manifest() => projects.push(project),
+            _ => find_projects_recursive(&subdir, depth + 1, default_package_manager, projects),
+        }
+    }
+}
+
+// Determine whether a directory should be skipped while scanning
+pub fn is_ignored(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_none_or(|name| name.starts_with('.') || name == "node_modules" || name == "target")
+}
@@ -3,10 +3,16 @@ use std::fmt;
 pub struct Script {
     pub name: String,
     pub command: String,
+    // The shell command that runs the script
+    pub start_command: String,
 }
 
 impl fmt::Display for Script {
     fn fmt(&self, formatter: &mut std::fmt::Formatter) -> fmt::Result {
-        write!(formatter, "{}: {}", self.name, self.command)
+        if self.name == self.command {
+            write!(formatter, "{}", self.name)
+        } else {
+            write!(formatter, "{}: {}", self.name, self.command)
+        }
     }
 }
@@ -0,0 +1,115 @@
+use super::server::Server;
+use colored::*;
+use ngrammatic::CorpusBuilder;
+
+// The minimum trigram similarity for a field to match a query that it doesn't contain
+const MIN_SIMILARITY: f32 = 0.3;
+
+// This enum represents the fields of a server that are searched, in order of priority
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Field {
+    Name,
+    StartCommand,
+    Dir,
+    Description,
+}
+
+const FIELDS: [Field; 4] = [
+    Field::Name,
+    Field::StartCommand,
+    Field::Dir,
+    Field::Description,
+];
+
+impl Field {
+    // Return the server's text for this field, if it has one
+    fn get_text(&self, server: &Server) -> Option<String> {
+        match self {
+            Field::Name => Some(server.name.clone()),
+            Field::StartCommand => Some(server.start_command.clone()),
+            Field::Dir => Some(server.dir.display().to_string()),
+            Field::Description => server.description.clone(),
+        }
+    }
+}
+
+// This struct represents a server that matches a search query and the field that matched best
+pub struct SearchMatch<'s> {
+    pub server: &'s Server,
+    pub field: Field,
+    pub score: f32,
+}
+
+// Find the servers with a field that matches the query, ordered from the best match to the worst
+// Fields that contain the query rank above fields that are only similar to it
+pub fn find<'s>(servers: Vec<&'s Server>, query: &str) -> Vec<SearchMatch<'s>> {
+    let query = query.to_lowercase();
+    let mut matches = servers
+        .into_iter()
+        .filter_map(|server| {
+            FIELDS
+                .iter()
+                .filter_map(|field| {
+                    let score = get_score(&field.get_text(server)?, &query)?;
+                    Some(SearchMatch {
+                        server,
+                        field: *field,
+                        score,
+                    })
+                })
+                // Keep the first field out of fields with the same score
+                .fold(None, |best: Option<SearchMatch>, current| match best {
+                    Some(best) if best.score >= current.score => Some(best),
+                    _ => Some(current),
+                })
+        })
+        .collect::<Vec<_>>();
+    matches.sort_by(|match1, match2| {
+        match2
+            .score
+            .partial_cmp(&match1.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| match1.server.name.cmp(&match2.server.name))
+    });
+    matches
+}
+
+// Score how well the text matches the lowercase query, or return None if it doesn't match
+fn get_score(text: &str, query: &str) -> Option<f32> {
+    if text.to_lowercase().contains(query) {
+        // Shorter texts match the query more closely
+        return Some(1.0 + query.len() as f32 / text.len() as f32);
+    }
+    let mut corpus = CorpusBuilder::new().case_insensitive().finish();
+    corpus.add_text(text);
+    corpus
+        .search(query, MIN_SIMILARITY)
+        .first()
+        .map(|result| result.similarity)
+}
+
+// Highlight the part of the text that contains the query, or the whole text if it is only similar
+// The rest of the text is drawn in the base color, if any
+pub fn highlight(text: &str, query: &str, base_color: Option<Color>) -> String {
+    let paint = |text: &str| match base_color {
+        Some(color) => text.color(color).to_string(),
+        None => text.to_string(),
+    };
+    let (lowercase_text, lowercase_query) = (text.to_lowercase(), query.to_lowercase());
+    let start = lowercase_text
+        .find(&lowercase_query)
+        // Lowercasing can change the length of non-ASCII text, which makes its offsets unusable
+        .filter(|_| lowercase_text.len() == text.len());
+    match start {
+        Some(start) => {
+            let end = start + lowercase_query.len();
+            format!(
+                "{}{}{}",
+                paint(&text[..start]),
+                text[start..end].bold().yellow(),
+                paint(&text[end..])
+            )
+        }
+        None => paint(text).underline().to_string(),
+    }
+}
@@ -1,11 +1,57 @@
+use super::envfile;
 use super::error::ApplicationError;
+use super::health::HealthCheck;
+use super::logging;
+use super::package_manager::PackageManager;
+use super::process;
 use super::project::Project;
+use super::shell::Shell;
+use super::tls::TlsCert;
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::f64::consts::LN_2;
 use std::fmt;
-use std::path::PathBuf;
-use std::process::Command;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-// This struct represents the server as used by the rest of the application
+// This enum represents when a server should be restarted after its start command exits
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartPolicy {
+    #[default]
+    Never,
+    OnFailure,
+    Always,
+}
+
+impl fmt::Display for RestartPolicy {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> fmt::Result {
+        let policy = match self {
+            RestartPolicy::Never => "never",
+            RestartPolicy::OnFailure => "on-failure",
+            RestartPolicy::Always => "always",
+        };
+        write!(formatter, "{}", policy)
+    }
+}
+
+impl RestartPolicy {
+    // Determine whether a server that exited with the provided status should be restarted
+    pub fn should_restart(&self, status: &ExitStatus) -> bool {
+        match self {
+            RestartPolicy::Never => false,
+            RestartPolicy::OnFailure => !status.success(),
+            RestartPolicy::Always => true,
+        }
+    }
+}
+
+/// A server that server-room manages, which is a start command that runs in a project directory
+/// on a port
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Server {
     pub name: String,
@@ -13,6 +59,86 @@ pub struct Server {
     pub start_command: String,
     pub port: u16,
     pub frecency: f64,
+    #[serde(default)]
+    pub restart: RestartPolicy,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package_manager: Option<PackageManager>,
+    // Whether to set the PORT environment variable when starting the server
+    #[serde(default = "default_inject_port")]
+    pub inject_port: bool,
+    // Dotenv files relative to the project directory that are loaded when starting the server
+    #[serde(default)]
+    pub env_files: Vec<PathBuf>,
+    // The names of the servers that must be started before this server
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    // Archived servers are disabled, which hides them from prompts, listings, and proxies while
+    // keeping their history
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    // Pinned servers are listed first when picking a server
+    #[serde(default)]
+    pub pinned: bool,
+    // The time that the server was last started, in seconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_run: Option<u64>,
+    #[serde(default)]
+    pub run_count: u32,
+    // The rolling average of the seconds between starting the server and it becoming ready
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub startup_time: Option<f64>,
+    // The number of minutes without activity after which the proxy stops the server
+    // Zero disables idle shutdown, and None falls back to the config's idle timeout
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_timeout: Option<u64>,
+    // The hostname that proxies route to the server instead of the name-derived default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    // The number of seconds that the server gets to exit after SIGTERM before it is killed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grace_period: Option<u64>,
+    // The shell that runs the start command, which defaults to the config's shell
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shell: Option<Shell>,
+    // A note describing what the server is
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    // The port that Node's inspector listens on during this run, which is never saved
+    #[serde(skip)]
+    pub inspect_port: Option<u16>,
+    // TOML tables must come after plain values, so these tables must be the last fields
+    // Environment variables set when starting the server, which override ones from env files
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    // The certificate presented for the server's domain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<TlsCert>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health_check: Option<HealthCheck>,
+}
+
+// Uses the frecency algorithm described here https://wiki.mozilla.org/User:Jesse/NewFrecency
+const FRECENCY_HALF_LIFE_MICROS: f64 = 30f64 * 24f64 * 60f64 * 60f64 * 1_000_000f64; // one month
+const DECAY: f64 = LN_2 / FRECENCY_HALF_LIFE_MICROS;
+const SCORE_INCREASE_PER_RUN: f64 = 1f64;
+
+// How much the latest startup time counts towards the rolling average
+const STARTUP_TIME_WEIGHT: f64 = 0.3;
+
+// The number of seconds that servers get to exit after SIGTERM by default
+pub const DEFAULT_GRACE_PERIOD: u64 = 5;
+
+// The port that Node's inspector listens on by default
+pub const DEFAULT_INSPECT_PORT: u16 = 9229;
+
+fn default_inject_port() -> bool {
+    true
+}
+
+fn default_enabled() -> bool {
+    true
 }
 
 impl fmt::Display for Server {
@@ -30,12 +156,35 @@ impl Server {
             start_command,
             port,
             frecency: 0f64,
+            restart: RestartPolicy::default(),
+            tags: vec![],
+            package_manager: None,
+            inject_port: default_inject_port(),
+            env_files: vec![],
+            depends_on: vec![],
+            enabled: default_enabled(),
+            pinned: false,
+            last_run: None,
+            run_count: 0,
+            startup_time: None,
+            idle_timeout: None,
+            domain: None,
+            grace_period: None,
+            shell: None,
+            description: None,
+            inspect_port: None,
+            env: BTreeMap::new(),
+            tls: None,
+            health_check: None,
         }
     }
 
     // Create a new server from a project
     pub fn from_project(project: Project, start_command: String, port: u16) -> Self {
-        Self::new(project.name, project.dir, start_command, port)
+        Server {
+            package_manager: project.package_manager,
+            ..Self::new(project.name, project.dir, start_command, port)
+        }
     }
 
     // Calculate the likelihood that this server will be used again
@@ -44,16 +193,222 @@ impl Server {
         self.frecency
     }
 
-    // Start up the server
-    pub fn start(&self) -> Result<(), ApplicationError> {
+    // Calculate the server's current frecency score, which halves every month that it isn't run
+    pub fn get_frecency_score(&self) -> f64 {
+        (self.frecency - get_now_decay()).exp()
+    }
+
+    // Update the server's frecency and usage statistics to reflect that it was just started
+    pub fn record_run(&mut self) {
+        let now_decay = get_now_decay();
+        let score = (self.frecency - now_decay).exp();
+        let new_score = score + SCORE_INCREASE_PER_RUN;
+        self.frecency = new_score.ln() + now_decay;
+        self.last_run = Some(get_now_secs());
+        self.run_count += 1;
+    }
+
+    // Update the rolling average startup time to include a start that took this long to be ready
+    pub fn record_startup_time(&mut self, elapsed: Duration) {
+        let elapsed = elapsed.as_secs_f64();
+        self.startup_time = Some(match self.startup_time {
+            Some(average) => average + STARTUP_TIME_WEIGHT * (elapsed - average),
+            None => elapsed,
+        });
+    }
+
+    // Describe how long the server usually takes to be ready, like "typically ready in ~4s"
+    pub fn get_startup_summary(&self) -> Option<String> {
+        self.startup_time
+            .map(|average| format!("typically ready in ~{}s", (average.round() as u64).max(1)))
+    }
+
+    // Summarize how recently and how often the server has been run, like "last run 2 days ago, 37 runs"
+    pub fn get_usage_summary(&self) -> String {
+        let last_run = match self.last_run {
+            Some(last_run) => format!(
+                "last run {}",
+                format_time_ago(get_now_secs().saturating_sub(last_run))
+            ),
+            None => "never run".to_string(),
+        };
+        let runs = if self.run_count == 1 { "run" } else { "runs" };
+        format!("{}, {} {}", last_run, self.run_count, runs)
+    }
+
+    // Return the URL that the server can be visited at
+    pub fn get_url(&self) -> String {
+        match &self.domain {
+            Some(domain) => format!("http://{}", domain),
+            None => format!("http://localhost:{}", self.port),
+        }
+    }
+
+    // Calculate the hostname that proxies route to the server, deriving it from the server's name
+    // and the suffix if the server doesn't have a custom domain
+    // Slashes in the names of workspace packages aren't valid in hostnames
+    pub fn get_domain(&self, suffix: &str) -> String {
+        self.domain
+            .clone()
+            .unwrap_or_else(|| format!("{}{}", self.name.replace('/', "-"), suffix))
+    }
+
+    // Make sure that the domain is a hostname that can be written to the hosts file and Caddyfile
+    pub fn validate_domain(domain: &str) -> Result<(), ApplicationError> {
+        let is_valid_label = |label: &str| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .chars()
+                    .all(|char| char.is_ascii_alphanumeric() || char == '-')
+        };
+        if domain.len() <= 253 && domain.split('.').all(is_valid_label) {
+            Ok(())
+        } else {
+            Err(ApplicationError::InvalidDomain(domain.to_string()))
+        }
+    }
+
+    // Calculate how long the server may be idle before it is stopped, if at all
+    pub fn get_idle_timeout(&self, default_minutes: Option<u64>) -> Option<Duration> {
+        match self.idle_timeout.or(default_minutes) {
+            None | Some(0) => None,
+            Some(minutes) => Some(Duration::from_secs(minutes.saturating_mul(60))),
+        }
+    }
+
+    // Return how long the server gets to exit after SIGTERM before it is killed
+    pub fn get_grace_period(&self) -> Duration {
+        Duration::from_secs(self.grace_period.unwrap_or(DEFAULT_GRACE_PERIOD))
+    }
+
+    // Return the shell that runs the server's commands
+    pub fn get_shell(&self) -> Shell {
+        self.shell.unwrap_or_else(Shell::get_default)
+    }
+
+    // Determine whether the server has the specified tag
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|server_tag| server_tag == tag)
+    }
+
+    // Build the command that executes the server's start command in its project directory
+    pub fn build_command(&self) -> Result<Command, ApplicationError> {
+        let mut command = self.get_shell().build_command(&self.start_command);
+        command.current_dir(self.get_project_dir());
+        // Later env files override variables from earlier ones
+        for env_file in &self.env_files {
+            command.envs(envfile::load(&self.get_project_dir().join(env_file))?);
+        }
+        command.envs(&self.env);
+        // Let the server know which port server-room expects it to listen on
+        if self.inject_port {
+            command.env("PORT", self.port.to_string());
+        }
+        // Keep the Node options that the user already set, preferring the ones from the server's
+        // env files and variables because they override the inherited environment
+        if let Some(inspect_port) = self.inspect_port {
+            let inspect = format!("--inspect=127.0.0.1:{}", inspect_port);
+            let node_options = command
+                .get_envs()
+                .find(|(key, _)| *key == "NODE_OPTIONS")
+                .map(|(_, value)| value.map(|value| value.to_string_lossy().to_string()))
+                .unwrap_or_else(|| env::var("NODE_OPTIONS").ok());
+            let node_options = match node_options {
+                Some(node_options) if !node_options.is_empty() => {
+                    format!("{} {}", node_options, inspect)
+                }
+                _ => inspect,
+            };
+            command.env("NODE_OPTIONS", node_options);
+        }
+        Ok(command)
+    }
+
+    // Start up the server, copying its output to the log file if one is provided
+    // Returns the server's exit status once it exits
+    pub fn start(&self, log_path: Option<&Path>) -> Result<ExitStatus, ApplicationError> {
         // Execute the server's start command, sending input and output to stdin and stdout
-        let status = Command::new("sh")
-            .args(["-c", self.start_command.as_str()])
-            .current_dir(self.get_project_dir())
-            .status();
-        match status {
-            Ok(_) => Ok(()),
-            Err(_) => Err(ApplicationError::RunScript(self.start_command.clone())),
+        let mut command = self.build_command()?;
+        match log_path {
+            Some(log_path) => logging::run_logged(
+                command,
+                self.start_command.as_str(),
+                log_path,
+                self.get_grace_period(),
+            ),
+            None => {
+                let (mut child, group) =
+                    process::ForegroundGroup::spawn(&mut command, true, self.get_grace_period())
+                        .map_err(|_| ApplicationError::RunScript(self.start_command.clone()))?;
+                group
+                    .wait(&mut child)
+                    .map_err(|_| ApplicationError::RunScript(self.start_command.clone()))
+            }
+        }
+    }
+
+    // Start up the server in the background and return its PID
+    pub fn start_detached(&self, log_path: Option<&Path>) -> Result<u32, ApplicationError> {
+        process::spawn_detached(self.build_command()?, self.start_command.as_str(), log_path)
+    }
+
+    // Determine whether the server's project has been deleted
+    pub fn is_dead(&self) -> bool {
+        let dir = self.get_project_dir();
+        // Node servers can't start without their package.json file
+        !dir.is_dir() || (self.package_manager.is_some() && !dir.join("package.json").is_file())
+    }
+
+    // Determine why the server's dependencies need to be installed before it can start, if they do
+    pub fn get_install_reason(&self) -> Option<&'static str> {
+        let package_manager = self.package_manager?;
+        let dir = self.get_project_dir();
+        if !dir.join("package.json").is_file() {
+            return None;
+        }
+        let node_modules = match fs::metadata(dir.join("node_modules")) {
+            Ok(metadata) => metadata,
+            Err(_) => return Some("node_modules doesn't exist"),
+        };
+        // Installing dependencies updates node_modules, so a newer lockfile means that it changed
+        // since the last install, like after pulling new commits
+        let installed_at = node_modules.modified().ok()?;
+        let lockfile_changed = package_manager.get_lockfiles().iter().any(|lockfile| {
+            fs::metadata(dir.join(lockfile))
+                .and_then(|metadata| metadata.modified())
+                .is_ok_and(|modified_at| modified_at > installed_at)
+        });
+        if lockfile_changed {
+            Some("the lockfile changed since node_modules was installed")
+        } else {
+            None
+        }
+    }
+
+    // Build the command that installs the server's dependencies with its package manager
+    pub fn build_install_command(&self) -> Command {
+        let mut command = Command::new(self.package_manager.unwrap_or_default().get_executable());
+        command.arg("install").current_dir(self.get_project_dir());
+        command
+    }
+
+    // Install the server's dependencies with its package manager
+    pub fn install_dependencies(&self) -> Result<(), ApplicationError> {
+        let install_command = self
+            .package_manager
+            .unwrap_or_default()
+            .get_install_command();
+        let status = self
+            .build_install_command()
+            .status()
+            .map_err(|_| ApplicationError::RunScript(install_command))?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(ApplicationError::InstallDependencies(self.name.clone()))
         }
     }
 
@@ -62,3 +417,59 @@ impl Server {
         self.dir.clone()
     }
 }
+
+// Return the current time in seconds since the Unix epoch
+pub fn get_now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+// Return the frecency decay accumulated between the Unix epoch and now
+fn get_now_decay() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_else(|_| std::time::Duration::from_micros(0))
+        .as_micros() as f64
+        * DECAY
+}
+
+// Describe an elapsed number of seconds in the largest whole unit, like "3 hours ago"
+pub fn format_time_ago(seconds: u64) -> String {
+    if seconds < 60 {
+        "just now".to_string()
+    } else {
+        format!("{} ago", format_duration(seconds))
+    }
+}
+
+// Describe a number of seconds in the largest whole unit, like "3 hours"
+pub fn format_duration(seconds: u64) -> String {
+    const UNITS: [(&str, u64); 6] = [
+        ("year", 365 * 24 * 60 * 60),
+        ("month", 30 * 24 * 60 * 60),
+        ("day", 24 * 60 * 60),
+        ("hour", 60 * 60),
+        ("minute", 60),
+        ("second", 1),
+    ];
+    let (unit, unit_seconds) = UNITS
+        .iter()
+        .find(|(_, unit_seconds)| seconds >= *unit_seconds)
+        .unwrap_or(&("second", 1));
+    let count = seconds / unit_seconds;
+    let plural = if count == 1 { "" } else { "s" };
+    format!("{} {}{}", count, unit, plural)
+}
+
+// Trim a list of tags or server names, dropping blank and duplicate ones
+pub fn normalize_names(names: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = vec![];
+    for name in names.iter().map(|name| name.trim()) {
+        if !name.is_empty() && !normalized.iter().any(|existing| existing == name) {
+            normalized.push(name.to_string());
+        }
+    }
+    normalized
+}
@@ -1,41 +1,51 @@
+use super::envfile;
 use super::error::ApplicationError;
+use super::health::HealthCheck;
+use super::logging;
+use super::package_manager::PackageManager;
+use super::port;
+use super::process::{self, RuntimeState, StopPhase};
 use super::project::Project;
-use super::server::Server;
+use super::run_history::{self, RunRecord};
+use super::server::{get_now_secs, normalize_names, RestartPolicy, Server};
+use super::shell::Shell;
+use super::store::{self, RawServerStore, Store};
+use super::supervisor;
+use super::tls::TlsCert;
 use ngrammatic::CorpusBuilder;
-use serde::{Deserialize, Serialize};
-use std::f64::consts::LN_2;
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::ops::RangeInclusive;
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info};
 
-// This struct represents the user-configured servers used by the rest of the application
+/// The servers that the user has added, keyed by name
+///
+/// Load a store with [`ServerStore::load`], usually from [`crate::get_default_store_path`].
 // It is stored as a vector in the Datastore, but is deserialized into a hashmap of servers, where
 // the key is the server name
 #[derive(Clone)]
 pub struct ServerStore {
     servers: std::collections::HashMap<String, Server>,
-    store_path: PathBuf,
-}
-
-#[derive(Deserialize, Serialize)]
-pub struct RawServerStore {
-    servers: Vec<Server>,
+    store: Arc<dyn Store>,
 }
 
 impl ServerStore {
     // Load the data store from disk
     pub fn load(store_path: PathBuf) -> Result<ServerStore, ApplicationError> {
-        let server_store_str =
-            fs::read_to_string(&store_path).unwrap_or_else(|_| "servers = []".to_string());
-        let raw_store: RawServerStore = toml::from_str(&server_store_str)
-            .map_err(|_| ApplicationError::ParseStore(store_path.clone()))?;
+        let store = store::open(store_path.clone())?;
+        let servers = store.load()?;
+        debug!("Loaded {} servers from {:?}", servers.len(), store_path);
         Ok(ServerStore {
-            servers: raw_store
-                .servers
+            servers: servers
                 .into_iter()
                 .map(|server| (server.name.clone(), server))
                 .collect(),
-            store_path,
+            store,
         })
     }
 
@@ -46,19 +56,42 @@ impl ServerStore {
         // Sort the servers lexicographically by their name
         servers.sort_by(|server1, server2| server1.name.cmp(&server2.name));
 
-        let stringified = toml::to_string_pretty(&RawServerStore { servers })
-            .map_err(|_| ApplicationError::StringifyStore)?;
-
-        // Create the parent directory before attempting to write the new store file
-        let parent_dir = self
-            .store_path
-            .parent()
-            .ok_or_else(|| ApplicationError::WriteStore(self.store_path.clone()))?;
-        fs::create_dir_all(parent_dir)
-            .map_err(|_| ApplicationError::WriteStore(self.store_path.clone()))?;
-        fs::write(&self.store_path, stringified)
-            .map_err(|_| ApplicationError::WriteStore(self.store_path.clone()))?;
-        Ok(())
+        debug!("Saving {} servers", servers.len());
+        self.store.save(servers)
+    }
+
+    // Read the servers from a file created by exporting a server store
+    pub fn read_export(path: &Path) -> Result<Vec<Server>, ApplicationError> {
+        let content = fs::read_to_string(path)
+            .map_err(|_| ApplicationError::ReadExport(path.to_path_buf()))?;
+        // Also accept the array of servers printed by `list --format json`
+        let servers = toml::from_str::<RawServerStore>(&content)
+            .or_else(|_| serde_json::from_str::<RawServerStore>(&content))
+            .map(|raw_store| raw_store.servers)
+            .or_else(|_| serde_json::from_str::<Vec<Server>>(&content))
+            .map_err(|_| ApplicationError::ParseExport(path.to_path_buf()))?;
+
+        let mut names = HashSet::new();
+        if let Some(server) = servers
+            .iter()
+            .find(|server| !names.insert(server.name.as_str()))
+        {
+            return Err(ApplicationError::DuplicateServerName(server.name.clone()));
+        }
+        for domain in servers.iter().filter_map(|server| server.domain.as_ref()) {
+            Server::validate_domain(domain)?;
+        }
+        Ok(servers)
+    }
+
+    // Permanently replace every server in the server store
+    pub fn set_servers(&self, servers: Vec<Server>) -> Result<(), ApplicationError> {
+        let mut new_store = self.clone();
+        new_store.servers = servers
+            .into_iter()
+            .map(|server| (server.name.clone(), server))
+            .collect();
+        new_store.flush()
     }
 
     // Permanently add a new server to the server store
@@ -68,30 +101,136 @@ impl ServerStore {
         start_command: String,
         port: u16,
     ) -> Result<(), ApplicationError> {
-        // Don't add the project if it doesn't validate
-        self.validate_new_project(project)?;
+        let server = Server::from_project(project.clone(), start_command, port);
+        self.add_servers(vec![server])
+    }
 
+    // Permanently add several new servers to the server store
+    pub fn add_servers(&self, servers: Vec<Server>) -> Result<(), ApplicationError> {
         let mut new_store = self.clone();
-        let server = Server::from_project(project.clone(), start_command, port);
-        new_store.servers.insert(project.name.clone(), server);
+        for server in servers {
+            // Don't add any servers if one of them doesn't validate
+            // Servers added together may share a directory, like the processes in a Procfile
+            new_store.validate_new_server_name(&server.name)?;
+            self.validate_new_server_dir(&server.dir)?;
+            new_store.servers.insert(server.name.clone(), server);
+        }
+        new_store.flush()
+    }
+
+    // Permanently add a copy of the specified server with a new name and port
+    // The copy starts without usage statistics or a custom domain, which must be unique
+    pub fn clone_server(
+        &self,
+        server_name: &str,
+        new_name: String,
+        port: u16,
+    ) -> Result<(), ApplicationError> {
+        if new_name.is_empty() {
+            return Err(ApplicationError::EmptyServerName);
+        }
+        self.validate_new_server_name(&new_name)?;
+        if let Some(existing) = self.servers.values().find(|server| server.port == port) {
+            return Err(ApplicationError::DuplicateServerPort {
+                port,
+                existing: existing.name.clone(),
+            });
+        }
+
+        let copy = Server {
+            name: new_name,
+            port,
+            domain: None,
+            tls: None,
+            pinned: false,
+            frecency: 0f64,
+            last_run: None,
+            run_count: 0,
+            startup_time: None,
+            ..self.get_one(server_name)?.clone()
+        };
+        let mut new_store = self.clone();
+        new_store.servers.insert(copy.name.clone(), copy);
         new_store.flush()
     }
 
+    // Describe the problems that would prevent the servers from being used together, like servers
+    // with the same name or port and servers whose directories don't exist
+    pub fn find_conflicts(servers: &[Server]) -> Vec<String> {
+        let mut conflicts = vec![];
+        let find_duplicates = |key: &dyn Fn(&Server) -> String| {
+            let mut groups = BTreeMap::<String, Vec<&str>>::new();
+            for server in servers {
+                groups.entry(key(server)).or_default().push(&server.name);
+            }
+            groups
+                .into_iter()
+                .filter(|(_, names)| names.len() > 1)
+                .collect::<Vec<_>>()
+        };
+        for (name, names) in find_duplicates(&|server| server.name.clone()) {
+            conflicts.push(format!("{} servers are named \"{}\"", names.len(), name));
+        }
+        for (port, names) in find_duplicates(&|server| server.port.to_string()) {
+            conflicts.push(format!("Port {} is used by {}", port, names.join(", ")));
+        }
+        for (dir, names) in find_duplicates(&|server| server.dir.display().to_string()) {
+            conflicts.push(format!(
+                "Directory \"{}\" is used by {}",
+                dir,
+                names.join(", ")
+            ));
+        }
+        for server in servers {
+            if server.name.is_empty() {
+                conflicts.push("A server has an empty name".to_string());
+            }
+            if !server.dir.is_dir() {
+                conflicts.push(format!(
+                    "Directory {:?} of server \"{}\" doesn't exist",
+                    server.dir, server.name
+                ));
+            }
+            for dependency in &server.depends_on {
+                if !servers.iter().any(|other| &other.name == dependency) {
+                    conflicts.push(format!(
+                        "Server \"{}\" depends on nonexistent server \"{}\"",
+                        server.name, dependency
+                    ));
+                }
+            }
+        }
+        conflicts
+    }
+
     // Check whether the project is a valid new project
     // Checks whether the name and directory are unique
     pub fn validate_new_project(&self, project: &Project) -> Result<(), ApplicationError> {
-        if self.servers.contains_key(&project.name) {
-            return Err(ApplicationError::DuplicateServerName(project.name.clone()));
+        self.validate_new_server_name(&project.name)?;
+        self.validate_new_server_dir(&project.dir)
+    }
+
+    // Check whether a new server could use the specified name and directory
+    pub fn validate_new_server(&self, name: &str, dir: &Path) -> Result<(), ApplicationError> {
+        self.validate_new_server_name(name)?;
+        self.validate_new_server_dir(dir)
+    }
+
+    // Check whether a new server could use the specified name
+    fn validate_new_server_name(&self, name: &str) -> Result<(), ApplicationError> {
+        if self.servers.contains_key(name) {
+            return Err(ApplicationError::DuplicateServerName(name.to_string()));
         }
 
-        if let Some(existing) = self
-            .servers
-            .values()
-            .find(|server| server.dir == project.dir)
-        {
+        Ok(())
+    }
+
+    // Check whether a new server could use the specified directory
+    fn validate_new_server_dir(&self, dir: &Path) -> Result<(), ApplicationError> {
+        if let Some(existing) = self.servers.values().find(|server| server.dir == dir) {
             return Err(ApplicationError::DuplicateServerDir {
-                dir: project.dir.clone(),
-                existing: existing.clone(),
+                dir: dir.to_path_buf(),
+                existing: Box::new(existing.clone()),
             });
         }
 
@@ -114,59 +253,404 @@ impl ServerStore {
 
         let mut new_store = self.clone();
         let server = new_store.get_one_mut(server_name)?;
-        server.name = new_name;
+        server.name = new_name.clone();
+        // Keep the servers that depend on the renamed server pointing at it
+        for server in new_store.servers.values_mut() {
+            for dependency in server.depends_on.iter_mut() {
+                if dependency == server_name {
+                    *dependency = new_name.clone();
+                }
+            }
+        }
         new_store.flush()
     }
 
-    // Permanently set the start command of the specified server
+    // Permanently set the start command and package manager of the specified server
     pub fn set_server_start_command(
         &self,
         server_name: &str,
         start_command: String,
+        package_manager: Option<PackageManager>,
     ) -> Result<(), ApplicationError> {
         let mut new_store = self.clone();
         let server = new_store.get_one_mut(server_name)?;
         server.start_command = start_command;
+        server.package_manager = package_manager;
         new_store.flush()
     }
 
-    // Permanently set the port of the specified server
+    // Permanently move the specified server to a new project directory
+    pub fn set_server_dir(&self, server_name: &str, dir: PathBuf) -> Result<(), ApplicationError> {
+        if let Some(existing) = self
+            .servers
+            .values()
+            .find(|server| server.name != server_name && server.dir == dir)
+        {
+            return Err(ApplicationError::DuplicateServerDir {
+                dir,
+                existing: Box::new(existing.clone()),
+            });
+        }
+
+        let mut new_store = self.clone();
+        let server = new_store.get_one_mut(server_name)?;
+        server.dir = dir;
+        new_store.flush()
+    }
+
+    // Permanently set the port of the specified server, which no other server can have
     pub fn set_server_port(&self, server_name: &str, port: u16) -> Result<(), ApplicationError> {
+        if let Some(existing) = self
+            .servers
+            .values()
+            .find(|server| server.port == port && server.name != server_name)
+        {
+            return Err(ApplicationError::DuplicateServerPort {
+                port,
+                existing: existing.name.clone(),
+            });
+        }
+
         let mut new_store = self.clone();
         let server = new_store.get_one_mut(server_name)?;
         server.port = port;
         new_store.flush()
     }
 
-    // Permanently record a new start time
-    pub fn start_server(&self, server_name: &str) -> Result<(), ApplicationError> {
+    // Permanently archive or unarchive the specified server
+    pub fn set_server_enabled(
+        &self,
+        server_name: &str,
+        enabled: bool,
+    ) -> Result<(), ApplicationError> {
+        let mut new_store = self.clone();
+        let server = new_store.get_one_mut(server_name)?;
+        server.enabled = enabled;
+        new_store.flush()
+    }
+
+    // Permanently pin or unpin the specified server
+    pub fn set_server_pinned(
+        &self,
+        server_name: &str,
+        pinned: bool,
+    ) -> Result<(), ApplicationError> {
+        let mut new_store = self.clone();
+        let server = new_store.get_one_mut(server_name)?;
+        server.pinned = pinned;
+        new_store.flush()
+    }
+
+    // Permanently set whether the PORT environment variable is set for the specified server
+    pub fn set_server_inject_port(
+        &self,
+        server_name: &str,
+        inject_port: bool,
+    ) -> Result<(), ApplicationError> {
+        let mut new_store = self.clone();
+        let server = new_store.get_one_mut(server_name)?;
+        server.inject_port = inject_port;
+        new_store.flush()
+    }
+
+    // Permanently set the dotenv files of the specified server
+    pub fn set_server_env_files(
+        &self,
+        server_name: &str,
+        env_files: Vec<PathBuf>,
+    ) -> Result<(), ApplicationError> {
+        let mut new_store = self.clone();
+        let server = new_store.get_one_mut(server_name)?;
+
+        // Make sure that the env files are valid before saving them
+        for env_file in &env_files {
+            envfile::load(&server.get_project_dir().join(env_file))?;
+        }
+        server.env_files = env_files;
+        new_store.flush()
+    }
+
+    // Permanently set the health check of the specified server
+    pub fn set_server_health_check(
+        &self,
+        server_name: &str,
+        health_check: Option<HealthCheck>,
+    ) -> Result<(), ApplicationError> {
+        if let Some(health_check) = &health_check {
+            health_check.validate()?;
+        }
+
+        let mut new_store = self.clone();
+        new_store.get_one_mut(server_name)?.health_check = health_check;
+        new_store.flush()
+    }
+
+    // Permanently set the certificate of each of the specified servers
+    pub fn set_server_tls_certs(
+        &self,
+        tls_certs: Vec<(String, Option<TlsCert>)>,
+    ) -> Result<(), ApplicationError> {
+        let mut new_store = self.clone();
+        for (server_name, tls_cert) in tls_certs {
+            new_store.get_one_mut(&server_name)?.tls = tls_cert;
+        }
+        new_store.flush()
+    }
+
+    // Permanently set the custom domain of the specified server
+    pub fn set_server_domain(
+        &self,
+        server_name: &str,
+        domain: Option<String>,
+        domain_suffix: &str,
+    ) -> Result<(), ApplicationError> {
+        if let Some(domain) = &domain {
+            Server::validate_domain(domain)?;
+        }
+
+        let mut new_store = self.clone();
+        new_store.get_one_mut(server_name)?.domain = domain;
+        let new_domain = new_store.get_one(server_name)?.get_domain(domain_suffix);
+        if new_store.servers.values().any(|server| {
+            server.name != server_name && server.get_domain(domain_suffix) == new_domain
+        }) {
+            return Err(ApplicationError::DuplicateServerDomain(new_domain));
+        }
+        new_store.flush()
+    }
+
+    // Permanently set the idle timeout of the specified server
+    pub fn set_server_idle_timeout(
+        &self,
+        server_name: &str,
+        idle_timeout: Option<u64>,
+    ) -> Result<(), ApplicationError> {
+        let mut new_store = self.clone();
+        new_store.get_one_mut(server_name)?.idle_timeout = idle_timeout;
+        new_store.flush()
+    }
+
+    // Permanently set the grace period of the specified server, or use the default if it is None
+    pub fn set_server_grace_period(
+        &self,
+        server_name: &str,
+        grace_period: Option<u64>,
+    ) -> Result<(), ApplicationError> {
+        let mut new_store = self.clone();
+        new_store.get_one_mut(server_name)?.grace_period = grace_period;
+        new_store.flush()
+    }
+
+    // Permanently set the restart policy of the specified server
+    pub fn set_server_restart_policy(
+        &self,
+        server_name: &str,
+        restart: RestartPolicy,
+    ) -> Result<(), ApplicationError> {
         let mut new_store = self.clone();
         let server = new_store.get_one_mut(server_name)?;
+        server.restart = restart;
+        new_store.flush()
+    }
 
-        // Uses the frecency algorithm described here https://wiki.mozilla.org/User:Jesse/NewFrecency
-        const FRECENCY_HALF_LIFE_MICROS: f64 = 30f64 * 24f64 * 60f64 * 60f64 * 1_000_000f64; // one month
-        const DECAY: f64 = LN_2 / FRECENCY_HALF_LIFE_MICROS;
-        const SCORE_INCREASE_PER_RUN: f64 = 1f64;
-        let now_decay = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_else(|_| std::time::Duration::from_micros(0))
-            .as_micros() as f64
-            * DECAY;
-        let score = (server.frecency - now_decay).exp();
-        let new_score = score + SCORE_INCREASE_PER_RUN;
-        server.frecency = new_score.ln() + now_decay;
-        new_store.flush()?;
+    // Permanently set the shell of the specified server, or use the default shell if it is None
+    pub fn set_server_shell(
+        &self,
+        server_name: &str,
+        shell: Option<Shell>,
+    ) -> Result<(), ApplicationError> {
+        let mut new_store = self.clone();
+        let server = new_store.get_one_mut(server_name)?;
+        server.shell = shell;
+        new_store.flush()
+    }
 
-        new_store.get_one(server_name)?.start()
+    // Permanently set the description of the specified server
+    pub fn set_server_description(
+        &self,
+        server_name: &str,
+        description: Option<String>,
+    ) -> Result<(), ApplicationError> {
+        let mut new_store = self.clone();
+        let server = new_store.get_one_mut(server_name)?;
+        server.description = description;
+        new_store.flush()
+    }
+
+    // Permanently set the tags of the specified server
+    pub fn set_server_tags(
+        &self,
+        server_name: &str,
+        tags: Vec<String>,
+    ) -> Result<(), ApplicationError> {
+        let mut new_store = self.clone();
+        // Ignore blank and duplicate tags
+        new_store.get_one_mut(server_name)?.tags = normalize_names(&tags);
+        new_store.flush()
+    }
+
+    // Permanently set the servers that the specified server depends on
+    pub fn set_server_dependencies(
+        &self,
+        server_name: &str,
+        depends_on: Vec<String>,
+    ) -> Result<(), ApplicationError> {
+        let new_depends_on = normalize_names(&depends_on);
+        for dependency in &new_depends_on {
+            self.get_one(dependency)?;
+        }
+
+        let mut new_store = self.clone();
+        new_store.get_one_mut(server_name)?.depends_on = new_depends_on;
+        // Reject dependencies that would make it impossible to start the server
+        supervisor::get_start_order(&new_store, &[server_name])?;
+        new_store.flush()
+    }
+
+    // Permanently record a new start time, start the server, and record how it exits
+    pub fn start_server(
+        &self,
+        server_name: &str,
+        log_path: Option<&Path>,
+        history_path: &Path,
+    ) -> Result<RunRecord, ApplicationError> {
+        self.record_starts(&[server_name])?;
+        let started_at = get_now_secs();
+        let status = self.get_one(server_name)?.start(log_path)?;
+        let record = RunRecord::new(server_name, started_at, &status);
+        run_history::record(history_path, record.clone())?;
+        Ok(record)
+    }
+
+    // Permanently record a new start time for each server and run them all concurrently
+    // The servers in the open list are opened in the browser once they are ready
+    pub fn start_servers(
+        &self,
+        server_names: &[&str],
+        logs_dir: Option<&Path>,
+        history_path: &Path,
+        restart: Option<RestartPolicy>,
+        open: &[&str],
+    ) -> Result<(), ApplicationError> {
+        self.record_starts(server_names)?;
+        let servers = server_names
+            .iter()
+            .map(|server_name| self.get_one(server_name))
+            .collect::<Result<Vec<_>, _>>()?;
+        supervisor::run_all(self, &servers, logs_dir, history_path, restart, open)
+    }
+
+    // Permanently record a new start time for each server and start them in the background
+    // Returns the PID of each server's process
+    pub fn start_servers_detached(
+        &self,
+        server_names: &[&str],
+        runtime_state: &RuntimeState,
+        logs_dir: Option<&Path>,
+    ) -> Result<Vec<u32>, ApplicationError> {
+        if let Some(running) = server_names
+            .iter()
+            .find(|server_name| runtime_state.get(server_name).is_some())
+        {
+            return Err(ApplicationError::ServerAlreadyRunning(running.to_string()));
+        }
+
+        self.record_starts(server_names)?;
+        let mut processes = Vec::new();
+        for server_name in server_names {
+            let log_path = logs_dir.map(|logs_dir| logging::get_log_path(logs_dir, server_name));
+            let pid = self
+                .get_one(server_name)?
+                .start_detached(log_path.as_deref())?;
+            processes.push((*server_name, pid));
+        }
+        runtime_state.record(&processes)?;
+        Ok(processes.into_iter().map(|(_, pid)| pid).collect())
+    }
+
+    // Stop the server's background process and forget about it
+    pub fn stop_server(
+        &self,
+        server_name: &str,
+        runtime_state: &RuntimeState,
+    ) -> Result<StopPhase, ApplicationError> {
+        let server = self.get_one(server_name)?;
+        let process = runtime_state
+            .get(&server.name)
+            .ok_or_else(|| ApplicationError::ServerNotRunning(server.name.clone()))?;
+        let phase = process::terminate(process.pid, server.get_grace_period())?;
+        info!("Stopped {} (PID {}): {}", server.name, process.pid, phase);
+        // Record the signal that stopped the server, unless the process that started it already
+        // saw it exit
+        let signal = match phase {
+            StopPhase::Terminated => libc::SIGTERM,
+            StopPhase::Killed => libc::SIGKILL,
+        };
+        runtime_state.record_exit(
+            &server.name,
+            process.pid,
+            Some(&ExitStatus::from_raw(signal)),
+        )?;
+        Ok(phase)
+    }
+
+    // Permanently record a new start time for each of the specified servers
+    fn record_starts(&self, server_names: &[&str]) -> Result<(), ApplicationError> {
+        let mut new_store = self.clone();
+        for server_name in server_names {
+            new_store.get_one_mut(server_name)?.record_run();
+        }
+        new_store.flush()
+    }
+
+    // Permanently record how long the server took to be ready after starting
+    // The servers are reloaded first because the server usually becomes ready long after this store
+    // was loaded, and the store might have changed since then
+    pub fn record_startup_time(
+        &self,
+        server_name: &str,
+        elapsed: Duration,
+    ) -> Result<(), ApplicationError> {
+        let mut servers = self.store.load()?;
+        let server = servers
+            .iter_mut()
+            .find(|server| server.name == server_name)
+            .ok_or_else(|| ApplicationError::NonExistentServer(server_name.to_string()))?;
+        server.record_startup_time(elapsed);
+        self.store.save(servers)
     }
 
     // Permanently remove the server from the store
     pub fn remove_server(&self, server_name: &str) -> Result<(), ApplicationError> {
+        self.remove_servers(&[server_name])
+    }
+
+    // Permanently remove several servers from the store
+    pub fn remove_servers(&self, server_names: &[&str]) -> Result<(), ApplicationError> {
         let mut new_store = self.clone();
-        new_store.servers.remove(server_name);
+        for server_name in server_names {
+            new_store.servers.remove(*server_name);
+        }
+        // Forget about dependencies on the removed servers
+        for server in new_store.servers.values_mut() {
+            server
+                .depends_on
+                .retain(|dependency| !server_names.contains(&dependency.as_str()));
+        }
         new_store.flush()
     }
 
+    // Return the servers whose projects have been deleted
+    pub fn get_dead_servers(&self) -> Vec<&Server> {
+        let mut servers = self
+            .servers
+            .values()
+            .filter(|server| server.is_dead())
+            .collect::<Vec<_>>();
+        servers.sort_by_key(|server| server.name.as_str());
+        servers
+    }
+
     // Return the name of the server closest to the provided server name
     pub fn get_closest_server_name(&self, server_name: &str) -> Option<String> {
         let mut corpus = CorpusBuilder::new().finish();
@@ -192,4 +676,39 @@ impl ServerStore {
     pub fn get_all(&self) -> Vec<&Server> {
         self.servers.values().collect::<Vec<_>>()
     }
+
+    // Return the server that was started most recently, if any server has been started
+    pub fn get_last_run(&self) -> Option<&Server> {
+        self.servers
+            .values()
+            .filter(|server| server.last_run.is_some())
+            .max_by_key(|server| server.last_run)
+    }
+
+    // Return the lowest ports in the range that aren't used by any server or any other process
+    pub fn get_free_ports(&self, range: RangeInclusive<u16>, count: usize) -> Vec<u16> {
+        let reserved = self
+            .servers
+            .values()
+            .map(|server| server.port)
+            .collect::<HashSet<_>>();
+        port::find_free_ports(range, &reserved, count)
+    }
+
+    // Return all servers with the specified tag, or all servers if no tag is provided
+    pub fn get_with_tag(&self, tag: Option<&str>) -> Vec<&Server> {
+        self.servers
+            .values()
+            .filter(|server| tag.is_none_or(|tag| server.has_tag(tag)))
+            .collect::<Vec<_>>()
+    }
+
+    // Return the servers that aren't archived with the specified tag, or all of them if no tag is
+    // provided
+    pub fn get_enabled_with_tag(&self, tag: Option<&str>) -> Vec<&Server> {
+        self.get_with_tag(tag)
+            .into_iter()
+            .filter(|server| server.enabled)
+            .collect()
+    }
 }
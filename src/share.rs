@@ -0,0 +1,173 @@
+use super::error::ApplicationError;
+use super::server::Server;
+use clap::ValueEnum;
+use colored::*;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+
+// This enum represents the programs that can expose a local port through a public tunnel
+#[derive(Clone, Copy, ValueEnum)]
+pub enum TunnelProvider {
+    Cloudflared,
+    Ngrok,
+}
+
+impl TunnelProvider {
+    // Return the first provider that is installed
+    pub fn detect() -> Option<Self> {
+        [TunnelProvider::Cloudflared, TunnelProvider::Ngrok]
+            .iter()
+            .copied()
+            .find(|provider| {
+                Command::new(provider.get_executable())
+                    .arg("--version")
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .status()
+                    .is_ok()
+            })
+    }
+
+    fn get_executable(&self) -> &'static str {
+        match self {
+            TunnelProvider::Cloudflared => "cloudflared",
+            TunnelProvider::Ngrok => "ngrok",
+        }
+    }
+
+    // Build the command that opens a tunnel to the port
+    fn build_command(&self, port: u16) -> Command {
+        let mut command = Command::new(self.get_executable());
+        match self {
+            TunnelProvider::Cloudflared => {
+                command.args(["tunnel", "--url", &format!("http://localhost:{}", port)]);
+            }
+            TunnelProvider::Ngrok => {
+                command.args([
+                    "http",
+                    &port.to_string(),
+                    "--log",
+                    "stdout",
+                    "--log-format",
+                    "json",
+                ]);
+            }
+        }
+        command
+    }
+
+    // Find the public URL in a line of the tunnel's output
+    fn parse_url(&self, line: &str) -> Option<String> {
+        match self {
+            // cloudflared prints the URL inside of a banner
+            TunnelProvider::Cloudflared => line
+                .split(|char: char| char.is_whitespace() || char == '|')
+                .find(|word| word.starts_with("https://") && word.ends_with(".trycloudflare.com"))
+                .map(|url| url.to_string()),
+            // ngrok logs a JSON message with the URL once the tunnel starts
+            TunnelProvider::Ngrok => serde_json::from_str::<serde_json::Value>(line)
+                .ok()?
+                .get("url")?
+                .as_str()
+                .filter(|url| url.starts_with("https://"))
+                .map(|url| url.to_string()),
+        }
+    }
+}
+
+// Open a public tunnel to the server's port and keep it open until the provider exits
+pub fn share(server: &Server, provider: TunnelProvider) -> Result<(), ApplicationError> {
+    let executable = provider.get_executable().to_string();
+    let mut child = provider
+        .build_command(server.port)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|_| ApplicationError::RunTunnel(executable.clone()))?;
+
+    // The URL may be printed to either stream, so scan both of them
+    let (sender, receiver) = mpsc::channel();
+    spawn_url_scanner(child.stdout.take(), provider, sender.clone());
+    spawn_url_scanner(child.stderr.take(), provider, sender);
+    let url = match receiver.recv() {
+        Ok(url) => url,
+        Err(_) => {
+            stop(&mut child);
+            return Err(ApplicationError::RunTunnel(executable));
+        }
+    };
+
+    println!("Sharing {} at {}", server.name.bold(), url.bold().green());
+    if copy_to_clipboard(&url) {
+        println!("Copied the URL to the clipboard");
+    } else {
+        eprintln!(
+            "{}: couldn't copy the URL to the clipboard",
+            "Warning".bold().yellow()
+        );
+    }
+    println!("Press Ctrl+C to stop sharing");
+    child
+        .wait()
+        .map_err(|_| ApplicationError::RunTunnel(executable))?;
+    Ok(())
+}
+
+// Send the first URL found in the stream's lines, then keep draining it so the provider never blocks
+fn spawn_url_scanner(
+    stream: Option<impl Read + Send + 'static>,
+    provider: TunnelProvider,
+    sender: mpsc::Sender<String>,
+) {
+    let stream = match stream {
+        Some(stream) => stream,
+        None => return,
+    };
+    thread::spawn(move || {
+        let mut sent = false;
+        for line in BufReader::new(stream).lines().map_while(Result::ok) {
+            if !sent {
+                if let Some(url) = provider.parse_url(&line) {
+                    let _ = sender.send(url);
+                    sent = true;
+                }
+            }
+        }
+    });
+}
+
+// Tear down the tunnel
+fn stop(child: &mut Child) {
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+// Copy the text to the system clipboard, returning whether it succeeded
+fn copy_to_clipboard(text: &str) -> bool {
+    let commands: &[(&str, &[&str])] = &[
+        ("pbcopy", &[]),
+        ("wl-copy", &[]),
+        ("xclip", &["-selection", "clipboard"]),
+        ("xsel", &["--clipboard", "--input"]),
+    ];
+    commands.iter().any(|(program, args)| {
+        let mut child = match Command::new(program)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(_) => return false,
+        };
+        let written = child
+            .stdin
+            .take()
+            .is_some_and(|mut stdin| stdin.write_all(text.as_bytes()).is_ok());
+        child.wait().is_ok_and(|status| status.success()) && written
+    })
+}
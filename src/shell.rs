@@ -0,0 +1,61 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::process::Command;
+use std::sync::OnceLock;
+
+// This enum represents the shells that can run start commands
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Shell {
+    Sh,
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+    Pwsh,
+}
+
+// The shell configured for servers that don't choose their own
+static DEFAULT_SHELL: OnceLock<Shell> = OnceLock::new();
+
+// Run commands with the shell unless a server chooses its own
+pub fn set_default(shell: Shell) {
+    let _ = DEFAULT_SHELL.set(shell);
+}
+
+impl fmt::Display for Shell {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}", self.get_executable())
+    }
+}
+
+impl Shell {
+    // Return the configured default shell, falling back to sh
+    // server-room only builds on Unix-like systems, so there is no Windows default like cmd
+    pub fn get_default() -> Shell {
+        DEFAULT_SHELL.get().copied().unwrap_or(Shell::Sh)
+    }
+
+    // Return the name of the shell's executable
+    pub fn get_executable(&self) -> &'static str {
+        match self {
+            Shell::Sh => "sh",
+            Shell::Bash => "bash",
+            Shell::Zsh => "zsh",
+            Shell::Fish => "fish",
+            Shell::Powershell => "powershell",
+            Shell::Pwsh => "pwsh",
+        }
+    }
+
+    // Build a command that runs the script with the shell
+    pub fn build_command(&self, script: &str) -> Command {
+        let mut command = Command::new(self.get_executable());
+        match self {
+            Shell::Sh | Shell::Bash | Shell::Zsh | Shell::Fish => command.args(["-c", script]),
+            Shell::Powershell | Shell::Pwsh => command.args(["-NoProfile", "-Command", script]),
+        };
+        command
+    }
+}
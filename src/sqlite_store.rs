@@ -0,0 +1,96 @@
+use super::backup;
+use super::error::ApplicationError;
+use super::server::Server;
+use super::store::Store;
+use rusqlite::{params, Connection};
+use std::fs;
+use std::path::PathBuf;
+
+// This struct represents a store that is persisted in a SQLite database
+// Each server's full definition is stored as JSON, and the fields worth querying are also stored in
+// their own columns
+// Only servers live in the database: the run history and logs stay in their files next to it
+// because the daemon, proxy, and detached processes append to them without going through a store
+pub struct SqliteStore {
+    db_path: PathBuf,
+}
+
+impl SqliteStore {
+    pub fn new(db_path: PathBuf) -> Self {
+        SqliteStore { db_path }
+    }
+
+    // Open the database, creating it and its tables if necessary
+    // Connections aren't shared between threads, so each operation opens its own
+    fn connect(&self) -> Result<Connection, ApplicationError> {
+        let open_error = || ApplicationError::ParseStore(self.db_path.clone());
+        if let Some(parent_dir) = self.db_path.parent() {
+            fs::create_dir_all(parent_dir).map_err(|_| open_error())?;
+        }
+        let connection = Connection::open(&self.db_path).map_err(|_| open_error())?;
+        connection
+            .execute_batch(
+                "CREATE TABLE IF NOT EXISTS servers (
+                    name TEXT PRIMARY KEY,
+                    dir TEXT NOT NULL,
+                    port INTEGER NOT NULL,
+                    last_run INTEGER,
+                    run_count INTEGER NOT NULL,
+                    definition TEXT NOT NULL
+                );",
+            )
+            .map_err(|_| open_error())?;
+        Ok(connection)
+    }
+}
+
+impl Store for SqliteStore {
+    fn load(&self) -> Result<Vec<Server>, ApplicationError> {
+        let parse_error = || ApplicationError::ParseStore(self.db_path.clone());
+        let connection = self.connect()?;
+        let mut statement = connection
+            .prepare("SELECT definition FROM servers ORDER BY name")
+            .map_err(|_| parse_error())?;
+        let definitions = statement
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(|_| parse_error())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| parse_error())?;
+        definitions
+            .iter()
+            .map(|definition| serde_json::from_str(definition).map_err(|_| parse_error()))
+            .collect()
+    }
+
+    fn save(&self, servers: Vec<Server>) -> Result<(), ApplicationError> {
+        let write_error = || ApplicationError::WriteStore(self.db_path.clone());
+
+        // Keep a copy of the current database so that the change can be rolled back
+        backup::create_pending(&self.db_path)?;
+        let mut connection = self.connect()?;
+        // Replace every server atomically so that a failed write leaves the old servers intact
+        let transaction = connection.transaction().map_err(|_| write_error())?;
+        transaction
+            .execute("DELETE FROM servers", [])
+            .map_err(|_| write_error())?;
+        for server in servers {
+            let definition =
+                serde_json::to_string(&server).map_err(|_| ApplicationError::StringifyStore)?;
+            transaction
+                .execute(
+                    "INSERT INTO servers (name, dir, port, last_run, run_count, definition)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        server.name,
+                        server.dir.to_string_lossy(),
+                        server.port,
+                        server.last_run,
+                        server.run_count,
+                        definition
+                    ],
+                )
+                .map_err(|_| write_error())?;
+        }
+        transaction.commit().map_err(|_| write_error())
+    }
+}
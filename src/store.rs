@@ -0,0 +1,304 @@
+use super::backup;
+use super::config::Config;
+use super::error::ApplicationError;
+use super::server::Server;
+#[cfg(feature = "sqlite")]
+use super::sqlite_store::SqliteStore;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use toml_edit::{ArrayOfTables, DocumentMut, Item, Table};
+use tracing::debug;
+
+// The file extensions of store files that are SQLite databases instead of TOML files
+const SQLITE_EXTENSIONS: [&str; 3] = ["db", "sqlite", "sqlite3"];
+
+// This trait represents the storage backend that servers are persisted in
+pub trait Store: Send + Sync {
+    // Read every server from storage
+    fn load(&self) -> Result<Vec<Server>, ApplicationError>;
+
+    // Replace every server in storage
+    fn save(&self, servers: Vec<Server>) -> Result<(), ApplicationError>;
+}
+
+// Open the storage backend for the store file, which is a SQLite database if it has a database
+// extension and a TOML file otherwise
+// When the config next to the store file has a path root, server directories are stored relative to it
+pub fn open(store_path: PathBuf) -> Result<Arc<dyn Store>, ApplicationError> {
+    let config = Config::load(store_path.with_file_name("config.toml"))?;
+    let backend = open_backend(store_path)?;
+    Ok(match config.get_path_root() {
+        Some(root) => {
+            debug!("Storing server directories relative to {:?}", root);
+            Arc::new(RelativeStore { backend, root })
+        }
+        None => backend,
+    })
+}
+
+fn open_backend(store_path: PathBuf) -> Result<Arc<dyn Store>, ApplicationError> {
+    let is_sqlite = store_path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| SQLITE_EXTENSIONS.contains(&extension));
+    if !is_sqlite {
+        return Ok(Arc::new(TomlStore { store_path }));
+    }
+
+    #[cfg(feature = "sqlite")]
+    return Ok(Arc::new(SqliteStore::new(store_path)));
+    #[cfg(not(feature = "sqlite"))]
+    Err(ApplicationError::SqliteUnsupported(store_path))
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct RawServerStore {
+    pub servers: Vec<Server>,
+}
+
+// This struct represents a store that is persisted as a human-editable TOML file
+struct TomlStore {
+    store_path: PathBuf,
+}
+
+impl Store for TomlStore {
+    fn load(&self) -> Result<Vec<Server>, ApplicationError> {
+        let server_store_str =
+            fs::read_to_string(&self.store_path).unwrap_or_else(|_| "servers = []".to_string());
+        let raw_store: RawServerStore = toml::from_str(&server_store_str)
+            .map_err(|_| ApplicationError::ParseStore(self.store_path.clone()))?;
+        Ok(raw_store.servers)
+    }
+
+    fn save(&self, servers: Vec<Server>) -> Result<(), ApplicationError> {
+        let stringified = self.stringify(servers)?;
+        debug!("Writing server store {:?}", self.store_path);
+
+        // Keep a copy of the current store file so that the change can be rolled back
+        backup::create_pending(&self.store_path)?;
+
+        // Create the parent directory before attempting to write the new store file
+        let parent_dir = self
+            .store_path
+            .parent()
+            .ok_or_else(|| ApplicationError::WriteStore(self.store_path.clone()))?;
+        fs::create_dir_all(parent_dir)
+            .map_err(|_| ApplicationError::WriteStore(self.store_path.clone()))?;
+        fs::write(&self.store_path, stringified)
+            .map_err(|_| ApplicationError::WriteStore(self.store_path.clone()))?;
+        Ok(())
+    }
+}
+
+// This struct represents a store that saves server directories within the root as relative paths
+// and expands them again when loading
+struct RelativeStore {
+    backend: Arc<dyn Store>,
+    root: PathBuf,
+}
+
+impl Store for RelativeStore {
+    fn load(&self) -> Result<Vec<Server>, ApplicationError> {
+        let mut servers = self.backend.load()?;
+        for server in servers.iter_mut() {
+            if server.dir.is_relative() {
+                server.dir = self.root.join(&server.dir);
+            }
+        }
+        Ok(servers)
+    }
+
+    fn save(&self, mut servers: Vec<Server>) -> Result<(), ApplicationError> {
+        for server in servers.iter_mut() {
+            // Directories outside of the root stay absolute
+            if let Ok(relative_dir) = server.dir.strip_prefix(&self.root) {
+                server.dir = relative_dir_or_dot(relative_dir);
+            }
+        }
+        self.backend.save(servers)
+    }
+}
+
+// A directory that is the root itself is stored as "."
+fn relative_dir_or_dot(relative_dir: &Path) -> PathBuf {
+    if relative_dir.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        relative_dir.to_path_buf()
+    }
+}
+
+impl TomlStore {
+    // Serialize the servers, preserving the comments and order of the servers in the existing
+    // store file so that manual edits survive
+    fn stringify(&self, servers: Vec<Server>) -> Result<String, ApplicationError> {
+        let fresh = toml::to_string_pretty(&RawServerStore { servers })
+            .map_err(|_| ApplicationError::StringifyStore)?;
+        let mut document = match fs::read_to_string(&self.store_path)
+            .ok()
+            .and_then(|existing| existing.parse::<DocumentMut>().ok())
+        {
+            Some(document) if document["servers"].is_array_of_tables() => document,
+            _ => return Ok(fresh),
+        };
+        // Empty stores are serialized as an inline array
+        let fresh_document = fresh
+            .parse::<DocumentMut>()
+            .map_err(|_| ApplicationError::StringifyStore)?;
+        let fresh_servers = match fresh_document["servers"].as_array_of_tables() {
+            Some(fresh_servers) => fresh_servers,
+            None => return Ok(fresh),
+        };
+        let get_name = |table: &Table| table.get("name")?.as_str().map(|name| name.to_string());
+
+        // Update the existing servers in place, then append the new servers
+        let existing_servers = document["servers"]
+            .as_array_of_tables()
+            .cloned()
+            .unwrap_or_default();
+        let mut merged_servers = ArrayOfTables::new();
+        let mut existing_names = HashSet::new();
+        for mut existing in existing_servers {
+            let name = get_name(&existing);
+            let fresh = fresh_servers
+                .iter()
+                .find(|fresh| name.is_some() && get_name(fresh) == name);
+            if let Some(fresh) = fresh {
+                merge_table(&mut existing, fresh);
+                existing_names.extend(name);
+                merged_servers.push(existing);
+            }
+        }
+        for fresh in fresh_servers.iter() {
+            if get_name(fresh).is_none_or(|name| !existing_names.contains(&name)) {
+                let mut fresh = fresh.clone();
+                // Separate the new server from the one before it with a blank line
+                if !merged_servers.is_empty() {
+                    fresh.decor_mut().set_prefix("\n");
+                }
+                merged_servers.push(fresh);
+            }
+        }
+        // Tables copied from the fresh document keep their positions in that document, so renumber
+        // every table to keep each server's sub-tables directly after it
+        let mut position = 0;
+        for server in merged_servers.iter_mut() {
+            renumber_tables(server, &mut position);
+        }
+        document["servers"] = Item::ArrayOfTables(merged_servers);
+        Ok(document.to_string())
+    }
+}
+
+// Assign increasing document positions to the table and its sub-tables in order
+fn renumber_tables(table: &mut Table, position: &mut usize) {
+    table.set_position(*position);
+    *position += 1;
+    for (_, item) in table.iter_mut() {
+        match item {
+            Item::Table(table) => renumber_tables(table, position),
+            Item::ArrayOfTables(tables) => {
+                for table in tables.iter_mut() {
+                    renumber_tables(table, position);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Update the existing table to match the fresh table, keeping the existing table's comments,
+// formatting, and key order
+fn merge_table(existing: &mut Table, fresh: &Table) {
+    let stale_keys = existing
+        .iter()
+        .map(|(key, _)| key.to_string())
+        .filter(|key| !fresh.contains_key(key))
+        .collect::<Vec<_>>();
+    for key in stale_keys {
+        existing.remove(&key);
+    }
+
+    for (key, fresh_item) in fresh.iter() {
+        match (existing.get_mut(key), fresh_item) {
+            (Some(Item::Table(existing)), Item::Table(fresh)) => merge_table(existing, fresh),
+            (Some(Item::Value(existing)), Item::Value(fresh)) => {
+                // Compare the values without their surrounding whitespace and comments
+                let (mut bare_existing, mut bare_fresh) = (existing.clone(), fresh.clone());
+                bare_existing.decor_mut().clear();
+                bare_fresh.decor_mut().clear();
+                if bare_existing.to_string() != bare_fresh.to_string() {
+                    let decor = existing.decor().clone();
+                    *existing = fresh.clone();
+                    *existing.decor_mut() = decor;
+                }
+            }
+            _ => {
+                existing.insert(key, fresh_item.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_servers(servers_str: &str) -> Vec<Server> {
+        toml::from_str::<RawServerStore>(servers_str)
+            .unwrap()
+            .servers
+    }
+
+    #[test]
+    fn test_stringify_replaces_server_with_tables() {
+        let removed = "[[servers]]\nname = 'old'\ndir = '/old'\nstart_command = 'make'\nport = 2999\nfrecency = 0.0\n\n[servers.tls]\ncert = '/certs/old.pem'\nkey = '/certs/old-key.pem'\n\n";
+        let kept = "[[servers]]\n# The main app\nname = 'app'\ndir = '/app'\nstart_command = 'npm run dev'\nport = 3000\nfrecency = 1.0\n\n[servers.tls]\ncert = '/certs/app.pem'\nkey = '/certs/app-key.pem'\n";
+        let existing = format!("{}{}", removed, kept);
+        let store_path = std::env::temp_dir().join(format!(
+            "server-room-store-test-{}.toml",
+            std::process::id()
+        ));
+        fs::write(&store_path, &existing).unwrap();
+
+        let appended = "[[servers]]\nname = 'api'\ndir = '/api'\nstart_command = 'cargo run'\nport = 3001\nfrecency = 0.0\n\n[servers.tls]\ncert = '/certs/api.pem'\nkey = '/certs/api-key.pem'\n\n[servers.health_check]\ntype = 'http'\nurl = 'http://localhost:3001/health'\n";
+        // Remove the first server and append a new one, which shifts every table's position
+        let mut servers = parse_servers(kept);
+        servers.extend(parse_servers(appended));
+        let stringified = TomlStore {
+            store_path: store_path.clone(),
+        }
+        .stringify(servers.clone())
+        .unwrap();
+        fs::remove_file(&store_path).unwrap();
+
+        // The kept server's comment survives and each server's tables stay directly after it
+        assert!(stringified
+            .trim_start()
+            .starts_with("[[servers]]\n# The main app\n"));
+        let headers = stringified
+            .lines()
+            .filter(|line| line.starts_with('['))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            headers,
+            vec![
+                "[[servers]]",
+                "[servers.tls]",
+                "[[servers]]",
+                "[servers.tls]",
+                "[servers.health_check]",
+            ]
+        );
+        assert_eq!(
+            toml::to_string(&RawServerStore {
+                servers: parse_servers(&stringified)
+            })
+            .unwrap(),
+            toml::to_string(&RawServerStore { servers }).unwrap()
+        );
+    }
+}
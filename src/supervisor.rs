@@ -0,0 +1,426 @@
+use super::browser;
+use super::crash;
+use super::error::ApplicationError;
+use super::health;
+use super::logging;
+use super::notification;
+use super::process::ForegroundGroup;
+use super::run_history::{self, RunRecord};
+use super::server::{get_now_secs, RestartPolicy, Server};
+use super::server_store::ServerStore;
+use colored::*;
+use std::collections::{HashSet, VecDeque};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::process::{self, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+// The delay before the first restart, which doubles after each consecutive restart
+pub const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+pub const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+// Servers that stay up at least this long are considered healthy and have their backoff reset
+pub const HEALTHY_UPTIME: Duration = Duration::from_secs(60);
+
+// The colors used to distinguish each server's output, assigned in order
+const PREFIX_COLORS: [Color; 6] = [
+    Color::Cyan,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Red,
+];
+
+// Messages sent from the child process readers to the shared writer
+enum Event {
+    Line { index: usize, line: Vec<u8> },
+    Exited { index: usize, status: ExitStatus, record: RunRecord },
+    Restarting { index: usize, restarts: u32, delay: Duration },
+    Ready { index: usize, elapsed: Duration },
+    RestartFailed { index: usize, error: String },
+}
+
+// This struct represents a server being run by the supervisor
+struct Child {
+    server: Server,
+    prefix: ColoredString,
+    log: Option<File>,
+    // The last lines of output from the current run, which are shown if the server crashes
+    output: VecDeque<String>,
+    // The URL to open in the browser the first time the server is ready
+    open_url: Option<String>,
+}
+
+// Return the names of the servers and all of their dependencies, ordered so that every server
+// comes after the servers that it depends on
+pub fn get_start_order(
+    server_store: &ServerStore,
+    server_names: &[&str],
+) -> Result<Vec<String>, ApplicationError> {
+    let mut order = vec![];
+    let mut visited = HashSet::new();
+    for server_name in server_names {
+        visit_dependencies(
+            server_store,
+            server_name,
+            &mut vec![],
+            &mut visited,
+            &mut order,
+        )?;
+    }
+    Ok(order)
+}
+
+// Add the server to the start order after its dependencies using a depth-first search
+// The path contains the servers currently being visited, so reaching one of them again means that
+// the dependencies form a cycle
+fn visit_dependencies(
+    server_store: &ServerStore,
+    server_name: &str,
+    path: &mut Vec<String>,
+    visited: &mut HashSet<String>,
+    order: &mut Vec<String>,
+) -> Result<(), ApplicationError> {
+    if let Some(index) = path.iter().position(|name| name == server_name) {
+        let mut cycle = path[index..].to_vec();
+        cycle.push(server_name.to_string());
+        return Err(ApplicationError::DependencyCycle(cycle));
+    }
+    if visited.contains(server_name) {
+        return Ok(());
+    }
+
+    let server = server_store.get_one(server_name)?;
+    path.push(server.name.clone());
+    for dependency in &server.depends_on {
+        visit_dependencies(server_store, dependency, path, visited, order)?;
+    }
+    path.pop();
+
+    visited.insert(server.name.clone());
+    order.push(server.name.clone());
+    Ok(())
+}
+
+// Run several servers concurrently, interleaving their output with a colored prefix per server
+// The time that each server takes to be ready is recorded in the store
+// If a logs directory is provided, each server's output is also captured in its log file
+// If a restart policy is provided, it overrides each server's own restart policy
+// The servers in the open list are opened in the browser once they are ready
+pub fn run_all(
+    server_store: &ServerStore,
+    servers: &[&Server],
+    logs_dir: Option<&Path>,
+    history_path: &Path,
+    restart: Option<RestartPolicy>,
+    open: &[&str],
+) -> Result<(), ApplicationError> {
+    let prefix_width = servers
+        .iter()
+        .map(|server| server.name.len())
+        .max()
+        .unwrap_or(0);
+    let (sender, receiver) = mpsc::channel();
+
+    let mut children = Vec::new();
+    let started = (|| -> Result<(), ApplicationError> {
+        for (index, server) in servers.iter().enumerate() {
+            let log = match logs_dir {
+                Some(logs_dir) => {
+                    let log_path = logging::get_log_path(logs_dir, &server.name);
+                    let mut log = logging::open_log(&log_path)?;
+                    logging::write_header(&mut log, &server.start_command)
+                        .map_err(|_| ApplicationError::WriteLog(log_path.clone()))?;
+                    Some(log)
+                }
+                None => None,
+            };
+            let color = PREFIX_COLORS[index % PREFIX_COLORS.len()];
+            children.push(Child {
+                server: (*server).clone(),
+                prefix: format!("[{:width$}]", server.name, width = prefix_width).color(color),
+                log,
+                output: VecDeque::with_capacity(crash::TAIL_LINES),
+                open_url: if open.contains(&server.name.as_str()) {
+                    Some(server.get_url())
+                } else {
+                    None
+                },
+            });
+            supervise(
+                server,
+                restart.unwrap_or(server.restart),
+                history_path,
+                index,
+                sender.clone(),
+            )?;
+        }
+        Ok(())
+    })();
+    if let Err(err) = started {
+        // Stop the servers that already started instead of leaving them running unsupervised
+        drop(sender);
+        ForegroundGroup::stop_all();
+        for _ in receiver {}
+        return Err(err);
+    }
+
+    // Drop the original sender so that the loop below ends once every child has exited
+    drop(sender);
+
+    let mut stdout = io::stdout();
+    for event in receiver {
+        match event {
+            Event::Line { index, line } => {
+                let child = &mut children[index];
+                let _ = write!(stdout, "{} ", child.prefix);
+                let _ = stdout.write_all(&line);
+                if !line.ends_with(b"\n") {
+                    let _ = writeln!(stdout);
+                }
+                if let Some(log) = child.log.as_mut() {
+                    let _ = logging::write_line(log, &line);
+                }
+                if child.output.len() == crash::TAIL_LINES {
+                    child.output.pop_front();
+                }
+                child
+                    .output
+                    .push_back(String::from_utf8_lossy(&line).trim_end().to_string());
+            }
+            Event::Exited {
+                index,
+                status,
+                record,
+            } => {
+                let child = &mut children[index];
+                let _ = writeln!(
+                    stdout,
+                    "{} {} exited with {}",
+                    child.prefix,
+                    child.server.name.bold(),
+                    status
+                );
+                // Servers stopped with Ctrl-C exit on purpose, even if they report a failure
+                if record.is_crash() && !ForegroundGroup::is_shutting_down() {
+                    notification::crashed(&record);
+                    let output = child.output.drain(..).collect::<Vec<_>>();
+                    let summary = crash::get_summary(&child.server, &record, &output);
+                    for line in summary.lines() {
+                        let _ = writeln!(stdout, "{} {}", child.prefix, line);
+                    }
+                }
+                child.output.clear();
+            }
+            Event::Restarting {
+                index,
+                restarts,
+                delay,
+            } => {
+                let child = &children[index];
+                let _ = writeln!(
+                    stdout,
+                    "{} Restarting {} in {}s (restart #{})",
+                    child.prefix,
+                    child.server.name.bold(),
+                    delay.as_secs(),
+                    restarts
+                );
+                notification::restarting(&child.server.name, restarts, delay);
+            }
+            Event::RestartFailed { index, error } => {
+                let child = &children[index];
+                let _ = writeln!(
+                    stdout,
+                    "{} {}: couldn't restart {}: {}",
+                    child.prefix,
+                    "Error".bold().red(),
+                    child.server.name.bold(),
+                    error
+                );
+            }
+            Event::Ready { index, elapsed } => {
+                let child = &mut children[index];
+                let _ = writeln!(
+                    stdout,
+                    "{} {} ready in {}",
+                    child.prefix,
+                    child.server.name.bold(),
+                    health::format_elapsed(elapsed).green()
+                );
+                notification::ready(&child.server.name, elapsed);
+                let _ = server_store.record_startup_time(&child.server.name, elapsed);
+                if let Some(url) = child.open_url.take() {
+                    if let Err(err) = browser::open(&url) {
+                        let _ = writeln!(
+                            stdout,
+                            "{} {}: {}",
+                            child.prefix,
+                            "Warning".bold().yellow(),
+                            err
+                        );
+                    }
+                }
+            }
+        }
+        let _ = stdout.flush();
+    }
+
+    Ok(())
+}
+
+// Start a server and keep restarting it according to its restart policy, forwarding its output
+// and exit status to the writer
+fn supervise(
+    server: &Server,
+    restart: RestartPolicy,
+    history_path: &Path,
+    index: usize,
+    sender: Sender<Event>,
+) -> Result<(), ApplicationError> {
+    let server = server.clone();
+    let history_path = history_path.to_path_buf();
+
+    // Spawn the first child synchronously so that failures to start are reported immediately
+    let (mut child, mut group) = spawn(&server)?;
+    thread::spawn(move || {
+        let mut restarts = 0;
+        let mut backoff = INITIAL_BACKOFF;
+        loop {
+            let started_at = Instant::now();
+            let started_at_secs = get_now_secs();
+            let exited = watch_readiness(&server, index, &sender);
+            let status = wait_forwarding(child, group, index, &sender);
+            exited.store(true, Ordering::Relaxed);
+            let status = match status {
+                Some(status) => status,
+                None => break,
+            };
+            let record = RunRecord::new(&server.name, started_at_secs, &status);
+            if let Err(err) = run_history::record(&history_path, record.clone()) {
+                warn!("Couldn't record how {} exited: {}", server.name, err);
+            }
+            let _ = sender.send(Event::Exited {
+                index,
+                status,
+                record,
+            });
+            // Servers that were stopped with Ctrl-C shouldn't be restarted
+            if !restart.should_restart(&status) || ForegroundGroup::is_shutting_down() {
+                break;
+            }
+
+            if started_at.elapsed() >= HEALTHY_UPTIME {
+                backoff = INITIAL_BACKOFF;
+            }
+            restarts += 1;
+            let _ = sender.send(Event::Restarting {
+                index,
+                restarts,
+                delay: backoff,
+            });
+            thread::sleep(backoff);
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+            if ForegroundGroup::is_shutting_down() {
+                break;
+            }
+
+            (child, group) = match spawn(&server) {
+                Ok(spawned) => spawned,
+                Err(err) => {
+                    let _ = sender.send(Event::RestartFailed {
+                        index,
+                        error: err.to_string(),
+                    });
+                    break;
+                }
+            };
+        }
+    });
+    Ok(())
+}
+
+// Report when the server becomes ready, giving up once the returned flag is set because the
+// server exited
+fn watch_readiness(server: &Server, index: usize, sender: &Sender<Event>) -> Arc<AtomicBool> {
+    let exited = Arc::new(AtomicBool::new(false));
+    let server = server.clone();
+    let sender = sender.clone();
+    let exited_clone = exited.clone();
+    thread::spawn(move || {
+        if let Some(elapsed) = health::wait_until_ready(&server, None, &exited_clone) {
+            let _ = sender.send(Event::Ready { index, elapsed });
+        }
+    });
+    exited
+}
+
+// Start a server in its own process group with its output piped back to the supervisor
+// The servers share the terminal with the supervisor, which forwards Ctrl-C to each of them
+fn spawn(server: &Server) -> Result<(process::Child, ForegroundGroup), ApplicationError> {
+    ForegroundGroup::spawn(
+        server
+            .build_command()?
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped()),
+        false,
+        server.get_grace_period(),
+    )
+    .map_err(|_| ApplicationError::RunScript(server.start_command.clone()))
+}
+
+// Forward the child's output to the writer until it exits and return its exit status
+fn wait_forwarding(
+    mut child: process::Child,
+    group: ForegroundGroup,
+    index: usize,
+    sender: &Sender<Event>,
+) -> Option<ExitStatus> {
+    let readers = vec![
+        child
+            .stdout
+            .take()
+            .map(|stdout| forward_lines(stdout, index, sender.clone())),
+        child
+            .stderr
+            .take()
+            .map(|stderr| forward_lines(stderr, index, sender.clone())),
+    ];
+    let status = group.wait(&mut child).ok();
+    // Stop leftover processes in the group before waiting for the output pipes that they hold open
+    drop(group);
+    for reader in readers.into_iter().flatten() {
+        let _ = reader.join();
+    }
+    status
+}
+
+// Send each line from the reader to the writer
+fn forward_lines<R: Read + Send + 'static>(
+    reader: R,
+    index: usize,
+    sender: Sender<Event>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(reader);
+        loop {
+            let mut line = Vec::new();
+            match reader.read_until(b'\n', &mut line) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    if sender.send(Event::Line { index, line }).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    })
+}
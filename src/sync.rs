@@ -0,0 +1,241 @@
+use super::error::ApplicationError;
+use super::server::Server;
+use super::server_store::ServerStore;
+use super::store::RawServerStore;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+// The remote that servers are synced with, which is separate from the remotes of a repo that the
+// store happens to live in
+const REMOTE_NAME: &str = "server-room";
+
+// The fields that track usage on this machine, which are never merged or considered conflicts
+const LOCAL_FIELDS: [&str; 4] = ["frecency", "last_run", "run_count", "startup_time"];
+
+// This enum represents what syncing did
+pub enum SyncOutcome {
+    // The store was committed but there is no remote to sync with
+    Committed,
+    // The remote already had every change
+    UpToDate,
+    // Changes were pulled from and/or pushed to the remote
+    Synced { pulled: usize },
+}
+
+// Commit the store file into a git repo in its directory, then merge the servers from the remote
+// and push the result
+// Servers changed on both sides are reported as conflicts instead of being merged textually
+pub fn sync(store_path: &Path, remote: Option<&str>) -> Result<SyncOutcome, ApplicationError> {
+    let dir = store_path
+        .parent()
+        .ok_or_else(|| ApplicationError::WriteStore(store_path.to_path_buf()))?;
+    let store_file = store_path
+        .file_name()
+        .and_then(|file_name| file_name.to_str())
+        .filter(|file_name| file_name.ends_with(".toml"))
+        .ok_or_else(|| ApplicationError::SyncUnsupported(store_path.to_path_buf()))?;
+
+    if !dir.join(".git").exists() {
+        git(dir, &["init", "--quiet"])?;
+        // Only the store file is shared because everything else next to it is machine-specific
+        fs::write(
+            dir.join(".gitignore"),
+            format!("*\n!.gitignore\n!{}\n", store_file),
+        )
+        .map_err(|_| ApplicationError::WriteStore(dir.join(".gitignore")))?;
+    }
+    commit(dir, store_file, "Update servers")?;
+
+    let remote = match remote {
+        Some(remote) => remote,
+        None => return Ok(SyncOutcome::Committed),
+    };
+    if git(dir, &["remote", "get-url", REMOTE_NAME]).is_ok() {
+        git(dir, &["remote", "set-url", REMOTE_NAME, remote])?;
+    } else {
+        git(dir, &["remote", "add", REMOTE_NAME, remote])?;
+    }
+    let branch = git(dir, &["rev-parse", "--abbrev-ref", "HEAD"])?;
+    let remote_branch = format!("{}/{}", REMOTE_NAME, branch);
+    git(dir, &["fetch", "--quiet", REMOTE_NAME])?;
+
+    let mut pulled = 0;
+    if git(dir, &["rev-parse", "--verify", "--quiet", &remote_branch]).is_ok() {
+        let is_ancestor = |ancestor: &str, descendant: &str| {
+            git(dir, &["merge-base", "--is-ancestor", ancestor, descendant]).is_ok()
+        };
+        if is_ancestor(&remote_branch, "HEAD") {
+            if git(dir, &["rev-parse", "HEAD"])? == git(dir, &["rev-parse", &remote_branch])? {
+                return Ok(SyncOutcome::UpToDate);
+            }
+        } else if is_ancestor("HEAD", &remote_branch) {
+            pulled = count_changes(dir, store_file, Some("HEAD"), &remote_branch)?;
+            git(dir, &["merge", "--quiet", "--ff-only", &remote_branch])?;
+        } else {
+            // Repos created separately on each machine have no common history
+            let base_commit = git(dir, &["merge-base", "HEAD", &remote_branch]).ok();
+            pulled = count_changes(dir, store_file, base_commit.as_deref(), &remote_branch)?;
+            merge(
+                dir,
+                store_path,
+                store_file,
+                base_commit.as_deref(),
+                &remote_branch,
+            )?;
+        }
+    }
+    git(dir, &["push", "--quiet", "-u", REMOTE_NAME, &branch])?;
+    Ok(SyncOutcome::Synced { pulled })
+}
+
+// Commit the store file if it changed
+// Only the store file is staged and committed because the directory may be a repo that also
+// contains the user's own files
+fn commit(dir: &Path, store_file: &str, message: &str) -> Result<(), ApplicationError> {
+    git(dir, &["add", "--", store_file])?;
+    if !git(dir, &["status", "--porcelain", "--", store_file])?.is_empty() {
+        git(dir, &["commit", "--quiet", "-m", message, "--", store_file])?;
+    }
+    Ok(())
+}
+
+// Merge the remote's servers into the local ones server by server
+fn merge(
+    dir: &Path,
+    store_path: &Path,
+    store_file: &str,
+    base_commit: Option<&str>,
+    remote_branch: &str,
+) -> Result<(), ApplicationError> {
+    let base = read_servers(dir, base_commit, store_file)?;
+    let ours = read_servers(dir, Some("HEAD"), store_file)?;
+    let theirs = read_servers(dir, Some(remote_branch), store_file)?;
+
+    let names = ours
+        .keys()
+        .chain(theirs.keys())
+        .cloned()
+        .collect::<BTreeSet<_>>();
+    let mut merged = vec![];
+    let mut conflicts = vec![];
+    for name in names {
+        let (base, ours, theirs) = (base.get(&name), ours.get(&name), theirs.get(&name));
+        let server = if is_same(ours, theirs) || is_same(base, theirs) {
+            ours.cloned()
+        } else if is_same(base, ours) {
+            theirs.map(|theirs| with_local_fields(theirs, ours))
+        } else {
+            conflicts.push(name);
+            continue;
+        };
+        merged.extend(server);
+    }
+    if !conflicts.is_empty() {
+        return Err(ApplicationError::SyncConflict(conflicts));
+    }
+
+    // Record the remote as merged while writing the merged servers in place of either side
+    git(
+        dir,
+        &[
+            "merge",
+            "--quiet",
+            "-s",
+            "ours",
+            "--no-commit",
+            "--allow-unrelated-histories",
+            remote_branch,
+        ],
+    )?;
+    ServerStore::load(store_path.to_path_buf())?.set_servers(merged)?;
+    git(dir, &["add", store_file])?;
+    git(
+        dir,
+        &["commit", "--quiet", "-m", "Merge servers from remote"],
+    )?;
+    Ok(())
+}
+
+// Copy this machine's usage statistics onto a server that changed remotely
+fn with_local_fields(server: &Server, local_server: Option<&Server>) -> Server {
+    let mut server = server.clone();
+    if let Some(local_server) = local_server {
+        server.frecency = local_server.frecency;
+        server.last_run = local_server.last_run;
+        server.run_count = local_server.run_count;
+        server.startup_time = local_server.startup_time;
+    }
+    server
+}
+
+// Determine whether two versions of a server are the same, ignoring their usage statistics
+fn is_same(server1: Option<&Server>, server2: Option<&Server>) -> bool {
+    let shared_fields = |server: Option<&Server>| {
+        let mut value = serde_json::to_value(server?).ok()?;
+        if let Value::Object(fields) = &mut value {
+            for field in LOCAL_FIELDS {
+                fields.remove(field);
+            }
+        }
+        Some(value)
+    };
+    shared_fields(server1) == shared_fields(server2)
+}
+
+// Count the servers that differ between two commits
+fn count_changes(
+    dir: &Path,
+    store_file: &str,
+    from: Option<&str>,
+    to: &str,
+) -> Result<usize, ApplicationError> {
+    let from = read_servers(dir, from, store_file)?;
+    let to = read_servers(dir, Some(to), store_file)?;
+    Ok(from
+        .keys()
+        .chain(to.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter(|name| !is_same(from.get(*name), to.get(*name)))
+        .count())
+}
+
+// Read the servers in the store file at a commit, which has no servers if it doesn't exist
+fn read_servers(
+    dir: &Path,
+    commit: Option<&str>,
+    store_file: &str,
+) -> Result<HashMap<String, Server>, ApplicationError> {
+    let content = commit
+        .and_then(|commit| git(dir, &["show", &format!("{}:{}", commit, store_file)]).ok())
+        .unwrap_or_default();
+    let content = if content.is_empty() {
+        "servers = []".to_string()
+    } else {
+        content
+    };
+    let raw_store = toml::from_str::<RawServerStore>(&content)
+        .map_err(|_| ApplicationError::ParseStore(dir.join(store_file)))?;
+    Ok(raw_store
+        .servers
+        .into_iter()
+        .map(|server| (server.name.clone(), server))
+        .collect())
+}
+
+// Run a git command in the directory and return its trimmed output
+fn git(dir: &Path, args: &[&str]) -> Result<String, ApplicationError> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|_| ApplicationError::RunGit(args.join(" ")))?;
+    if !output.status.success() {
+        return Err(ApplicationError::RunGit(args.join(" ")));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
@@ -0,0 +1,159 @@
+use super::error::ApplicationError;
+use super::server::Server;
+use rustls::crypto::ring;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::ResolvesServerCertUsingSni;
+use rustls::sign::CertifiedKey;
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// How long a read waits for data on the socket before checking for buffered data again
+const READ_INTERVAL: Duration = Duration::from_millis(50);
+
+// This struct represents the certificate and private key files for a server's domain
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct TlsCert {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+// Mint a locally-trusted certificate for the server's domain with mkcert
+pub fn mint_cert(
+    server: &Server,
+    domain_suffix: &str,
+    certs_dir: &Path,
+) -> Result<TlsCert, ApplicationError> {
+    fs::create_dir_all(certs_dir).map_err(|_| ApplicationError::RunMkcert(server.name.clone()))?;
+    let tls_cert = TlsCert {
+        cert: certs_dir.join(format!("{}.pem", server.name)),
+        key: certs_dir.join(format!("{}-key.pem", server.name)),
+    };
+    let status = Command::new("mkcert")
+        .arg("-cert-file")
+        .arg(&tls_cert.cert)
+        .arg("-key-file")
+        .arg(&tls_cert.key)
+        .arg(server.get_domain(domain_suffix))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    match status {
+        Ok(status) if status.success() => Ok(tls_cert),
+        _ => Err(ApplicationError::RunMkcert(server.name.clone())),
+    }
+}
+
+// Build the TLS config that presents each server's certificate for its domain
+pub fn build_server_config(
+    servers: &[&Server],
+    domain_suffix: &str,
+) -> Result<Arc<ServerConfig>, ApplicationError> {
+    let provider = Arc::new(ring::default_provider());
+    let mut resolver = ResolvesServerCertUsingSni::new();
+    for server in servers {
+        let tls_cert = match &server.tls {
+            Some(tls_cert) => tls_cert,
+            None => continue,
+        };
+        let load_error = || ApplicationError::LoadCert(tls_cert.cert.clone());
+        let certs = CertificateDer::pem_file_iter(&tls_cert.cert)
+            .map_err(|_| load_error())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| load_error())?;
+        let key = PrivateKeyDer::from_pem_file(&tls_cert.key).map_err(|_| load_error())?;
+        let signing_key = provider
+            .key_provider
+            .load_private_key(key)
+            .map_err(|_| load_error())?;
+        resolver
+            .add(
+                &server.get_domain(domain_suffix),
+                CertifiedKey::new(certs, signing_key),
+            )
+            .map_err(|_| load_error())?;
+    }
+
+    let mut config = ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|_| ApplicationError::ConfigureTls)?
+        .with_no_client_auth()
+        .with_cert_resolver(Arc::new(resolver));
+    // The proxy forwards requests as-is, so only HTTP/1.1 can be negotiated
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(Arc::new(config))
+}
+
+// This struct represents a TLS connection that can be read from and written to on separate threads
+#[derive(Clone)]
+pub struct TlsStream {
+    stream: Arc<Mutex<StreamOwned<ServerConnection, TcpStream>>>,
+    // A handle to the underlying socket for waiting for data without locking the stream
+    socket: Arc<TcpStream>,
+}
+
+impl TlsStream {
+    // Wrap an accepted TCP connection in a TLS connection
+    pub fn accept(config: Arc<ServerConfig>, socket: TcpStream) -> io::Result<Self> {
+        // Reads time out periodically so that they don't block writes from other threads forever
+        socket.set_read_timeout(Some(READ_INTERVAL))?;
+        let connection = ServerConnection::new(config).map_err(io::Error::other)?;
+        Ok(TlsStream {
+            socket: Arc::new(socket.try_clone()?),
+            stream: Arc::new(Mutex::new(StreamOwned::new(connection, socket))),
+        })
+    }
+
+    // Tell the client that nothing more will be written
+    pub fn close_write(&self) {
+        let mut stream = self.stream.lock().unwrap();
+        stream.conn.send_close_notify();
+        let _ = stream.flush();
+    }
+}
+
+impl Read for TlsStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            // Return data that was already decrypted
+            match self.stream.lock().unwrap().conn.reader().read(buf) {
+                Err(err) if err.kind() == ErrorKind::WouldBlock => {}
+                result => return result,
+            }
+
+            // Wait for more data without holding the lock so that other threads can write
+            match self.socket.peek(&mut [0]) {
+                Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                    continue
+                }
+                Err(err) => return Err(err),
+                // The stream reports the end of the connection
+                Ok(_) => {}
+            }
+            match self.stream.lock().unwrap().read(buf) {
+                Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                    continue
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Write for TlsStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.lock().unwrap().flush()
+    }
+}
@@ -0,0 +1,209 @@
+use super::daemon::DaemonClient;
+use super::error::ApplicationError;
+use super::logging;
+use super::process::{RunningProcess, RuntimeState};
+use super::resources::{self, ResourceSampler};
+use super::server::{format_duration, get_now_secs};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Paragraph, Row, Table};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+// How often to measure the running servers
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+// How long to wait for a key press before checking whether it is time to refresh
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+const HELP: &str = "c sort by CPU  m sort by memory  n sort by name  q quit";
+
+// This struct contains the files that the monitor reads from
+pub struct TopPaths {
+    pub runtime_state_path: PathBuf,
+    pub logs_dir: PathBuf,
+    pub socket_path: PathBuf,
+}
+
+// This enum represents the column that the servers are sorted by
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SortKey {
+    Name,
+    Cpu,
+    Memory,
+}
+
+// This struct represents a running server and its latest measurements
+struct ServerRow {
+    process: RunningProcess,
+    restarts: u32,
+    cpu_percent: Option<f64>,
+    memory_bytes: Option<u64>,
+    last_log_line: String,
+}
+
+// This struct represents the state of the monitor
+struct Top {
+    paths: TopPaths,
+    sampler: ResourceSampler,
+    rows: Vec<ServerRow>,
+    sort_key: SortKey,
+}
+
+// Display the running servers' resource usage until the user quits
+pub fn run(paths: TopPaths) -> Result<(), ApplicationError> {
+    let mut top = Top {
+        paths,
+        sampler: ResourceSampler::default(),
+        rows: vec![],
+        sort_key: SortKey::Name,
+    };
+    top.refresh()?;
+    let mut terminal = ratatui::try_init().map_err(|_| ApplicationError::DrawDashboard)?;
+    let result = top.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl Top {
+    // Handle key presses and periodically refresh until the user quits
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<(), ApplicationError> {
+        let mut last_refresh = Instant::now();
+        loop {
+            terminal
+                .draw(|frame| self.draw(frame))
+                .map_err(|_| ApplicationError::DrawDashboard)?;
+
+            if event::poll(POLL_INTERVAL).map_err(|_| ApplicationError::DrawDashboard)? {
+                if let Event::Key(key) =
+                    event::read().map_err(|_| ApplicationError::DrawDashboard)?
+                {
+                    if key.kind != KeyEventKind::Press {
+                        continue;
+                    }
+                    match key.code {
+                        KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                        KeyCode::Char('c') => self.sort_key = SortKey::Cpu,
+                        KeyCode::Char('m') => self.sort_key = SortKey::Memory,
+                        KeyCode::Char('n') => self.sort_key = SortKey::Name,
+                        _ => {}
+                    }
+                    self.sort();
+                }
+            }
+
+            if last_refresh.elapsed() >= REFRESH_INTERVAL {
+                self.refresh()?;
+                last_refresh = Instant::now();
+            }
+        }
+    }
+
+    // Reload the running servers and measure them again
+    fn refresh(&mut self) -> Result<(), ApplicationError> {
+        let runtime_state = RuntimeState::load(self.paths.runtime_state_path.clone())?;
+        // Only the daemon knows how many times its servers have been restarted
+        let restarts = DaemonClient::connect(&self.paths.socket_path)
+            .and_then(|mut client| client.status().ok())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|process| (process.server, process.restarts))
+            .collect::<HashMap<_, _>>();
+        self.sampler.sample();
+
+        self.rows = runtime_state
+            .get_all()
+            .into_iter()
+            .map(|process| {
+                let usage = self.sampler.get(process.pid);
+                let log_path = logging::get_log_path(&self.paths.logs_dir, &process.server);
+                ServerRow {
+                    process: process.clone(),
+                    restarts: restarts.get(&process.server).copied().unwrap_or(0),
+                    cpu_percent: usage.and_then(|usage| usage.cpu_percent),
+                    memory_bytes: usage.map(|usage| usage.memory_bytes),
+                    last_log_line: logging::read_tail(&log_path, 1)
+                        .unwrap_or_default()
+                        .pop()
+                        .unwrap_or_default(),
+                }
+            })
+            .collect();
+        self.sort();
+        Ok(())
+    }
+
+    // Order the servers by the selected column, with the heaviest servers first
+    fn sort(&mut self) {
+        match self.sort_key {
+            SortKey::Name => self
+                .rows
+                .sort_by(|row1, row2| row1.process.server.cmp(&row2.process.server)),
+            SortKey::Cpu => self.rows.sort_by(|row1, row2| {
+                row2.cpu_percent
+                    .unwrap_or(0.0)
+                    .total_cmp(&row1.cpu_percent.unwrap_or(0.0))
+            }),
+            SortKey::Memory => self
+                .rows
+                .sort_by_key(|row| std::cmp::Reverse(row.memory_bytes.unwrap_or(0))),
+        }
+    }
+
+    // Render the monitor
+    fn draw(&mut self, frame: &mut Frame) {
+        let [table_area, footer_area] =
+            Layout::vertical([Constraint::Min(3), Constraint::Length(1)]).areas(frame.area());
+
+        let now = get_now_secs();
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| {
+                let cpu = match row.cpu_percent {
+                    Some(cpu_percent) => format!("{:.1}%", cpu_percent),
+                    None => "-".to_string(),
+                };
+                let memory = match row.memory_bytes {
+                    Some(memory_bytes) => resources::format_bytes(memory_bytes),
+                    None => "-".to_string(),
+                };
+                Row::new(vec![
+                    Line::from(row.process.server.clone()).bold(),
+                    Line::from(row.process.pid.to_string()),
+                    Line::from(cpu).green(),
+                    Line::from(memory).yellow(),
+                    Line::from(format_duration(now.saturating_sub(row.process.started_at))),
+                    Line::from(row.restarts.to_string()),
+                    Line::from(row.last_log_line.clone()).dark_gray(),
+                ])
+            })
+            .collect::<Vec<_>>();
+        let title = format!(" {} running ", self.rows.len());
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Percentage(15),
+                Constraint::Length(8),
+                Constraint::Length(7),
+                Constraint::Length(10),
+                Constraint::Length(11),
+                Constraint::Length(8),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(
+            Row::new(vec![
+                "Server", "PID", "CPU", "Memory", "Uptime", "Restarts", "Last log",
+            ])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .block(Block::bordered().title(title));
+        frame.render_widget(table, table_area);
+        frame.render_widget(Paragraph::new(Line::from(HELP).dark_gray()), footer_area);
+    }
+}
@@ -0,0 +1,299 @@
+use super::error::ApplicationError;
+use super::logging;
+use super::process::{self, RuntimeState};
+use super::prompt;
+use super::server::Server;
+use super::server_store::ServerStore;
+use super::supervisor;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+// How often to reload the servers, their running state, and their logs from disk
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+// How long to wait for a key press before redrawing
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// The number of lines to read from the selected server's log file
+const LOG_LINES: usize = 200;
+
+const HELP: &str = "↑↓ select  s start  x stop  l logs  e edit  q quit";
+
+// This struct contains the files that the dashboard reads from and writes to
+pub struct DashboardPaths {
+    pub store_path: PathBuf,
+    pub runtime_state_path: PathBuf,
+    pub logs_dir: PathBuf,
+}
+
+// This struct represents the state of the dashboard
+struct Dashboard {
+    paths: DashboardPaths,
+    server_store: ServerStore,
+    runtime_state: RuntimeState,
+    table_state: TableState,
+    show_logs: bool,
+    log_lines: Vec<String>,
+    // The result of the last action, which replaces the help text until the next action
+    message: Option<String>,
+}
+
+// Display the dashboard until the user quits
+pub fn run(paths: DashboardPaths) -> Result<(), ApplicationError> {
+    let mut dashboard = Dashboard::load(paths)?;
+    let mut terminal = ratatui::try_init().map_err(|_| ApplicationError::DrawDashboard)?;
+    let result = dashboard.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl Dashboard {
+    // Load the initial state of the dashboard
+    fn load(paths: DashboardPaths) -> Result<Self, ApplicationError> {
+        let server_store = ServerStore::load(paths.store_path.clone())?;
+        let runtime_state = RuntimeState::load(paths.runtime_state_path.clone())?;
+        Ok(Dashboard {
+            paths,
+            server_store,
+            runtime_state,
+            table_state: TableState::default().with_selected(Some(0)),
+            show_logs: false,
+            log_lines: vec![],
+            message: None,
+        })
+    }
+
+    // Handle key presses and periodically refresh until the user quits
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<(), ApplicationError> {
+        let mut last_refresh = Instant::now();
+        loop {
+            terminal
+                .draw(|frame| self.draw(frame))
+                .map_err(|_| ApplicationError::DrawDashboard)?;
+
+            if event::poll(POLL_INTERVAL).map_err(|_| ApplicationError::DrawDashboard)? {
+                if let Event::Key(key) =
+                    event::read().map_err(|_| ApplicationError::DrawDashboard)?
+                {
+                    if key.kind != KeyEventKind::Press {
+                        continue;
+                    }
+                    match key.code {
+                        KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                        KeyCode::Up | KeyCode::Char('k') => self.table_state.select_previous(),
+                        KeyCode::Down | KeyCode::Char('j') => self.table_state.select_next(),
+                        KeyCode::Char('s') => self.start_selected(),
+                        KeyCode::Char('x') => self.stop_selected(),
+                        KeyCode::Char('l') => self.show_logs = !self.show_logs,
+                        KeyCode::Char('e') => {
+                            // Leave the dashboard while the edit prompts are displayed
+                            ratatui::restore();
+                            self.edit_selected();
+                            *terminal =
+                                ratatui::try_init().map_err(|_| ApplicationError::DrawDashboard)?;
+                        }
+                        _ => {}
+                    }
+                    self.refresh()?;
+                    last_refresh = Instant::now();
+                }
+            }
+
+            if last_refresh.elapsed() >= REFRESH_INTERVAL {
+                self.refresh()?;
+                last_refresh = Instant::now();
+            }
+        }
+    }
+
+    // Reload the servers, their running state, and the selected server's logs
+    fn refresh(&mut self) -> Result<(), ApplicationError> {
+        self.server_store = ServerStore::load(self.paths.store_path.clone())?;
+        self.runtime_state = RuntimeState::load(self.paths.runtime_state_path.clone())?;
+        self.log_lines = match self.get_selected() {
+            Some(server) if self.show_logs => logging::read_tail(
+                &logging::get_log_path(&self.paths.logs_dir, &server.name),
+                LOG_LINES,
+            )
+            .unwrap_or_default(),
+            _ => vec![],
+        };
+        Ok(())
+    }
+
+    // Return the servers in the order that they are displayed
+    fn get_servers(&self) -> Vec<&Server> {
+        let mut servers = self.server_store.get_all();
+        servers.sort_by_key(|server| server.name.as_str());
+        servers
+    }
+
+    // Return the server that is currently selected
+    fn get_selected(&self) -> Option<&Server> {
+        let servers = self.get_servers();
+        let index = self
+            .table_state
+            .selected()?
+            .min(servers.len().checked_sub(1)?);
+        servers.get(index).copied()
+    }
+
+    // Start the selected server and its dependencies in the background
+    fn start_selected(&mut self) {
+        let server_name = match self.get_selected() {
+            Some(server) => server.name.clone(),
+            None => return,
+        };
+        let result = supervisor::get_start_order(&self.server_store, &[server_name.as_str()])
+            .and_then(|server_names| {
+                let server_names = server_names
+                    .iter()
+                    .map(|name| name.as_str())
+                    .filter(|name| *name == server_name || self.runtime_state.get(name).is_none())
+                    .collect::<Vec<_>>();
+                let pids = self.server_store.start_servers_detached(
+                    &server_names,
+                    &self.runtime_state,
+                    Some(&self.paths.logs_dir),
+                )?;
+                process::reap_in_background(&self.runtime_state, &server_names, &pids);
+                Ok(pids)
+            });
+        self.message = Some(match result {
+            Ok(pids) => format!(
+                "Started {} (PID {})",
+                server_name,
+                pids.last().copied().unwrap_or_default()
+            ),
+            Err(err) => format!("Error: {}", err),
+        });
+    }
+
+    // Stop the selected server's background process
+    fn stop_selected(&mut self) {
+        let server_name = match self.get_selected() {
+            Some(server) => server.name.clone(),
+            None => return,
+        };
+        self.message = Some(
+            match self
+                .server_store
+                .stop_server(&server_name, &self.runtime_state)
+            {
+                Ok(phase) => format!("Stopped {} ({})", server_name, phase),
+                Err(err) => format!("Error: {}", err),
+            },
+        );
+    }
+
+    // Let the user edit one of the selected server's fields with the edit subcommand's prompts
+    fn edit_selected(&mut self) {
+        let server_name = match self.get_selected() {
+            Some(server) => server.name.clone(),
+            None => return,
+        };
+        let result = prompt::choose_edit_field("What do you want to edit?").and_then(|field| {
+            let field = match field {
+                Some(field) => field,
+                None => return Ok(None),
+            };
+            let subcommand = format!("edit {} {}", field, server_name);
+            let run_error = || ApplicationError::RunSubcommand(subcommand.clone());
+            let exe = env::current_exe().map_err(|_| run_error())?;
+            // Edit the store that the dashboard displays, even if it isn't the default one
+            Command::new(exe)
+                .arg("--store")
+                .arg(&self.paths.store_path)
+                // Attach the name to the flag so that names starting with "-" aren't parsed as flags
+                .args(["edit", field, &format!("--server={}", server_name)])
+                .status()
+                .map(Some)
+                .map_err(|_| run_error())
+        });
+        self.message = match result {
+            Ok(Some(status)) if status.success() => Some(format!("Edited {}", server_name)),
+            Ok(_) => Some(format!("Didn't edit {}", server_name)),
+            Err(err) => Some(format!("Error: {}", err)),
+        };
+    }
+
+    // Render the dashboard
+    fn draw(&mut self, frame: &mut Frame) {
+        let log_height = if self.show_logs {
+            Constraint::Percentage(50)
+        } else {
+            Constraint::Length(0)
+        };
+        let [table_area, log_area, footer_area] =
+            Layout::vertical([Constraint::Min(3), log_height, Constraint::Length(1)])
+                .areas(frame.area());
+
+        let rows = self
+            .get_servers()
+            .into_iter()
+            .map(|server| {
+                let status = match self.runtime_state.get(&server.name) {
+                    Some(process) => Line::from(format!("running (PID {})", process.pid)).green(),
+                    None => Line::from("stopped").dark_gray(),
+                };
+                Row::new(vec![
+                    Line::from(server.name.clone()).bold(),
+                    status,
+                    Line::from(server.port.to_string()).yellow(),
+                    Line::from(format!("{:.2}", server.get_frecency_score())),
+                    Line::from(server.start_command.clone()).cyan(),
+                ])
+            })
+            .collect::<Vec<_>>();
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Percentage(20),
+                Constraint::Length(20),
+                Constraint::Length(6),
+                Constraint::Length(9),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(
+            Row::new(vec!["Server", "Status", "Port", "Frecency", "Command"])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .row_highlight_style(Style::default().bg(Color::DarkGray))
+        .block(Block::bordered().title(" server-room "));
+        frame.render_stateful_widget(table, table_area, &mut self.table_state);
+
+        if self.show_logs {
+            let title = match self.get_selected() {
+                Some(server) => format!(" {} logs ", server.name),
+                None => " logs ".to_string(),
+            };
+            // Only show as many of the most recent lines as fit inside the borders
+            let visible_lines = log_area.height.saturating_sub(2) as usize;
+            let lines = self
+                .log_lines
+                .iter()
+                .skip(self.log_lines.len().saturating_sub(visible_lines))
+                .map(|line| Line::from(line.as_str()))
+                .collect::<Vec<_>>();
+            frame.render_widget(
+                Paragraph::new(lines).block(Block::bordered().title(title)),
+                log_area,
+            );
+        }
+
+        let footer = match &self.message {
+            Some(message) => Line::from(message.as_str()).yellow(),
+            None => Line::from(HELP).dark_gray(),
+        };
+        frame.render_widget(Paragraph::new(footer), footer_area);
+    }
+}
@@ -0,0 +1,92 @@
+use super::error::ApplicationError;
+use super::server::Server;
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::path::Path;
+
+// The name of the compound configuration that launches every generated configuration
+const COMPOUND_NAME: &str = "server-room";
+
+// Generate a launch config with a configuration for each server and a compound configuration that
+// launches all of them at once
+// Configurations in the existing launch config with other names are preserved
+// The "node-terminal" type runs the start command in a terminal where VS Code attaches its Node
+// inspector to every Node process, including ones started by package managers
+pub fn generate_launch_config(servers: &[&Server], existing: Option<Value>) -> Value {
+    let mut launch_config = match existing {
+        Some(Value::Object(launch_config)) => launch_config,
+        _ => Map::new(),
+    };
+    let names = servers
+        .iter()
+        .map(|server| server.name.as_str())
+        .collect::<Vec<_>>();
+
+    let mut configurations = get_array(&launch_config, "configurations")
+        .into_iter()
+        .filter(|configuration| {
+            !configuration["name"]
+                .as_str()
+                .is_some_and(|name| names.contains(&name))
+        })
+        .collect::<Vec<_>>();
+    configurations.extend(servers.iter().map(|server| {
+        let mut env = Map::new();
+        if server.inject_port {
+            env.insert("PORT".to_string(), json!(server.port.to_string()));
+        }
+        json!({
+            "type": "node-terminal",
+            "request": "launch",
+            "name": server.name,
+            "command": server.start_command,
+            "cwd": server.get_project_dir(),
+            "env": env,
+        })
+    }));
+
+    let mut compounds = get_array(&launch_config, "compounds")
+        .into_iter()
+        .filter(|compound| compound["name"] != COMPOUND_NAME)
+        .collect::<Vec<_>>();
+    compounds.push(json!({
+        "name": COMPOUND_NAME,
+        "configurations": names,
+        "stopAll": true,
+    }));
+
+    launch_config
+        .entry("version")
+        .or_insert_with(|| json!("0.2.0"));
+    launch_config.insert("configurations".to_string(), json!(configurations));
+    launch_config.insert("compounds".to_string(), json!(compounds));
+    Value::Object(launch_config)
+}
+
+// Add the servers to the launch config file, creating it and its directory if necessary
+pub fn write_launch_config(servers: &[&Server], path: &Path) -> Result<(), ApplicationError> {
+    // launch.json files that VS Code created contain comments, which JSON can't parse
+    let existing = match fs::read_to_string(path) {
+        Ok(content) => Some(
+            serde_json::from_str(&content)
+                .map_err(|_| ApplicationError::ParseLaunchConfig(path.to_path_buf()))?,
+        ),
+        Err(_) => None,
+    };
+    let launch_config = generate_launch_config(servers, existing);
+    let write_error = || ApplicationError::WriteLaunchConfig(path.to_path_buf());
+    if let Some(parent_dir) = path.parent() {
+        fs::create_dir_all(parent_dir).map_err(|_| write_error())?;
+    }
+    let content = serde_json::to_string_pretty(&launch_config).map_err(|_| write_error())?;
+    fs::write(path, content + "\n").map_err(|_| write_error())
+}
+
+// Return a copy of the array at the key, or an empty array if it isn't an array
+fn get_array(object: &Map<String, Value>, key: &str) -> Vec<Value> {
+    object
+        .get(key)
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default()
+}
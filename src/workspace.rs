@@ -0,0 +1,242 @@
+use super::project::Project;
+use super::scan;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// The scripts that a workspace package needs to be added as a server
+const SERVER_SCRIPTS: [&str; 2] = ["dev", "start"];
+
+// Find the packages in the project's npm, yarn, or pnpm workspaces that can be run as servers
+// Each package is named after the project and its directory, like "repo/package", and uses the
+// project's package manager because workspaces share the lockfile at their root
+pub fn find_packages(project: &Project) -> Vec<Project> {
+    let mut patterns = project.get_workspace_patterns();
+    patterns.extend(read_pnpm_patterns(&project.dir));
+
+    let (excluded, included): (Vec<_>, Vec<_>) = patterns
+        .iter()
+        .partition(|pattern| pattern.starts_with('!'));
+    let excluded = excluded
+        .iter()
+        .flat_map(|pattern| expand_pattern(&project.dir, &pattern[1..]))
+        .collect::<Vec<_>>();
+    let mut dirs = included
+        .iter()
+        .flat_map(|pattern| expand_pattern(&project.dir, pattern))
+        .filter(|dir| dir != &project.dir && !excluded.contains(dir))
+        .collect::<Vec<_>>();
+    dirs.sort();
+    dirs.dedup();
+
+    dirs.into_iter()
+        .filter_map(|dir| {
+            let package_name = dir.file_name()?.to_str()?.to_string();
+            let package = Project {
+                name: format!("{}/{}", project.name, package_name),
+                dir,
+                package_manager: project.package_manager,
+                package_manager_version: project.package_manager_version.clone(),
+            };
+            let scripts = package.get_start_scripts().ok()?;
+            if scripts
+                .iter()
+                .any(|script| SERVER_SCRIPTS.contains(&script.name.as_str()))
+            {
+                Some(package)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+// Read the workspace patterns from the "packages" list in pnpm-workspace.yaml
+fn read_pnpm_patterns(dir: &Path) -> Vec<String> {
+    let contents = match fs::read_to_string(dir.join("pnpm-workspace.yaml")) {
+        Ok(contents) => contents,
+        Err(_) => return vec![],
+    };
+    let mut patterns = vec![];
+    let mut in_packages = false;
+    for line in contents.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        // Top-level keys start a new section
+        if !line.starts_with(char::is_whitespace) && !trimmed.starts_with('-') {
+            in_packages = trimmed == "packages:";
+            continue;
+        }
+        if let Some(pattern) = trimmed.strip_prefix('-').filter(|_| in_packages) {
+            let pattern = pattern.split(" #").next().unwrap_or_default().trim();
+            patterns.push(pattern.trim_matches(|c| c == '"' || c == '\'').to_string());
+        }
+    }
+    patterns
+}
+
+// Find the package directories within the root directory that match the workspace pattern
+// Patterns support "*" within a path segment and "**" to match any number of directories
+fn expand_pattern(root: &Path, pattern: &str) -> Vec<PathBuf> {
+    let segments = pattern
+        .trim_start_matches("./")
+        .split('/')
+        .filter(|segment| !segment.is_empty() && *segment != ".")
+        .collect::<Vec<_>>();
+    let mut dirs = vec![];
+    expand_segments(root.to_path_buf(), &segments, &mut dirs);
+    dirs.into_iter()
+        .filter(|dir| dir.join("package.json").is_file())
+        .collect()
+}
+
+fn expand_segments(dir: PathBuf, segments: &[&str], dirs: &mut Vec<PathBuf>) {
+    let (segment, rest) = match segments.split_first() {
+        Some(split) => split,
+        None => {
+            dirs.push(dir);
+            return;
+        }
+    };
+    if !segment.contains('*') {
+        let child = dir.join(segment);
+        if child.is_dir() {
+            expand_segments(child, rest, dirs);
+        }
+        return;
+    }
+
+    let mut children = match fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir() && !scan::is_ignored(path))
+            .collect::<Vec<_>>(),
+        Err(_) => return,
+    };
+    children.sort();
+    if *segment == "**" {
+        // "**" can also match zero directories
+        expand_segments(dir, rest, dirs);
+        for child in children {
+            expand_segments(child, segments, dirs);
+        }
+        return;
+    }
+    for child in children {
+        let matches = child
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| matches_wildcard(segment, name));
+        if matches {
+            expand_segments(child, rest, dirs);
+        }
+    }
+}
+
+// Determine whether the name matches the pattern, where "*" matches any sequence of characters
+fn matches_wildcard(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => {
+            let name = match name.strip_prefix(prefix) {
+                Some(name) => name,
+                None => return false,
+            };
+            // Try every possible length for the text that the "*" matches
+            (0..=name.len())
+                .filter(|index| name.is_char_boundary(*index))
+                .any(|index| matches_wildcard(rest, &name[index..]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Create an empty directory for a test to build a workspace in
+    fn create_test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "server-room-workspace-test-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn create_package(root: &Path, dir: &str) {
+        fs::create_dir_all(root.join(dir)).unwrap();
+        fs::write(root.join(dir).join("package.json"), "{}").unwrap();
+    }
+
+    #[test]
+    fn test_matches_wildcard() {
+        assert!(matches_wildcard("app", "app"));
+        assert!(!matches_wildcard("app", "apps"));
+        assert!(matches_wildcard("*", "anything"));
+        assert!(matches_wildcard("*", ""));
+        assert!(matches_wildcard("app-*", "app-web"));
+        assert!(!matches_wildcard("app-*", "web-app"));
+        assert!(matches_wildcard("*-app", "web-app"));
+        assert!(matches_wildcard("a*b*c", "aXbYc"));
+        assert!(!matches_wildcard("a*b*c", "aXbY"));
+        assert!(matches_wildcard("é*", "éa"));
+        assert!(matches_wildcard("*é", "aé"));
+    }
+
+    #[test]
+    fn test_expand_pattern() {
+        let root = create_test_dir("expand");
+        create_package(&root, "packages/web");
+        create_package(&root, "packages/api");
+        create_package(&root, "apps/nested/admin");
+        create_package(&root, "packages/node_modules/dependency");
+        // Directories without a package.json aren't packages
+        fs::create_dir_all(root.join("packages/docs")).unwrap();
+
+        let relative = |dirs: Vec<PathBuf>| {
+            dirs.into_iter()
+                .map(|dir| dir.strip_prefix(&root).unwrap().to_path_buf())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            relative(expand_pattern(&root, "packages/*")),
+            vec![PathBuf::from("packages/api"), PathBuf::from("packages/web")]
+        );
+        assert_eq!(
+            relative(expand_pattern(&root, "./packages/w*")),
+            vec![PathBuf::from("packages/web")]
+        );
+        assert_eq!(
+            relative(expand_pattern(&root, "apps/**")),
+            vec![PathBuf::from("apps/nested/admin")]
+        );
+        assert_eq!(
+            relative(expand_pattern(&root, "packages/api")),
+            vec![PathBuf::from("packages/api")]
+        );
+        assert!(expand_pattern(&root, "missing/*").is_empty());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_read_pnpm_patterns() {
+        let root = create_test_dir("pnpm");
+        assert!(read_pnpm_patterns(&root).is_empty());
+
+        fs::write(
+            root.join("pnpm-workspace.yaml"),
+            "# Workspace\npackages:\n  - 'packages/*'\n  - \"apps/**\" # The apps\n\n  - '!**/test/**'\ncatalog:\n  - react\n",
+        )
+        .unwrap();
+        assert_eq!(
+            read_pnpm_patterns(&root),
+            vec!["packages/*", "apps/**", "!**/test/**"]
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+}